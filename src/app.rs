@@ -0,0 +1,645 @@
+use std::path::{Path, PathBuf};
+
+use re_viewer::external::{
+    eframe, egui, re_entity_db, re_log, re_log_types, re_memory, re_types,
+    re_viewer_context::{self, SystemCommandSender as _},
+};
+
+use crate::{
+    blueprint, builtin_panels,
+    control::{self, Command, CommandResult, ControlServer},
+    export, grid_view, history, loader,
+    panel::{self, CartographerPanel, PanelContext, PanelRegistry},
+    persistence, recordings,
+    time_cursor::TimeCursor,
+    viewer_state,
+};
+
+/// Sets up a [`CartographerRerun`]: where its data comes from, extra panels and options.
+pub struct CartographerRerunBuilder {
+    recordings: recordings::Recordings,
+    receivers: Vec<re_smart_channel::Receiver<re_log_types::LogMsg>>,
+    panels: Vec<Box<dyn CartographerPanel>>,
+    memory_limit: re_memory::MemoryLimit,
+    default_blueprint: bool,
+    app_env: re_viewer::AppEnvironment,
+    control_addr: Option<String>,
+}
+
+impl Default for CartographerRerunBuilder {
+    fn default() -> Self {
+        Self {
+            recordings: Default::default(),
+            receivers: Vec::new(),
+            panels: Vec::new(),
+            memory_limit: re_memory::MemoryLimit::UNLIMITED,
+            default_blueprint: true,
+            // This is used for analytics, if the `analytics` feature is on in `Cargo.toml`
+            app_env: re_viewer::AppEnvironment::Custom("Cartographer Rerun Wrapper".to_owned()),
+            control_addr: None,
+        }
+    }
+}
+
+impl CartographerRerunBuilder {
+    /// Show the data arriving on `rx`, e.g. from [`re_sdk_comms::serve`].
+    pub fn with_receiver(mut self, rx: re_smart_channel::Receiver<re_log_types::LogMsg>) -> Self {
+        self.receivers.push(self.recordings.watch(rx));
+        self
+    }
+
+    /// Open a recording (`.rrd`) or a Cartographer state (`.pbstream`) file at startup.
+    pub fn with_file(self, path: &Path) -> Result<Self, Box<dyn std::error::Error>> {
+        Ok(self.with_receiver(loader::load_file(path)?))
+    }
+
+    /// Add a tab to the side panel, after the built-in ones.
+    pub fn with_panel(mut self, panel: impl CartographerPanel + 'static) -> Self {
+        self.panels.push(Box::new(panel));
+        self
+    }
+
+    /// When this limit is reached, the viewer drops the oldest data of all recordings.
+    /// No limit by default.
+    pub fn with_memory_limit(mut self, memory_limit: re_memory::MemoryLimit) -> Self {
+        self.memory_limit = memory_limit;
+        self
+    }
+
+    /// Make the Cartographer blueprint the default layout of each new application
+    /// (on by default), or keep Rerun's heuristic layout.
+    ///
+    /// Layouts from earlier sessions, and blueprints sent with the data, take precedence.
+    pub fn with_default_blueprint(mut self, default_blueprint: bool) -> Self {
+        self.default_blueprint = default_blueprint;
+        self
+    }
+
+    /// How the viewer identifies itself to Rerun analytics.
+    pub fn with_app_env(mut self, app_env: re_viewer::AppEnvironment) -> Self {
+        self.app_env = app_env;
+        self
+    }
+
+    /// Take commands from scripts over HTTP on `addr`, e.g. `127.0.0.1:9877`.
+    /// See [`crate::control`] for the commands.
+    pub fn with_control_server(mut self, addr: impl Into<String>) -> Self {
+        self.control_addr = Some(addr.into());
+        self
+    }
+
+    /// Create the app, for use in an [`eframe::AppCreator`].
+    pub fn build(
+        self,
+        cc: &eframe::CreationContext<'_>,
+    ) -> Result<CartographerRerun, Box<dyn std::error::Error + Send + Sync>> {
+        re_viewer::customize_eframe_and_setup_renderer(cc)?;
+
+        let startup_options = re_viewer::StartupOptions {
+            memory_limit: self.memory_limit,
+            ..Default::default()
+        };
+        let mut rerun_app = re_viewer::App::new(
+            re_viewer::build_info(),
+            &self.app_env,
+            startup_options,
+            cc.egui_ctx.clone(),
+            cc.storage,
+        );
+        if let Err(err) = rerun_app.add_space_view_class::<grid_view::OccupancyGridSpaceView>() {
+            re_log::error!("Failed to register the occupancy grid view: {err}");
+        }
+        for rx in self.receivers {
+            rerun_app.add_receiver(rx);
+        }
+
+        let control = self
+            .control_addr
+            .map(|addr| ControlServer::start(&addr, cc.egui_ctx.clone()))
+            .transpose()?;
+
+        let mut panels = builtin_panels::builtin_panels(&self.recordings, self.memory_limit);
+        panels.extend(self.panels);
+
+        let mut app = CartographerRerun {
+            rerun_app,
+            recordings: self.recordings,
+            panel: PanelState::default(),
+            panels: PanelRegistry::new(panels),
+            default_blueprint: self.default_blueprint,
+            blueprints_sent: Default::default(),
+            control,
+            pending_screenshot: None,
+        };
+        if let Some(storage) = cc.storage {
+            app.restore(persistence::load(storage));
+        }
+        Ok(app)
+    }
+
+    /// Open a native window with the viewer and run until it is closed.
+    pub fn run(self, window_title: &str) -> eframe::Result<()> {
+        let native_options = eframe::NativeOptions {
+            viewport: egui::ViewportBuilder::default().with_app_id("cartographer_rerun"),
+            ..re_viewer::native::eframe_options(None)
+        };
+        eframe::run_native(
+            window_title,
+            native_options,
+            Box::new(move |cc| Ok(Box::new(self.build(cc)?))),
+        )
+    }
+}
+
+/// The Rerun viewer with the Cartographer side panel.
+pub struct CartographerRerun {
+    rerun_app: re_viewer::App,
+
+    /// All recordings fed to the viewer, whether from the network or from files.
+    recordings: recordings::Recordings,
+
+    panel: PanelState,
+
+    /// The tabs of the side panel.
+    panels: PanelRegistry,
+
+    /// Send the Cartographer blueprint for each new application.
+    default_blueprint: bool,
+
+    /// Applications we sent the default blueprint for.
+    blueprints_sent: std::collections::HashSet<re_log_types::ApplicationId>,
+
+    control: Option<ControlServer>,
+
+    /// A screenshot asked for over the control server, saved once the image arrives.
+    pending_screenshot: Option<(PathBuf, control::Reply)>,
+}
+
+/// Settings of the Cartographer panel.
+struct PanelState {
+    /// Width of the side panel, as last resized by the user.
+    width: f32,
+
+    /// Show the data at the time cursor of the viewer.
+    follow_viewer: bool,
+
+    /// When following the viewer, show the latest data on its timeline
+    /// instead of the data at its current time.
+    pin_to_latest: bool,
+
+    /// The timeline the panel queries on when not following the viewer.
+    timeline: re_log_types::Timeline,
+
+    /// The entity selected in any of the tabs.
+    selection: Option<re_log_types::EntityPath>,
+
+    /// Components shown at the top of the entities tab, in the order they were pinned.
+    pinned_components: Vec<(re_log_types::EntityPath, re_types::ComponentName)>,
+}
+
+impl Default for PanelState {
+    fn default() -> Self {
+        Self {
+            width: 200.0,
+            follow_viewer: true,
+            pin_to_latest: false,
+            timeline: re_log_types::Timeline::log_time(),
+            selection: None,
+            pinned_components: Vec::new(),
+        }
+    }
+}
+
+impl eframe::App for CartographerRerun {
+    fn save(&mut self, storage: &mut dyn eframe::Storage) {
+        // Store viewer state on disk
+        self.rerun_app.save(storage);
+        persistence::save(storage, &self.persisted());
+    }
+
+    /// Called whenever we need repainting, which could be 60 Hz.
+    fn update(&mut self, ctx: &egui::Context, frame: &mut eframe::Frame) {
+        // Take dropped files before the Rerun Viewer sees them, so we can keep track of them:
+        let dropped_files = ctx.input_mut(|i| std::mem::take(&mut i.raw.dropped_files));
+        for file in dropped_files {
+            if let Some(path) = file.path {
+                self.open_file(&path);
+            }
+        }
+
+        self.run_control_commands(ctx);
+
+        if self.default_blueprint {
+            self.send_default_blueprints();
+        }
+
+        // First add our panel(s):
+        let response = egui::SidePanel::right("Cartographer")
+            .default_width(self.panel.width)
+            .show(ctx, |ui| {
+                self.ui(ui);
+            });
+        self.panel.width = response.response.rect.width();
+
+        // Now show the Rerun Viewer in the remaining space:
+        self.rerun_app.update(ctx, frame);
+    }
+}
+
+impl CartographerRerun {
+    pub fn builder() -> CartographerRerunBuilder {
+        CartographerRerunBuilder::default()
+    }
+
+    /// Open a recording (`.rrd`) or a Cartographer state (`.pbstream`) file,
+    /// logging an error if that fails.
+    pub fn open_file(&mut self, path: &Path) {
+        match loader::load_file(path) {
+            Ok(rx) => self.rerun_app.add_receiver(self.recordings.watch(rx)),
+            Err(err) => re_log::error!("{err}"),
+        }
+    }
+
+    /// The state of the Cartographer panel to keep for the next session.
+    fn persisted(&self) -> persistence::PersistedState {
+        persistence::PersistedState {
+            panel_width: Some(self.panel.width),
+            follow_viewer: self.panel.follow_viewer,
+            pin_to_latest: self.panel.pin_to_latest,
+            timeline: Some(self.panel.timeline.into()),
+            open_tabs: Some(self.panels.open_tabs().to_vec()),
+            active_tab: self.panels.active_tab().cloned(),
+            pinned_components: self
+                .panel
+                .pinned_components
+                .iter()
+                .map(Into::into)
+                .collect(),
+            panels: self.panels.panel_states(),
+            ..Default::default()
+        }
+    }
+
+    fn restore(&mut self, persisted: persistence::PersistedState) {
+        if let Some(width) = persisted.panel_width {
+            self.panel.width = width;
+        }
+        self.panel.follow_viewer = persisted.follow_viewer;
+        self.panel.pin_to_latest = persisted.pin_to_latest;
+        if let Some(timeline) = persisted.timeline {
+            // Checked against the timelines of the recording once there is one:
+            self.panel.timeline = timeline.into();
+        }
+        self.panel.pinned_components = persisted
+            .pinned_components
+            .into_iter()
+            .map(Into::into)
+            .collect();
+        self.panels
+            .restore(persisted.open_tabs, persisted.active_tab, persisted.panels);
+    }
+
+    /// Show the data arriving on `rx`.
+    pub fn add_receiver(&mut self, rx: re_smart_channel::Receiver<re_log_types::LogMsg>) {
+        self.rerun_app.add_receiver(self.recordings.watch(rx));
+    }
+
+    /// Make the Cartographer blueprint the default of applications we haven't seen before,
+    /// unless their data came with a blueprint.
+    ///
+    /// The viewer only uses it when there is no layout from an earlier session.
+    fn send_default_blueprints(&mut self) {
+        for info in self.recordings.list() {
+            if !self.blueprints_sent.insert(info.application_id.clone()) {
+                continue;
+            }
+            if self.recordings.has_blueprint(&info.application_id) {
+                re_log::debug!(
+                    "Not sending the default blueprint for {}, it has one",
+                    info.application_id
+                );
+                continue;
+            }
+            match blueprint::CartographerBlueprint::default().to_log_msgs(info.application_id, true)
+            {
+                Ok(msgs) => self.send_log_msgs(msgs),
+                Err(err) => re_log::error!("Failed to create the default blueprint: {err}"),
+            }
+        }
+    }
+
+    /// Add data computed by the panel to the viewer, e.g. trajectory errors.
+    fn send_log_msgs(&mut self, log_msgs: Vec<re_log_types::LogMsg>) {
+        let (tx, rx) = re_smart_channel::smart_channel(
+            re_smart_channel::SmartMessageSource::Sdk,
+            re_smart_channel::SmartChannelSource::Sdk,
+        );
+        for msg in log_msgs {
+            tx.send(msg).ok();
+        }
+        tx.quit(None).ok();
+        self.rerun_app.add_receiver(rx);
+    }
+
+    fn ui(&mut self, ui: &mut egui::Ui) {
+        ui.add_space(4.0);
+        ui.vertical_centered(|ui| {
+            ui.strong("Cartographer");
+        });
+        ui.separator();
+
+        self.recordings_ui(ui);
+        ui.separator();
+
+        let recording = self.rerun_app.recording_db().map(|entity_db| {
+            let time_cursor = time_cursor_ui(ui, entity_db, &mut self.panel);
+            (entity_db, time_cursor)
+        });
+        if recording.is_some() {
+            ui.separator();
+        }
+
+        let mut log_msgs = Vec::new();
+        let mut ctx = PanelContext {
+            rerun_app: &self.rerun_app,
+            entity_db: recording.map(|(entity_db, _)| entity_db),
+            time_cursor: recording.map(|(_, time_cursor)| time_cursor),
+            selection: &mut self.panel.selection,
+            pinned_components: &mut self.panel.pinned_components,
+            log_msgs: &mut log_msgs,
+        };
+        self.panels.ui(ui, &mut ctx);
+
+        if !log_msgs.is_empty() {
+            self.send_log_msgs(log_msgs);
+        }
+    }
+
+    /// List all recordings and let the user pick which one the viewer shows.
+    fn recordings_ui(&mut self, ui: &mut egui::Ui) {
+        ui.horizontal(|ui| {
+            ui.strong("Recordings:");
+            if ui.button("Open recording…").clicked() {
+                if let Some(paths) = rfd::FileDialog::new()
+                    .add_filter("Rerun recording", &["rrd"])
+                    .add_filter("Cartographer state", &["pbstream"])
+                    .add_filter("Rerun blueprint", &["rbl"])
+                    .pick_files()
+                {
+                    for path in paths {
+                        self.open_file(&path);
+                    }
+                }
+            }
+        });
+
+        let active = self
+            .rerun_app
+            .recording_db()
+            .map(|entity_db| entity_db.store_id().clone());
+
+        for info in self.recordings.list() {
+            let is_active = active.as_ref() == Some(&info.store_id);
+            let label = format!("{} ({})", info.application_id, info.store_source);
+            if ui
+                .selectable_label(is_active, label)
+                .on_hover_text(info.store_id.to_string())
+                .clicked()
+                && !is_active
+            {
+                self.rerun_app.command_sender.send_system(
+                    re_viewer_context::SystemCommand::ActivateRecording(info.store_id.clone()),
+                );
+            }
+        }
+    }
+}
+
+/// Pick the point in time the panel shows data for.
+fn time_cursor_ui(
+    ui: &mut egui::Ui,
+    entity_db: &re_entity_db::EntityDb,
+    panel: &mut PanelState,
+) -> TimeCursor {
+    ui.checkbox(&mut panel.follow_viewer, "Follow viewer time")
+        .on_hover_text("Show the values at the time selected in the viewer's timeline");
+
+    let viewer_cursor = if panel.follow_viewer {
+        TimeCursor::from_viewer(ui.ctx(), entity_db)
+    } else {
+        None
+    };
+
+    if let Some(mut time_cursor) = viewer_cursor {
+        ui.checkbox(&mut panel.pin_to_latest, "Pin to latest");
+        if panel.pin_to_latest {
+            time_cursor.time = None;
+        }
+
+        let playing = if time_cursor.playing {
+            " (playing)"
+        } else {
+            ""
+        };
+        ui.label(format!(
+            "{}: {}{playing}",
+            time_cursor.timeline.name(),
+            time_cursor.format_time()
+        ));
+        time_cursor
+    } else {
+        if panel.follow_viewer {
+            ui.weak("The viewer time is only known while a grid view is in the viewport");
+        }
+        timeline_ui(ui, entity_db, &mut panel.timeline);
+        TimeCursor::latest(panel.timeline)
+    }
+}
+
+/// Let the user pick one of the timelines in the log database.
+fn timeline_ui(
+    ui: &mut egui::Ui,
+    entity_db: &re_entity_db::EntityDb,
+    timeline: &mut re_log_types::Timeline,
+) {
+    // The selected timeline may be from another recording.
+    // There can be many timelines, but the `log_time` timeline is always there:
+    if !entity_db.timelines().any(|t| t == timeline) {
+        *timeline = re_log_types::Timeline::log_time();
+    }
+
+    egui::ComboBox::from_label("Timeline")
+        .selected_text(timeline.name().as_str())
+        .show_ui(ui, |ui| {
+            for available in entity_db.timelines() {
+                ui.selectable_value(timeline, *available, available.name().as_str());
+            }
+        });
+}
+
+/// Commands of the [`ControlServer`].
+impl CartographerRerun {
+    fn run_control_commands(&mut self, ctx: &egui::Context) {
+        if let Some((path, reply)) = self.pending_screenshot.take() {
+            let image = ctx.input(|i| {
+                i.raw.events.iter().find_map(|event| match event {
+                    egui::Event::Screenshot { image, .. } => Some(image.clone()),
+                    _ => None,
+                })
+            });
+            match image {
+                Some(image) => reply.send(control::save_screenshot(&path, &image)),
+                None => {
+                    self.pending_screenshot = Some((path, reply));
+                    ctx.request_repaint();
+                }
+            }
+        }
+
+        while let Some((command, reply)) = self.control.as_ref().and_then(ControlServer::try_recv) {
+            let result = match command {
+                Command::Screenshot { path } => {
+                    if self.pending_screenshot.is_some() {
+                        Err("Already taking a screenshot".to_owned())
+                    } else {
+                        // The image arrives as an event in one of the next frames:
+                        ctx.send_viewport_cmd(egui::ViewportCommand::Screenshot);
+                        ctx.request_repaint();
+                        self.pending_screenshot = Some((path, reply));
+                        continue;
+                    }
+                }
+                Command::Recordings => Ok(self.recordings_json()),
+                Command::Open { path } => loader::load_file(&path)
+                    .map(|rx| {
+                        self.add_receiver(rx);
+                        serde_json::Value::Null
+                    })
+                    .map_err(|err| err.to_string()),
+                Command::ActivateRecording { store_id } => self.activate_recording(&store_id),
+                Command::SetTime { timeline, time } => self.set_time(&timeline, time),
+                Command::Select { entity_path } => self.select(&entity_path),
+                Command::Export {
+                    entity_path,
+                    component,
+                    timeline,
+                    path,
+                } => self.export(&entity_path, &component, timeline.as_deref(), &path),
+            };
+            reply.send(result);
+        }
+    }
+
+    fn recordings_json(&self) -> serde_json::Value {
+        let active = self
+            .rerun_app
+            .recording_db()
+            .map(|entity_db| entity_db.store_id().clone());
+        self.recordings
+            .list()
+            .into_iter()
+            .map(|info| {
+                serde_json::json!({
+                    "store_id": info.store_id.to_string(),
+                    "application_id": info.application_id.to_string(),
+                    "source": info.store_source.to_string(),
+                    "active": active.as_ref() == Some(&info.store_id),
+                })
+            })
+            .collect::<Vec<_>>()
+            .into()
+    }
+
+    fn activate_recording(&self, store_id: &str) -> CommandResult {
+        let info = self
+            .recordings
+            .list()
+            .into_iter()
+            .find(|info| info.store_id.to_string() == store_id)
+            .ok_or_else(|| format!("Unknown recording {store_id:?}"))?;
+        self.rerun_app.command_sender.send_system(
+            re_viewer_context::SystemCommand::ActivateRecording(info.store_id),
+        );
+        Ok(serde_json::Value::Null)
+    }
+
+    fn entity_db(&self) -> Result<&re_entity_db::EntityDb, String> {
+        self.rerun_app
+            .recording_db()
+            .ok_or_else(|| "No recording loaded".to_owned())
+    }
+
+    fn set_time(&mut self, timeline: &str, time: Option<i64>) -> CommandResult {
+        let entity_db = self.entity_db()?;
+        let timeline = find_timeline(entity_db, timeline)?;
+
+        // Applied by the grid view in the next frame:
+        viewer_state::set_time(
+            entity_db.store_id().clone(),
+            timeline,
+            time.map(re_log_types::TimeInt::new_temporal),
+        );
+
+        // For when the panel doesn't follow the viewer:
+        self.panel.timeline = timeline;
+        Ok(serde_json::Value::Null)
+    }
+
+    fn select(&mut self, entity_path: &str) -> CommandResult {
+        let entity_path = re_log_types::EntityPath::from(entity_path);
+        if !self.entity_db()?.entity_paths().contains(&&entity_path) {
+            return Err(format!("Unknown entity {entity_path}"));
+        }
+        panel::select_in_viewer(&self.rerun_app.command_sender, &entity_path);
+        self.panel.selection = Some(entity_path);
+        Ok(serde_json::Value::Null)
+    }
+
+    fn export(
+        &self,
+        entity_path: &str,
+        component: &str,
+        timeline: Option<&str>,
+        path: &Path,
+    ) -> CommandResult {
+        let entity_db = self.entity_db()?;
+        let timeline = match timeline {
+            Some(timeline) => find_timeline(entity_db, timeline)?,
+            None => re_log_types::Timeline::log_time(),
+        };
+        let format = export::ExportFormat::ALL
+            .into_iter()
+            .find(|format| {
+                path.extension()
+                    .is_some_and(|ext| ext == format.extension())
+            })
+            .ok_or("Export to .csv, .ndjson or .parquet files")?;
+
+        let entity_path = re_log_types::EntityPath::from(entity_path);
+        let component = re_types::ComponentName::from(component);
+        let history = history::component_history(entity_db, timeline, &entity_path, component);
+        if history.is_empty() {
+            return Err(format!(
+                "No {component} logged to {entity_path} on the {} timeline",
+                timeline.name()
+            ));
+        }
+
+        let table = export::ExportTable::from_history(timeline, component, &history);
+        table
+            .write(format, path)
+            .map_err(|err| format!("Failed to export to {}: {err}", path.display()))?;
+        Ok(serde_json::json!({ "path": path, "rows": table.num_rows }))
+    }
+}
+
+fn find_timeline(
+    entity_db: &re_entity_db::EntityDb,
+    name: &str,
+) -> Result<re_log_types::Timeline, String> {
+    entity_db
+        .timelines()
+        .find(|timeline| timeline.name().as_str() == name)
+        .copied()
+        .ok_or_else(|| format!("Unknown timeline {name:?}"))
+}
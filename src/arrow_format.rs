@@ -0,0 +1,483 @@
+use std::fmt::Write as _;
+
+use re_viewer::external::{
+    arrow2::{
+        array::{
+            Array, BinaryArray, FixedSizeBinaryArray, FixedSizeListArray, ListArray,
+            PrimitiveArray, StructArray, UnionArray, Utf8Array,
+        },
+        types::f16,
+    },
+    egui,
+};
+
+/// How much of a value to show before truncating it.
+#[derive(Clone, Copy, Debug)]
+pub struct FormatOptions {
+    /// Maximum number of elements shown of a list.
+    pub max_list_items: usize,
+
+    /// Maximum number of characters shown of a string.
+    pub max_string_chars: usize,
+
+    /// Maximum number of bytes shown of a binary blob.
+    pub max_binary_bytes: usize,
+}
+
+impl FormatOptions {
+    /// Fits on a single line.
+    pub const COMPACT: Self = Self {
+        max_list_items: 8,
+        max_string_chars: 64,
+        max_binary_bytes: 16,
+    };
+}
+
+impl Default for FormatOptions {
+    fn default() -> Self {
+        Self::COMPACT
+    }
+}
+
+/// A value formatted as a string.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Formatted {
+    pub text: String,
+
+    /// Was anything left out of [`Self::text`]?
+    pub truncated: bool,
+}
+
+/// Format the value at `index`, recursing into structs, unions and lists.
+///
+/// Structs are shown as `{x: 1, y: 2}`, lists as `[1, 2, 3]`
+/// and unions as `variant(value)`.
+/// Anything longer than allowed by `options` is cut short with `…`.
+pub fn format_value(array: &dyn Array, index: usize, options: &FormatOptions) -> Formatted {
+    let mut formatter = Formatter {
+        options,
+        text: String::new(),
+        truncated: false,
+    };
+    formatter.value(array, index);
+    Formatted {
+        text: formatter.text,
+        truncated: formatter.truncated,
+    }
+}
+
+struct Formatter<'a> {
+    options: &'a FormatOptions,
+    text: String,
+    truncated: bool,
+}
+
+impl Formatter<'_> {
+    fn value(&mut self, array: &dyn Array, index: usize) {
+        if array.is_null(index) {
+            self.text.push_str("null");
+            return;
+        }
+
+        let any = array.as_any();
+        if let Some(array) = any.downcast_ref::<StructArray>() {
+            self.text.push('{');
+            for (i, (field, values)) in array.fields().iter().zip(array.values()).enumerate() {
+                if 0 < i {
+                    self.text.push_str(", ");
+                }
+                self.text.push_str(&field.name);
+                self.text.push_str(": ");
+                self.value(&**values, index);
+            }
+            self.text.push('}');
+        } else if let Some(array) = any.downcast_ref::<UnionArray>() {
+            let (field, slot) = array.index(index);
+            let fields = UnionArray::get_fields(array.data_type());
+            if let Some(field) = fields.get(field) {
+                self.text.push_str(&field.name);
+            }
+            self.text.push('(');
+            self.value(&*array.fields()[field], slot);
+            self.text.push(')');
+        } else if let Some(array) = any.downcast_ref::<ListArray<i32>>() {
+            self.list(&*array.value(index));
+        } else if let Some(array) = any.downcast_ref::<ListArray<i64>>() {
+            self.list(&*array.value(index));
+        } else if let Some(array) = any.downcast_ref::<FixedSizeListArray>() {
+            self.list(&*array.value(index));
+        } else if let Some(array) = any.downcast_ref::<Utf8Array<i32>>() {
+            self.string(array.value(index));
+        } else if let Some(array) = any.downcast_ref::<Utf8Array<i64>>() {
+            self.string(array.value(index));
+        } else if let Some(array) = any.downcast_ref::<BinaryArray<i32>>() {
+            self.binary(array.value(index));
+        } else if let Some(array) = any.downcast_ref::<BinaryArray<i64>>() {
+            self.binary(array.value(index));
+        } else if let Some(array) = any.downcast_ref::<FixedSizeBinaryArray>() {
+            self.binary(array.value(index));
+        } else if let Some(array) = any.downcast_ref::<PrimitiveArray<f16>>() {
+            // Not supported by `get_display`:
+            write!(self.text, "{}", array.value(index).to_f32()).ok();
+        } else {
+            // Primitives, booleans, timestamps, …
+            let display = re_viewer::external::arrow2::array::get_display(array, "null");
+            if display(&mut self.text, index).is_err() {
+                self.text.push('?');
+            }
+        }
+    }
+
+    fn list(&mut self, values: &dyn Array) {
+        let num_shown = values.len().min(self.options.max_list_items);
+        self.text.push('[');
+        for i in 0..num_shown {
+            if 0 < i {
+                self.text.push_str(", ");
+            }
+            self.value(values, i);
+        }
+        if num_shown < values.len() {
+            self.truncated = true;
+            write!(self.text, ", … {} more", values.len() - num_shown).ok();
+        }
+        self.text.push(']');
+    }
+
+    fn string(&mut self, string: &str) {
+        self.text.push('"');
+        match string.char_indices().nth(self.options.max_string_chars) {
+            Some((end, _)) => {
+                self.truncated = true;
+                self.text.push_str(&string[..end]);
+                self.text.push('…');
+            }
+            None => self.text.push_str(string),
+        }
+        self.text.push('"');
+    }
+
+    fn binary(&mut self, bytes: &[u8]) {
+        let num_shown = bytes.len().min(self.options.max_binary_bytes);
+        write!(self.text, "{} bytes: ", bytes.len()).ok();
+        for byte in &bytes[..num_shown] {
+            write!(self.text, "{byte:02x}").ok();
+        }
+        if num_shown < bytes.len() {
+            self.truncated = true;
+            self.text.push('…');
+        }
+    }
+}
+
+/// Maximum number of children shown when expanding a list.
+const MAX_EXPANDED_ITEMS: usize = 1000;
+
+/// Show the value at `index`.
+///
+/// Values that don't fit on a line can be expanded into a tree of their parts.
+pub fn value_ui(ui: &mut egui::Ui, array: &dyn Array, index: usize) {
+    let formatted = format_value(array, index, &FormatOptions::COMPACT);
+    if !formatted.truncated {
+        ui.label(formatted.text);
+        return;
+    }
+
+    egui::CollapsingHeader::new(formatted.text)
+        .id_source(index)
+        .show(ui, |ui| {
+            expanded_ui(ui, array, index);
+        });
+}
+
+fn expanded_ui(ui: &mut egui::Ui, array: &dyn Array, index: usize) {
+    let any = array.as_any();
+    if let Some(array) = any.downcast_ref::<StructArray>() {
+        for (field, values) in array.fields().iter().zip(array.values()) {
+            ui.push_id(&field.name, |ui| {
+                ui.horizontal(|ui| {
+                    ui.strong(format!("{}:", field.name));
+                    value_ui(ui, &**values, index);
+                });
+            });
+        }
+    } else if let Some(array) = any.downcast_ref::<UnionArray>() {
+        let (field, slot) = array.index(index);
+        value_ui(ui, &*array.fields()[field], slot);
+    } else if let Some(array) = any.downcast_ref::<ListArray<i32>>() {
+        list_ui(ui, &*array.value(index));
+    } else if let Some(array) = any.downcast_ref::<ListArray<i64>>() {
+        list_ui(ui, &*array.value(index));
+    } else if let Some(array) = any.downcast_ref::<FixedSizeListArray>() {
+        list_ui(ui, &*array.value(index));
+    } else {
+        // Long strings and blobs: show more of them, in a scrollable box.
+        let options = FormatOptions {
+            max_list_items: MAX_EXPANDED_ITEMS,
+            max_string_chars: 64 * 1024,
+            max_binary_bytes: 4 * 1024,
+        };
+        let formatted = format_value(array, index, &options);
+        egui::ScrollArea::vertical()
+            .max_height(200.0)
+            .show(ui, |ui| {
+                ui.add(egui::Label::new(egui::RichText::new(formatted.text).monospace()).wrap());
+            });
+    }
+}
+
+fn list_ui(ui: &mut egui::Ui, values: &dyn Array) {
+    let num_shown = values.len().min(MAX_EXPANDED_ITEMS);
+    for i in 0..num_shown {
+        ui.push_id(i, |ui| {
+            ui.horizontal(|ui| {
+                ui.weak(format!("{i}:"));
+                value_ui(ui, values, i);
+            });
+        });
+    }
+    if num_shown < values.len() {
+        ui.weak(format!("… {} more", values.len() - num_shown));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Arc;
+
+    use re_viewer::external::arrow2::{
+        array::{BooleanArray, Int32Array},
+        bitmap::Bitmap,
+        buffer::Buffer,
+        datatypes::{DataType, Field, UnionMode},
+        offset::Offsets,
+    };
+
+    use super::*;
+
+    fn format(array: &dyn Array, index: usize) -> String {
+        let formatted = format_value(array, index, &FormatOptions::COMPACT);
+        assert!(!formatted.truncated, "{} was truncated", formatted.text);
+        formatted.text
+    }
+
+    #[test]
+    fn primitives() {
+        let array = Int32Array::from(vec![Some(-7), None]);
+        assert_eq!(format(&array, 0), "-7");
+        assert_eq!(format(&array, 1), "null");
+
+        let array = PrimitiveArray::<f64>::from_slice([1.5]);
+        assert_eq!(format(&array, 0), "1.5");
+    }
+
+    #[test]
+    fn booleans() {
+        let array = BooleanArray::from(vec![Some(true), Some(false), None]);
+        assert_eq!(format(&array, 0), "true");
+        assert_eq!(format(&array, 1), "false");
+        assert_eq!(format(&array, 2), "null");
+    }
+
+    #[test]
+    fn unsigned() {
+        let array = PrimitiveArray::<u8>::from_slice([u8::MAX]);
+        assert_eq!(format(&array, 0), "255");
+        let array = PrimitiveArray::<u16>::from_slice([u16::MAX]);
+        assert_eq!(format(&array, 0), "65535");
+        let array = PrimitiveArray::<u32>::from_slice([u32::MAX]);
+        assert_eq!(format(&array, 0), "4294967295");
+        let array = PrimitiveArray::<u64>::from_slice([u64::MAX]);
+        assert_eq!(format(&array, 0), "18446744073709551615");
+    }
+
+    #[test]
+    fn half_floats() {
+        let array = PrimitiveArray::<f16>::from(vec![Some(f16::from_f32(-2.5)), None]);
+        assert_eq!(format(&array, 0), "-2.5");
+        assert_eq!(format(&array, 1), "null");
+    }
+
+    #[test]
+    fn strings() {
+        let array = Utf8Array::<i32>::from(vec![Some("map"), None]);
+        assert_eq!(format(&array, 0), "\"map\"");
+        assert_eq!(format(&array, 1), "null");
+
+        let array = Utf8Array::<i64>::from_slice(["large"]);
+        assert_eq!(format(&array, 0), "\"large\"");
+    }
+
+    #[test]
+    fn binary() {
+        let array = BinaryArray::<i32>::from_slice([[0x0a_u8, 0xff]]);
+        assert_eq!(format(&array, 0), "2 bytes: 0aff");
+
+        let array = BinaryArray::<i64>::from_slice([[0x01_u8]]);
+        assert_eq!(format(&array, 0), "1 bytes: 01");
+    }
+
+    #[test]
+    fn fixed_size_binary() {
+        // E.g. a tuid or an `Rgba32` packed as bytes:
+        let array = FixedSizeBinaryArray::new(
+            DataType::FixedSizeBinary(3),
+            Buffer::from(vec![0x01_u8, 0x02, 0x03, 0xaa, 0xbb, 0xcc]),
+            Some(Bitmap::from([true, false])),
+        );
+        assert_eq!(format(&array, 0), "3 bytes: 010203");
+        assert_eq!(format(&array, 1), "null");
+    }
+
+    #[test]
+    fn structs() {
+        let fields = vec![
+            Field::new("x", DataType::Int32, false),
+            Field::new("name", DataType::Utf8, true),
+        ];
+        let array = StructArray::new(
+            DataType::Struct(Arc::new(fields)),
+            vec![
+                Int32Array::from_slice([1, 2]).boxed(),
+                Utf8Array::<i32>::from(vec![Some("a"), None]).boxed(),
+            ],
+            Some(Bitmap::from([true, false])),
+        );
+        assert_eq!(format(&array, 0), "{x: 1, name: \"a\"}");
+        assert_eq!(format(&array, 1), "null");
+    }
+
+    fn union_fields() -> Vec<Field> {
+        vec![
+            Field::new("int", DataType::Int32, false),
+            Field::new("text", DataType::Utf8, false),
+        ]
+    }
+
+    #[test]
+    fn sparse_union() {
+        let array = UnionArray::new(
+            DataType::Union(Arc::new(union_fields()), None, UnionMode::Sparse),
+            Buffer::from(vec![0_i8, 1]),
+            vec![
+                Int32Array::from_slice([3, 0]).boxed(),
+                Utf8Array::<i32>::from_slice(["", "b"]).boxed(),
+            ],
+            None,
+        );
+        assert_eq!(format(&array, 0), "int(3)");
+        assert_eq!(format(&array, 1), "text(\"b\")");
+    }
+
+    #[test]
+    fn dense_union() {
+        let array = UnionArray::new(
+            DataType::Union(Arc::new(union_fields()), None, UnionMode::Dense),
+            Buffer::from(vec![1_i8, 0, 1]),
+            vec![
+                Int32Array::from_slice([5]).boxed(),
+                Utf8Array::<i32>::from_slice(["a", "b"]).boxed(),
+            ],
+            Some(Buffer::from(vec![0_i32, 0, 1])),
+        );
+        assert_eq!(format(&array, 0), "text(\"a\")");
+        assert_eq!(format(&array, 1), "int(5)");
+        assert_eq!(format(&array, 2), "text(\"b\")");
+    }
+
+    #[test]
+    fn lists() {
+        let array = ListArray::<i32>::new(
+            ListArray::<i32>::default_datatype(DataType::Int32),
+            Offsets::try_from(vec![0, 2, 2, 3]).unwrap().into(),
+            Int32Array::from(vec![Some(1), None, Some(3)]).boxed(),
+            Some(Bitmap::from([true, true, false])),
+        );
+        assert_eq!(format(&array, 0), "[1, null]");
+        assert_eq!(format(&array, 1), "[]");
+        assert_eq!(format(&array, 2), "null");
+    }
+
+    #[test]
+    fn large_lists() {
+        let array = ListArray::<i64>::new(
+            ListArray::<i64>::default_datatype(DataType::Int32),
+            Offsets::try_from(vec![0_i64, 1, 3]).unwrap().into(),
+            Int32Array::from_slice([1, 2, 3]).boxed(),
+            None,
+        );
+        assert_eq!(format(&array, 0), "[1]");
+        assert_eq!(format(&array, 1), "[2, 3]");
+    }
+
+    fn fixed_size_list(size: usize, values: Vec<f32>) -> FixedSizeListArray {
+        FixedSizeListArray::new(
+            DataType::FixedSizeList(Arc::new(Field::new("item", DataType::Float32, false)), size),
+            PrimitiveArray::<f32>::from_vec(values).boxed(),
+            None,
+        )
+    }
+
+    #[test]
+    fn position_3d() {
+        let array = fixed_size_list(3, vec![0.5, -1.5, 2.5, 3.5, 4.5, 5.5]);
+        assert_eq!(format(&array, 0), "[0.5, -1.5, 2.5]");
+        assert_eq!(format(&array, 1), "[3.5, 4.5, 5.5]");
+    }
+
+    #[test]
+    fn quaternion() {
+        let array = fixed_size_list(4, vec![0.25, 0.5, 0.75, 1.5]);
+        assert_eq!(format(&array, 0), "[0.25, 0.5, 0.75, 1.5]");
+    }
+
+    #[test]
+    fn line_strip_3d() {
+        // A list of points per strip:
+        let points = fixed_size_list(3, vec![0.0, 0.5, 1.0, 2.0, 2.5, 3.0, 4.0, 4.5, 5.0]);
+        let array = ListArray::<i32>::new(
+            ListArray::<i32>::default_datatype(points.data_type().clone()),
+            Offsets::try_from(vec![0, 2, 3]).unwrap().into(),
+            points.boxed(),
+            None,
+        );
+        assert_eq!(format(&array, 0), "[[0, 0.5, 1], [2, 2.5, 3]]");
+        assert_eq!(format(&array, 1), "[[4, 4.5, 5]]");
+    }
+
+    #[test]
+    fn truncation() {
+        let options = FormatOptions {
+            max_list_items: 2,
+            max_string_chars: 2,
+            max_binary_bytes: 1,
+        };
+
+        let array = ListArray::<i32>::new(
+            ListArray::<i32>::default_datatype(DataType::Int32),
+            Offsets::try_from(vec![0, 5]).unwrap().into(),
+            Int32Array::from_slice([1, 2, 3, 4, 5]).boxed(),
+            None,
+        );
+        let formatted = format_value(&array, 0, &options);
+        assert_eq!(formatted.text, "[1, 2, … 3 more]");
+        assert!(formatted.truncated);
+
+        // Cut at characters, not bytes:
+        let array = Utf8Array::<i32>::from_slice(["héllo"]);
+        let formatted = format_value(&array, 0, &options);
+        assert_eq!(formatted.text, "\"hé…\"");
+        assert!(formatted.truncated);
+
+        let array = BinaryArray::<i32>::from_slice([[1_u8, 2]]);
+        let formatted = format_value(&array, 0, &options);
+        assert_eq!(formatted.text, "2 bytes: 01…");
+        assert!(formatted.truncated);
+
+        // Exactly at the limit is not truncated:
+        let array = Utf8Array::<i32>::from_slice(["ab"]);
+        let formatted = format_value(&array, 0, &options);
+        assert_eq!(formatted.text, "\"ab\"");
+        assert!(!formatted.truncated);
+    }
+}
@@ -0,0 +1,463 @@
+//! Viewer layouts (blueprints) for Cartographer data.
+//!
+//! A blueprint is a recording of its own, with the layout logged as blueprint archetypes.
+//! Sending it to the viewer makes it the active layout of the application.
+//! Blueprints are saved as `.rbl` files, which can be opened like any recording.
+//!
+//! All Cartographer layouts of an application go to the same blueprint store (see [`store_id`]),
+//! and later changes only log what changed, e.g. the contents of the map views.
+
+use std::path::Path;
+
+use re_types_blueprint::blueprint::{archetypes as viewport, components as viewport_components};
+use re_viewer::external::{
+    egui, re_log,
+    re_log_types::{self, BlueprintActivationCommand, LogMsg},
+    re_types::{
+        blueprint::{archetypes, components},
+        datatypes,
+    },
+};
+
+use crate::submaps;
+
+/// Stable ids, so that views keep their state when the blueprint is sent again.
+const ROOT_CONTAINER_ID: u128 = 0xca27_0000_0000_0000_0000_0000_0000_0001;
+const MAP_VIEW_ID: u128 = 0xca27_0000_0000_0000_0000_0000_0000_0002;
+const MAP_2D_VIEW_ID: u128 = 0xca27_0000_0000_0000_0000_0000_0000_0003;
+const TIME_SERIES_VIEW_ID: u128 = 0xca27_0000_0000_0000_0000_0000_0000_0004;
+const TEXT_LOG_VIEW_ID: u128 = 0xca27_0000_0000_0000_0000_0000_0000_0005;
+const MAPS_CONTAINER_ID: u128 = 0xca27_0000_0000_0000_0000_0000_0000_0006;
+const PLOTS_CONTAINER_ID: u128 = 0xca27_0000_0000_0000_0000_0000_0000_0007;
+const GRID_VIEW_ID: u128 = 0xca27_0000_0000_0000_0000_0000_0000_0008;
+
+fn uuid(id: u128) -> datatypes::Uuid {
+    datatypes::Uuid {
+        bytes: id.to_be_bytes(),
+    }
+}
+
+fn space_view_path(id: u128) -> String {
+    format!("space_view/{}", uuid::Uuid::from_u128(id))
+}
+
+fn container_path(id: u128) -> String {
+    format!("container/{}", uuid::Uuid::from_u128(id))
+}
+
+/// The blueprint store the Cartographer layout of the application is logged to.
+pub fn store_id(app_id: &re_log_types::ApplicationId) -> re_log_types::StoreId {
+    re_log_types::StoreId::from_string(re_log_types::StoreKind::Blueprint, recording_id(app_id))
+}
+
+fn recording_id(app_id: &re_log_types::ApplicationId) -> String {
+    format!("cartographer_layout_{app_id}")
+}
+
+/// A view of the layout: whether it is shown, its id, class, name and contents.
+type View = (bool, u128, &'static str, &'static str, Vec<String>);
+
+/// Views whose contents depend on [`CartographerBlueprint::submaps`].
+const MAP_VIEW_IDS: [u128; 3] = [MAP_VIEW_ID, MAP_2D_VIEW_ID, GRID_VIEW_ID];
+
+/// Which views the layout has.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Views {
+    /// Submap grids and trajectories from above.
+    pub map_2d: bool,
+
+    /// Submap probabilities, see [`crate::grid_view`].
+    pub grid: bool,
+
+    /// Everything in 3D, e.g. point clouds, submaps, trajectories and constraints.
+    pub map_3d: bool,
+
+    /// Scalars, e.g. IMU and odometry.
+    pub time_series: bool,
+
+    /// Text log messages.
+    pub text_log: bool,
+}
+
+impl Default for Views {
+    fn default() -> Self {
+        Self {
+            map_2d: true,
+            grid: true,
+            map_3d: true,
+            time_series: true,
+            text_log: true,
+        }
+    }
+}
+
+/// The layout we show Cartographer data in.
+#[derive(Clone, Debug, Default)]
+pub struct CartographerBlueprint {
+    pub views: Views,
+    pub submaps: submaps::SubmapVisibility,
+}
+
+/// A stream to the blueprint store of the application.
+///
+/// Blueprints are logged on the `blueprint` timeline, where the latest write wins.
+/// We use the wall clock there, so that our writes come after earlier ones and the
+/// viewer's own edits (which add one to the latest time) come after ours.
+fn blueprint_stream(
+    app_id: &re_log_types::ApplicationId,
+) -> Result<(re_sdk::RecordingStream, re_sdk::sink::MemorySinkStorage), Box<dyn std::error::Error>>
+{
+    let (rec, storage) = re_sdk::RecordingStreamBuilder::new(app_id.clone())
+        .recording_id(recording_id(app_id))
+        .blueprint()
+        .memory()?;
+    let now = std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .unwrap_or_default();
+    rec.set_time_sequence("blueprint", now.as_nanos() as i64);
+    Ok((rec, storage))
+}
+
+/// The messages of the stream, followed by making the blueprint active or the default.
+fn activated(
+    rec: &re_sdk::RecordingStream,
+    storage: &re_sdk::sink::MemorySinkStorage,
+    app_id: &re_log_types::ApplicationId,
+    make_default: bool,
+) -> Vec<LogMsg> {
+    rec.flush_blocking();
+    let mut msgs = storage.take();
+    msgs.push(LogMsg::BlueprintActivationCommand(
+        BlueprintActivationCommand {
+            blueprint_id: store_id(app_id),
+            make_active: !make_default,
+            make_default,
+        },
+    ));
+    msgs
+}
+
+impl CartographerBlueprint {
+    fn views(&self) -> [View; 5] {
+        let map_contents = |include: &[&str]| {
+            let mut contents: Vec<String> = include.iter().map(|&rule| rule.to_owned()).collect();
+            contents.extend(self.submaps.query_expressions());
+            contents
+        };
+        [
+            (
+                self.views.map_3d,
+                MAP_VIEW_ID,
+                "3D",
+                "Map",
+                map_contents(&["+ /**"]),
+            ),
+            (
+                self.views.map_2d,
+                MAP_2D_VIEW_ID,
+                "2D",
+                "Map 2D",
+                map_contents(&["+ /submaps/**", "+ /trajectories/**"]),
+            ),
+            (
+                self.views.grid,
+                GRID_VIEW_ID,
+                "OccupancyGrid",
+                "Submap grids",
+                map_contents(&["+ /submaps/**"]),
+            ),
+            (
+                self.views.time_series,
+                TIME_SERIES_VIEW_ID,
+                "TimeSeries",
+                "Time series",
+                vec!["+ /**".to_owned()],
+            ),
+            (
+                self.views.text_log,
+                TEXT_LOG_VIEW_ID,
+                "TextLog",
+                "Log",
+                vec!["+ /**".to_owned()],
+            ),
+        ]
+    }
+
+    /// Log the blueprint and make it the active one for the application.
+    ///
+    /// With `make_default`, it instead becomes the layout the viewer resets to, and
+    /// starts with if there is no layout of an earlier session.
+    /// Then it doesn't replace the layout the user has.
+    pub fn to_log_msgs(
+        &self,
+        app_id: re_log_types::ApplicationId,
+        make_default: bool,
+    ) -> Result<Vec<LogMsg>, Box<dyn std::error::Error>> {
+        let (rec, storage) = blueprint_stream(&app_id)?;
+
+        let views = self.views();
+        for (enabled, id, class, name, contents) in &views {
+            if !enabled {
+                continue;
+            }
+            rec.log(
+                space_view_path(*id),
+                &archetypes::SpaceViewBlueprint::new(*class).with_display_name(*name),
+            )?;
+            log_contents(&rec, *id, contents)?;
+        }
+
+        // Maps as tabs on the left, plots and log stacked on the right:
+        let enabled_views = |ids: &[u128]| -> Vec<datatypes::EntityPath> {
+            views
+                .iter()
+                .filter(|(enabled, id, ..)| *enabled && ids.contains(id))
+                .map(|(_, id, ..)| datatypes::EntityPath(space_view_path(*id).into()))
+                .collect()
+        };
+        let mut columns = Vec::new();
+        for (id, kind, view_ids, share) in [
+            (
+                MAPS_CONTAINER_ID,
+                viewport_components::ContainerKind::Tabs,
+                MAP_VIEW_IDS.to_vec(),
+                2.0,
+            ),
+            (
+                PLOTS_CONTAINER_ID,
+                viewport_components::ContainerKind::Vertical,
+                vec![TIME_SERIES_VIEW_ID, TEXT_LOG_VIEW_ID],
+                1.0,
+            ),
+        ] {
+            let contents = enabled_views(&view_ids);
+            if contents.is_empty() {
+                continue;
+            }
+            rec.log(
+                container_path(id),
+                &viewport::ContainerBlueprint::new(kind).with_contents(contents),
+            )?;
+            columns.push((datatypes::EntityPath(container_path(id).into()), share));
+        }
+
+        let (contents, shares): (Vec<_>, Vec<f32>) = columns.into_iter().unzip();
+        rec.log(
+            container_path(ROOT_CONTAINER_ID),
+            &viewport::ContainerBlueprint::new(viewport_components::ContainerKind::Horizontal)
+                .with_contents(contents)
+                .with_col_shares(shares),
+        )?;
+        rec.log(
+            "viewport",
+            &viewport::ViewportBlueprint::new()
+                .with_root_container(uuid(ROOT_CONTAINER_ID))
+                .with_auto_space_views(false),
+        )?;
+
+        Ok(activated(&rec, &storage, &app_id, make_default))
+    }
+
+    /// Update which submaps the map views show.
+    ///
+    /// If the viewer shows the Cartographer layout already (`is_active`), only the contents of
+    /// the map views are logged, keeping the changes the user made to the rest of the layout.
+    /// Otherwise the whole layout is logged and activated, since the blueprint store may not
+    /// have it yet, e.g. with `--no-default-blueprint`.
+    pub fn submap_log_msgs(
+        &self,
+        app_id: &re_log_types::ApplicationId,
+        is_active: bool,
+    ) -> Result<Vec<LogMsg>, Box<dyn std::error::Error>> {
+        if !is_active {
+            return self.to_log_msgs(app_id.clone(), false);
+        }
+
+        let (rec, storage) = blueprint_stream(app_id)?;
+        for (_, id, _, _, contents) in self
+            .views()
+            .iter()
+            .filter(|(_, id, ..)| MAP_VIEW_IDS.contains(id))
+        {
+            log_contents(&rec, *id, contents)?;
+        }
+        Ok(activated(&rec, &storage, app_id, false))
+    }
+
+    /// Save the blueprint as an `.rbl` file.
+    pub fn save(
+        &self,
+        path: &Path,
+        app_id: re_log_types::ApplicationId,
+    ) -> Result<(), Box<dyn std::error::Error>> {
+        let mut encoder = re_log_encoding::encoder::Encoder::new(
+            re_build_info::CrateVersion::LOCAL,
+            re_log_encoding::EncodingOptions::COMPRESSED,
+            std::io::BufWriter::new(std::fs::File::create(path)?),
+        )?;
+        for msg in self.to_log_msgs(app_id, false)? {
+            encoder.append(&msg)?;
+        }
+        encoder.flush_blocking()?;
+        Ok(())
+    }
+}
+
+fn log_contents(
+    rec: &re_sdk::RecordingStream,
+    id: u128,
+    contents: &[String],
+) -> Result<(), Box<dyn std::error::Error>> {
+    rec.log(
+        format!("{}/SpaceViewContents", space_view_path(id)),
+        &archetypes::SpaceViewContents::new(
+            contents
+                .iter()
+                .map(|rule| components::QueryExpression::from(rule.as_str())),
+        ),
+    )?;
+    Ok(())
+}
+
+/// Pick the views of the layout, and apply, save or load it.
+///
+/// Returns the blueprint messages to send to the viewer, if any.
+/// Loaded variants keep the application id they were saved with.
+pub fn blueprint_ui(
+    ui: &mut egui::Ui,
+    app_id: &re_log_types::ApplicationId,
+    blueprint: &mut CartographerBlueprint,
+) -> Option<Vec<LogMsg>> {
+    let views = &mut blueprint.views;
+    ui.checkbox(&mut views.map_3d, "3D map");
+    ui.checkbox(&mut views.map_2d, "2D map");
+    ui.checkbox(&mut views.grid, "Submap grids");
+    ui.checkbox(&mut views.time_series, "Time series");
+    ui.checkbox(&mut views.text_log, "Text log");
+
+    let mut msgs = None;
+    ui.horizontal(|ui| {
+        if ui.button("Apply").clicked() {
+            match blueprint.to_log_msgs(app_id.clone(), false) {
+                Ok(blueprint_msgs) => msgs = Some(blueprint_msgs),
+                Err(err) => re_log::error!("Failed to create blueprint: {err}"),
+            }
+        }
+
+        if ui.button("Save…").clicked() {
+            if let Some(path) = rfd::FileDialog::new()
+                .add_filter("Rerun blueprint", &["rbl"])
+                .set_file_name("cartographer.rbl")
+                .save_file()
+            {
+                match blueprint.save(&path, app_id.clone()) {
+                    Ok(()) => re_log::info!("Saved blueprint to {}", path.display()),
+                    Err(err) => re_log::error!("Failed to save {}: {err}", path.display()),
+                }
+            }
+        }
+
+        if ui.button("Load…").clicked() {
+            if let Some(path) = rfd::FileDialog::new()
+                .add_filter("Rerun blueprint", &["rbl"])
+                .pick_file()
+            {
+                match load(&path) {
+                    Ok(blueprint_msgs) => msgs = Some(blueprint_msgs),
+                    Err(err) => re_log::error!("Failed to load {}: {err}", path.display()),
+                }
+            }
+        }
+    });
+    msgs
+}
+
+/// Read a saved blueprint, making it the active one when sent to the viewer.
+fn load(path: &Path) -> Result<Vec<LogMsg>, Box<dyn std::error::Error>> {
+    let decoder = re_log_encoding::decoder::Decoder::new(
+        re_log_encoding::decoder::VersionPolicy::Warn,
+        std::io::BufReader::new(std::fs::File::open(path)?),
+    )?;
+    let mut msgs = decoder.collect::<Result<Vec<_>, _>>()?;
+
+    let blueprint_id = msgs
+        .iter()
+        .find_map(|msg| match msg {
+            LogMsg::SetStoreInfo(set_store_info)
+                if set_store_info.info.store_id.kind == re_log_types::StoreKind::Blueprint =>
+            {
+                Some(set_store_info.info.store_id.clone())
+            }
+            _ => None,
+        })
+        .ok_or("Not a blueprint file")?;
+    msgs.push(LogMsg::BlueprintActivationCommand(
+        BlueprintActivationCommand {
+            blueprint_id,
+            make_active: true,
+            make_default: false,
+        },
+    ));
+    Ok(msgs)
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::BTreeSet;
+
+    use re_viewer::external::re_log_types::DataTable;
+
+    use super::*;
+
+    fn entity_paths(msgs: &[LogMsg]) -> BTreeSet<String> {
+        msgs.iter()
+            .filter_map(|msg| match msg {
+                LogMsg::ArrowMsg(_, arrow_msg) => {
+                    Some(DataTable::from_arrow_msg(arrow_msg).unwrap())
+                }
+                _ => None,
+            })
+            .flat_map(|table| {
+                table
+                    .to_rows()
+                    .map(|row| row.unwrap().entity_path.to_string())
+                    .collect::<Vec<_>>()
+            })
+            .collect()
+    }
+
+    fn activation(msgs: &[LogMsg]) -> Option<&BlueprintActivationCommand> {
+        msgs.iter().find_map(|msg| match msg {
+            LogMsg::BlueprintActivationCommand(command) => Some(command),
+            _ => None,
+        })
+    }
+
+    #[test]
+    fn submaps_of_inactive_layout() {
+        let app_id = re_log_types::ApplicationId::from("test");
+        let blueprint = CartographerBlueprint::default();
+
+        // Contents alone would be an empty layout, so the whole layout is sent:
+        let msgs = blueprint.submap_log_msgs(&app_id, false).unwrap();
+        let paths = entity_paths(&msgs);
+        assert!(paths.contains("/viewport"), "{paths:?}");
+        assert!(paths.contains(&format!("/{}", container_path(ROOT_CONTAINER_ID))));
+        let command = activation(&msgs).unwrap();
+        assert_eq!(command.blueprint_id, store_id(&app_id));
+        assert!(command.make_active);
+    }
+
+    #[test]
+    fn submaps_of_active_layout() {
+        let app_id = re_log_types::ApplicationId::from("test");
+        let blueprint = CartographerBlueprint::default();
+
+        let msgs = blueprint.submap_log_msgs(&app_id, true).unwrap();
+        let expected: BTreeSet<String> = MAP_VIEW_IDS
+            .iter()
+            .map(|id| format!("/{}/SpaceViewContents", space_view_path(*id)))
+            .collect();
+        assert_eq!(entity_paths(&msgs), expected);
+    }
+}
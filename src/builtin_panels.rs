@@ -0,0 +1,264 @@
+//! The tabs the Cartographer panel always has.
+
+use re_viewer::external::{egui, re_entity_db, re_log, re_log_types, re_memory};
+
+use crate::{
+    blueprint, constraint_inspector, entity_tree, evaluation, ingest, inspector, memory,
+    panel::{CartographerPanel, PanelContext},
+    recordings::Recordings,
+    submaps,
+    time_cursor::TimeCursor,
+    viewer_state,
+};
+
+/// All built-in panels, in the order of their tabs.
+pub fn builtin_panels(
+    recordings: &Recordings,
+    memory_limit: re_memory::MemoryLimit,
+) -> Vec<Box<dyn CartographerPanel>> {
+    vec![
+        Box::<EntitiesPanel>::default(),
+        Box::<LayoutPanel>::default(),
+        Box::<ConstraintsPanel>::default(),
+        Box::<EvaluationPanel>::default(),
+        Box::new(IngestPanel {
+            monitor: recordings.ingest.clone(),
+            settings: Default::default(),
+        }),
+        Box::new(MemoryPanel {
+            pruner: recordings.pruner.clone(),
+            memory_limit,
+            state: Default::default(),
+        }),
+    ]
+}
+
+/// The recording and time cursor, or a note that there is no recording yet.
+fn recording<'a>(
+    ui: &mut egui::Ui,
+    ctx: &PanelContext<'a>,
+) -> Option<(&'a re_entity_db::EntityDb, TimeCursor)> {
+    let recording = ctx.entity_db.zip(ctx.time_cursor);
+    if recording.is_none() {
+        ui.label("No log database loaded yet.");
+    }
+    recording
+}
+
+/// Read the state stored by [`CartographerPanel::save_state`], logging what can't be read.
+fn stored_state<T: serde::de::DeserializeOwned>(id: &str, state: serde_json::Value) -> Option<T> {
+    serde_json::from_value(state)
+        .map_err(|err| re_log::warn!("Ignoring the stored state of the {id} panel: {err}"))
+        .ok()
+}
+
+#[derive(Default)]
+struct EntitiesPanel {
+    entity_tree: entity_tree::EntityTreeState,
+}
+
+impl CartographerPanel for EntitiesPanel {
+    fn id(&self) -> &str {
+        "entities"
+    }
+
+    fn name(&self) -> &str {
+        "Entities"
+    }
+
+    fn ui(&mut self, ui: &mut egui::Ui, ctx: &mut PanelContext<'_>) {
+        let Some((entity_db, time_cursor)) = recording(ui, ctx) else {
+            return;
+        };
+        let previous = ctx.selection().cloned();
+        let mut selection = previous.clone();
+        inspector::entity_db_ui(
+            ui,
+            entity_db,
+            &time_cursor,
+            &mut self.entity_tree,
+            &mut selection,
+            ctx.pinned_components,
+        );
+        if let Some(entity_path) =
+            selection.filter(|selection| Some(selection) != previous.as_ref())
+        {
+            ctx.select(entity_path);
+        }
+    }
+
+    fn save_state(&self) -> Option<serde_json::Value> {
+        serde_json::to_value(self.entity_tree.persisted()).ok()
+    }
+
+    fn load_state(&mut self, state: serde_json::Value) {
+        if let Some(persisted) = stored_state(self.id(), state) {
+            self.entity_tree.restore(persisted);
+        }
+    }
+}
+
+/// Views of the layout, and which submaps they show.
+#[derive(Default)]
+struct LayoutPanel {
+    blueprint: blueprint::CartographerBlueprint,
+}
+
+impl CartographerPanel for LayoutPanel {
+    fn id(&self) -> &str {
+        "layout"
+    }
+
+    fn name(&self) -> &str {
+        "Layout"
+    }
+
+    fn ui(&mut self, ui: &mut egui::Ui, ctx: &mut PanelContext<'_>) {
+        let Some((entity_db, time_cursor)) = recording(ui, ctx) else {
+            return;
+        };
+        let app_id = entity_db
+            .app_id()
+            .cloned()
+            .unwrap_or_else(re_log_types::ApplicationId::unknown);
+
+        if let Some(msgs) = blueprint::blueprint_ui(ui, &app_id, &mut self.blueprint) {
+            ctx.send_log_msgs(msgs);
+        }
+
+        ui.separator();
+        ui.strong("Submaps");
+        // Only known while the viewer shows a grid view, as the Cartographer layout does:
+        let is_active = viewer_state::get(ui.ctx(), entity_db.store_id())
+            .is_some_and(|state| state.blueprint_id == blueprint::store_id(&app_id));
+        if !is_active {
+            ui.weak("Changing the submaps shown switches the viewer to the Cartographer layout.");
+        }
+        let query = time_cursor.latest_at_query();
+        if submaps::submap_browser_ui(ui, entity_db, &query, &mut self.blueprint.submaps) {
+            match self.blueprint.submap_log_msgs(&app_id, is_active) {
+                Ok(msgs) => ctx.send_log_msgs(msgs),
+                Err(err) => re_log::error!("Failed to update the blueprint: {err}"),
+            }
+        }
+    }
+}
+
+#[derive(Default)]
+struct ConstraintsPanel {
+    state: constraint_inspector::ConstraintInspectorState,
+}
+
+impl CartographerPanel for ConstraintsPanel {
+    fn id(&self) -> &str {
+        "constraints"
+    }
+
+    fn name(&self) -> &str {
+        "Constraints"
+    }
+
+    fn ui(&mut self, ui: &mut egui::Ui, ctx: &mut PanelContext<'_>) {
+        let Some((entity_db, time_cursor)) = recording(ui, ctx) else {
+            return;
+        };
+        constraint_inspector::constraint_inspector_ui(
+            ui,
+            ctx.command_sender(),
+            entity_db,
+            time_cursor.timeline,
+            &mut self.state,
+        );
+    }
+
+    fn save_state(&self) -> Option<serde_json::Value> {
+        serde_json::to_value(self.state.persisted()).ok()
+    }
+
+    fn load_state(&mut self, state: serde_json::Value) {
+        if let Some(persisted) = stored_state(self.id(), state) {
+            self.state.restore(persisted);
+        }
+    }
+}
+
+#[derive(Default)]
+struct EvaluationPanel {
+    state: evaluation::EvaluationState,
+}
+
+impl CartographerPanel for EvaluationPanel {
+    fn id(&self) -> &str {
+        "evaluation"
+    }
+
+    fn name(&self) -> &str {
+        "Evaluation"
+    }
+
+    fn ui(&mut self, ui: &mut egui::Ui, ctx: &mut PanelContext<'_>) {
+        let Some((entity_db, time_cursor)) = recording(ui, ctx) else {
+            return;
+        };
+        if let Some(msgs) =
+            evaluation::evaluation_ui(ui, entity_db, time_cursor.timeline, &mut self.state)
+        {
+            ctx.send_log_msgs(msgs);
+        }
+    }
+}
+
+struct IngestPanel {
+    monitor: ingest::IngestMonitor,
+    settings: ingest::IngestSettings,
+}
+
+impl CartographerPanel for IngestPanel {
+    fn id(&self) -> &str {
+        "ingest"
+    }
+
+    fn name(&self) -> &str {
+        "Ingest"
+    }
+
+    fn ui(&mut self, ui: &mut egui::Ui, _ctx: &mut PanelContext<'_>) {
+        ingest::ingest_ui(ui, &self.monitor, &mut self.settings);
+    }
+
+    fn save_state(&self) -> Option<serde_json::Value> {
+        serde_json::to_value(self.settings.persisted()).ok()
+    }
+
+    fn load_state(&mut self, state: serde_json::Value) {
+        if let Some(persisted) = stored_state(self.id(), state) {
+            self.settings.restore(persisted);
+        }
+    }
+}
+
+struct MemoryPanel {
+    pruner: memory::Pruner,
+    memory_limit: re_memory::MemoryLimit,
+    state: memory::MemoryState,
+}
+
+impl CartographerPanel for MemoryPanel {
+    fn id(&self) -> &str {
+        "memory"
+    }
+
+    fn name(&self) -> &str {
+        "Memory"
+    }
+
+    fn ui(&mut self, ui: &mut egui::Ui, ctx: &mut PanelContext<'_>) {
+        memory::memory_ui(
+            ui,
+            ctx.entity_db,
+            self.memory_limit,
+            &self.pruner,
+            &mut self.state,
+        );
+    }
+}
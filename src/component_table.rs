@@ -0,0 +1,162 @@
+use re_viewer::external::{arrow2, egui};
+
+use crate::arrow_format;
+
+/// Number of instances shown per page.
+const PAGE_SIZE: usize = 1000;
+
+/// Pagination state of one component table, kept in egui memory.
+#[derive(Clone, Default)]
+struct TableState {
+    page: usize,
+
+    /// Contents of the jump-to-index text box.
+    jump_to: String,
+
+    /// Row on the current page to scroll to in the next frame.
+    scroll_to_row: Option<usize>,
+
+    /// Instance shown in full below the table.
+    selected: Option<usize>,
+}
+
+/// Show all instances of a component in a table.
+///
+/// Only the visible rows are formatted, and large components are split into pages,
+/// so even point clouds with hundreds of thousands of points stay responsive.
+/// Struct components (e.g. `Position3D`) get one column per field.
+/// Clicking a row shows the full value of that instance below the table.
+pub fn component_table_ui(
+    ui: &mut egui::Ui,
+    id_source: impl std::hash::Hash,
+    data: &dyn arrow2::array::Array,
+) {
+    ui.push_id(id_source, |ui| {
+        let id = ui.id();
+        let mut state: TableState = ui.data_mut(|d| d.get_temp(id)).unwrap_or_default();
+
+        let num_instances = data.len();
+        let num_pages = num_instances.div_ceil(PAGE_SIZE).max(1);
+        state.page = state.page.min(num_pages - 1);
+
+        if 1 < num_instances {
+            pagination_ui(ui, &mut state, num_instances, num_pages);
+        }
+
+        let page_start = state.page * PAGE_SIZE;
+        let page_len = PAGE_SIZE.min(num_instances - page_start);
+
+        // Struct components get one column per field:
+        let (column_names, columns): (Vec<String>, Vec<Box<dyn arrow2::array::Array>>) =
+            if let Some(struct_array) = data.as_any().downcast_ref::<arrow2::array::StructArray>() {
+                struct_array
+                    .fields()
+                    .iter()
+                    .map(|field| field.name.clone())
+                    .zip(struct_array.values().iter().cloned())
+                    .unzip()
+            } else {
+                (vec!["Value".to_owned()], vec![data.to_boxed()])
+            };
+
+        let row_height = ui.text_style_height(&egui::TextStyle::Body);
+        let mut table = egui_extras::TableBuilder::new(ui)
+            .striped(true)
+            .resizable(true)
+            .sense(egui::Sense::click())
+            .max_scroll_height(300.0)
+            .column(egui_extras::Column::auto())
+            .columns(
+                egui_extras::Column::remainder().clip(true),
+                column_names.len(),
+            );
+        if let Some(row) = state.scroll_to_row.take() {
+            table = table.scroll_to_row(row, Some(egui::Align::Center));
+        }
+
+        table
+            .header(row_height, |mut header| {
+                header.col(|ui| {
+                    ui.strong("#");
+                });
+                for name in &column_names {
+                    header.col(|ui| {
+                        ui.strong(name);
+                    });
+                }
+            })
+            .body(|body| {
+                body.rows(row_height, page_len, |mut row| {
+                    let instance = page_start + row.index();
+                    row.set_selected(state.selected == Some(instance));
+                    row.col(|ui| {
+                        ui.label(instance.to_string());
+                    });
+                    for column in &columns {
+                        row.col(|ui| {
+                            let formatted = arrow_format::format_value(
+                                &**column,
+                                instance,
+                                &arrow_format::FormatOptions::COMPACT,
+                            );
+                            ui.label(formatted.text);
+                        });
+                    }
+                    if row.response().clicked() {
+                        state.selected = Some(instance);
+                    }
+                });
+            });
+
+        if let Some(selected) = state.selected.filter(|&i| i < num_instances) {
+            ui.separator();
+            ui.strong(format!("Instance {selected}:"));
+            arrow_format::value_ui(ui, data, selected);
+        }
+
+        ui.data_mut(|d| d.insert_temp(id, state));
+    });
+}
+
+fn pagination_ui(
+    ui: &mut egui::Ui,
+    state: &mut TableState,
+    num_instances: usize,
+    num_pages: usize,
+) {
+    ui.horizontal(|ui| {
+        if ui
+            .add_enabled(0 < state.page, egui::Button::new("◀"))
+            .clicked()
+        {
+            state.page -= 1;
+        }
+        ui.label(format!("Page {} / {num_pages}", state.page + 1));
+        if ui
+            .add_enabled(state.page + 1 < num_pages, egui::Button::new("▶"))
+            .clicked()
+        {
+            state.page += 1;
+        }
+
+        ui.separator();
+
+        let response = ui.add(
+            egui::TextEdit::singleline(&mut state.jump_to)
+                .hint_text("Index")
+                .desired_width(60.0),
+        );
+        let submitted = response.lost_focus() && ui.input(|i| i.key_pressed(egui::Key::Enter));
+        if ui.button("Go").clicked() || submitted {
+            match state.jump_to.trim().parse::<usize>() {
+                Ok(index) if index < num_instances => {
+                    state.page = index / PAGE_SIZE;
+                    state.scroll_to_row = Some(index % PAGE_SIZE);
+                }
+                _ => {
+                    state.jump_to.clear();
+                }
+            }
+        }
+    });
+}
@@ -0,0 +1,363 @@
+//! A sortable, filterable list of pose graph constraints.
+
+use std::collections::BTreeMap;
+
+use re_viewer::external::{
+    egui, re_data_store, re_entity_db, re_log_types,
+    re_types::{self, Loggable as _},
+    re_viewer_context::{self, SystemCommandSender as _},
+};
+
+use crate::{
+    constraints::{self, ConstraintKind, LoggedConstraint, PosePaths, TrajectoryIndex},
+    history,
+    trajectory::{self, Pose},
+};
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, serde::Deserialize, serde::Serialize)]
+enum SortColumn {
+    Kind,
+    Submap,
+    Node,
+    TranslationWeight,
+    RotationWeight,
+    Residual,
+}
+
+pub struct ConstraintInspectorState {
+    show_intra_submap: bool,
+    show_inter_submap: bool,
+
+    /// Only show constraints whose submap or node id contains this, e.g. `0/12`.
+    id_filter: String,
+
+    /// Only show constraints with at least this translation residual, in meters.
+    min_residual: f64,
+
+    sort_column: SortColumn,
+    descending: bool,
+
+    /// The constraint last clicked in the list.
+    selected: Option<(re_log_types::EntityPath, usize)>,
+
+    rows: Option<(RowsKey, Vec<Row>)>,
+}
+
+/// The filters and sorting of [`ConstraintInspectorState`], kept across sessions.
+#[derive(serde::Deserialize, serde::Serialize)]
+pub struct PersistedConstraintInspector {
+    show_intra_submap: bool,
+    show_inter_submap: bool,
+    id_filter: String,
+    min_residual: f64,
+    sort_column: SortColumn,
+    descending: bool,
+}
+
+impl Default for ConstraintInspectorState {
+    fn default() -> Self {
+        Self {
+            show_intra_submap: true,
+            show_inter_submap: true,
+            id_filter: String::new(),
+            min_residual: 0.0,
+            sort_column: SortColumn::Residual,
+            descending: true,
+            selected: None,
+            rows: None,
+        }
+    }
+}
+
+/// Everything the rows depend on.
+#[derive(Clone, PartialEq)]
+struct RowsKey {
+    store_id: re_log_types::StoreId,
+    generation: re_data_store::StoreGeneration,
+    timeline: re_log_types::Timeline,
+}
+
+struct Row {
+    logged: LoggedConstraint,
+
+    /// How far the optimized poses are from satisfying the constraint:
+    /// translation in meters and rotation in radians.
+    residual: Option<(f64, f64)>,
+}
+
+impl ConstraintInspectorState {
+    pub fn persisted(&self) -> PersistedConstraintInspector {
+        PersistedConstraintInspector {
+            show_intra_submap: self.show_intra_submap,
+            show_inter_submap: self.show_inter_submap,
+            id_filter: self.id_filter.clone(),
+            min_residual: self.min_residual,
+            sort_column: self.sort_column,
+            descending: self.descending,
+        }
+    }
+
+    pub fn restore(&mut self, persisted: PersistedConstraintInspector) {
+        self.show_intra_submap = persisted.show_intra_submap;
+        self.show_inter_submap = persisted.show_inter_submap;
+        self.id_filter = persisted.id_filter;
+        self.min_residual = persisted.min_residual;
+        self.sort_column = persisted.sort_column;
+        self.descending = persisted.descending;
+    }
+
+    /// Collect the latest constraints and their residuals, unless the store changed
+    /// since last time.
+    fn update_rows(
+        &mut self,
+        entity_db: &re_entity_db::EntityDb,
+        timeline: re_log_types::Timeline,
+    ) {
+        let key = RowsKey {
+            store_id: entity_db.store_id().clone(),
+            generation: entity_db.generation(),
+            timeline,
+        };
+        if self.rows.as_ref().map(|(k, _)| k) != Some(&key) {
+            let query = re_data_store::LatestAtQuery::latest(timeline);
+            let mut poses = OptimizedPoses::default();
+            let rows = constraints::all_constraints(entity_db, &query)
+                .into_iter()
+                .map(|logged| Row {
+                    residual: poses.residual(entity_db, &query, &logged),
+                    logged,
+                })
+                .collect();
+            self.rows = Some((key, rows));
+        }
+    }
+
+    fn is_visible(&self, row: &Row) -> bool {
+        let constraint = &row.logged.constraint;
+        let kind_visible = match constraint.kind {
+            ConstraintKind::IntraSubmap => self.show_intra_submap,
+            ConstraintKind::InterSubmap => self.show_inter_submap,
+        };
+        let filter = self.id_filter.trim();
+        kind_visible
+            && (filter.is_empty()
+                || format_id(constraint.submap).contains(filter)
+                || format_id(constraint.node).contains(filter))
+            && row
+                .residual
+                .map_or(self.min_residual <= 0.0, |(t, _)| self.min_residual <= t)
+    }
+}
+
+/// Node and submap poses, where the constraints say they are logged.
+#[derive(Default)]
+struct OptimizedPoses {
+    /// Node poses by entity path and timeline, by node index.
+    nodes: BTreeMap<(re_log_types::EntityPath, String), BTreeMap<i64, Pose>>,
+}
+
+impl OptimizedPoses {
+    fn node(
+        &mut self,
+        entity_db: &re_entity_db::EntityDb,
+        paths: &PosePaths,
+        node: TrajectoryIndex,
+    ) -> Option<Pose> {
+        self.nodes
+            .entry((paths.node_entity_path.clone(), paths.node_timeline.clone()))
+            .or_insert_with(|| {
+                let timeline = re_log_types::Timeline::new_sequence(paths.node_timeline.as_str());
+                trajectory::pose_history(entity_db, timeline, &paths.node_entity_path)
+                    .into_iter()
+                    .map(|timed| (timed.time.as_i64(), timed.pose))
+                    .collect()
+            })
+            .get(&i64::from(node.index))
+            .copied()
+    }
+
+    fn submap(
+        entity_db: &re_entity_db::EntityDb,
+        query: &re_data_store::LatestAtQuery,
+        paths: &PosePaths,
+    ) -> Option<Pose> {
+        let data = history::latest_at(
+            entity_db,
+            query,
+            &paths.submap_entity_path,
+            trajectory::transform_component_name(),
+        )?;
+        let transform = re_types::components::Transform3D::from_arrow(&*data)
+            .ok()?
+            .into_iter()
+            .next()?;
+        Some(Pose::from_transform(&transform.0))
+    }
+
+    fn residual(
+        &mut self,
+        entity_db: &re_entity_db::EntityDb,
+        query: &re_data_store::LatestAtQuery,
+        logged: &LoggedConstraint,
+    ) -> Option<(f64, f64)> {
+        let constraint = &logged.constraint;
+        let paths = constraint.poses.as_ref()?;
+        let node = self.node(entity_db, paths, constraint.node)?;
+        let submap = Self::submap(entity_db, query, paths)?;
+        let error = constraint.relative_pose.inverse() * (submap.inverse() * node);
+        Some((
+            error.translation.length(),
+            error.rotation.angle_between(glam::DQuat::IDENTITY),
+        ))
+    }
+}
+
+fn format_id(id: TrajectoryIndex) -> String {
+    format!("{}/{}", id.trajectory_id, id.index)
+}
+
+/// List the latest constraints on the timeline.
+///
+/// Clicking a constraint selects it in the viewer, which highlights its line segment.
+pub fn constraint_inspector_ui(
+    ui: &mut egui::Ui,
+    command_sender: &re_viewer_context::CommandSender,
+    entity_db: &re_entity_db::EntityDb,
+    timeline: re_log_types::Timeline,
+    state: &mut ConstraintInspectorState,
+) {
+    ui.horizontal(|ui| {
+        ui.checkbox(&mut state.show_intra_submap, "Intra-submap");
+        ui.checkbox(&mut state.show_inter_submap, "Inter-submap");
+    });
+    ui.horizontal(|ui| {
+        ui.add(
+            egui::TextEdit::singleline(&mut state.id_filter)
+                .hint_text("Submap or node, e.g. 0/12")
+                .desired_width(140.0),
+        );
+        ui.label("Residual ≥");
+        ui.add(
+            egui::DragValue::new(&mut state.min_residual)
+                .speed(0.01)
+                .range(0.0..=f64::INFINITY)
+                .suffix(" m"),
+        );
+    });
+
+    // Take the rows out of the state so we can read the filter settings while showing them:
+    state.update_rows(entity_db, timeline);
+    let Some((key, rows)) = state.rows.take() else {
+        return;
+    };
+
+    let mut visible: Vec<&Row> = rows.iter().filter(|row| state.is_visible(row)).collect();
+    visible.sort_by(|a, b| {
+        let residual = |row: &Row| row.residual.map_or(f64::NEG_INFINITY, |(t, _)| t);
+        let (ca, cb) = (&a.logged.constraint, &b.logged.constraint);
+        match state.sort_column {
+            SortColumn::Kind => (ca.kind as u8).cmp(&(cb.kind as u8)),
+            SortColumn::Submap => ca.submap.cmp(&cb.submap),
+            SortColumn::Node => ca.node.cmp(&cb.node),
+            SortColumn::TranslationWeight => {
+                ca.translation_weight.total_cmp(&cb.translation_weight)
+            }
+            SortColumn::RotationWeight => ca.rotation_weight.total_cmp(&cb.rotation_weight),
+            SortColumn::Residual => residual(a).total_cmp(&residual(b)),
+        }
+    });
+    if state.descending {
+        visible.reverse();
+    }
+
+    ui.label(format!("{} of {} constraints", visible.len(), rows.len()));
+
+    let row_height = ui.text_style_height(&egui::TextStyle::Body);
+    egui_extras::TableBuilder::new(ui)
+        .striped(true)
+        .resizable(true)
+        .sense(egui::Sense::click())
+        .max_scroll_height(300.0)
+        .columns(egui_extras::Column::auto(), 7)
+        .column(egui_extras::Column::remainder())
+        .header(row_height, |mut header| {
+            for (name, column) in [
+                ("Kind", Some(SortColumn::Kind)),
+                ("Submap", Some(SortColumn::Submap)),
+                ("Node", Some(SortColumn::Node)),
+                ("Relative pose", None),
+                ("Yaw", None),
+                ("Weight t", Some(SortColumn::TranslationWeight)),
+                ("Weight r", Some(SortColumn::RotationWeight)),
+                ("Residual", Some(SortColumn::Residual)),
+            ] {
+                header.col(|ui| {
+                    let Some(column) = column else {
+                        ui.strong(name);
+                        return;
+                    };
+                    let arrow = match (state.sort_column == column, state.descending) {
+                        (false, _) => "",
+                        (true, false) => " ⏶",
+                        (true, true) => " ⏷",
+                    };
+                    if ui.button(format!("{name}{arrow}")).clicked() {
+                        if state.sort_column == column {
+                            state.descending = !state.descending;
+                        } else {
+                            state.sort_column = column;
+                        }
+                    }
+                });
+            }
+        })
+        .body(|body| {
+            body.rows(row_height, visible.len(), |mut row| {
+                let Row { logged, residual } = visible[row.index()];
+                let constraint = &logged.constraint;
+                let key = (logged.entity_path.clone(), logged.instance);
+                row.set_selected(state.selected.as_ref() == Some(&key));
+
+                let t = constraint.relative_pose.translation;
+                let (yaw, _, _) = constraint
+                    .relative_pose
+                    .rotation
+                    .to_euler(glam::EulerRot::ZYX);
+                let cells = [
+                    match constraint.kind {
+                        ConstraintKind::IntraSubmap => "intra".to_owned(),
+                        ConstraintKind::InterSubmap => "inter".to_owned(),
+                    },
+                    format_id(constraint.submap),
+                    format_id(constraint.node),
+                    format!("{:.3}, {:.3}, {:.3}", t.x, t.y, t.z),
+                    format!("{:.1}°", yaw.to_degrees()),
+                    format!("{:.3e}", constraint.translation_weight),
+                    format!("{:.3e}", constraint.rotation_weight),
+                    residual.map_or_else(
+                        || "–".to_owned(),
+                        |(t, r)| format!("{t:.3} m, {:.2}°", r.to_degrees()),
+                    ),
+                ];
+                for cell in cells {
+                    row.col(|ui| {
+                        ui.label(cell);
+                    });
+                }
+
+                if row.response().clicked() {
+                    command_sender.send_system(re_viewer_context::SystemCommand::SetSelection(
+                        re_viewer_context::Item::InstancePath(
+                            re_entity_db::InstancePath::instance(
+                                logged.entity_path.clone(),
+                                re_log_types::Instance::from(logged.instance as u64),
+                            ),
+                        ),
+                    ));
+                    state.selected = Some(key);
+                }
+            });
+        });
+
+    state.rows = Some((key, rows));
+}
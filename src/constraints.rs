@@ -0,0 +1,268 @@
+//! Pose graph constraints, logged as a custom component.
+//!
+//! Any entity with a [`COMPONENT_NAME`] component is a constraint entity.
+//! Instance `i` of the component describes the `i`th constraint, so it lines up
+//! with e.g. the `i`th line strip logged to the same entity.
+
+use re_viewer::external::{
+    arrow2::{
+        self,
+        array::{Array, Float64Array, Int32Array, StructArray, UInt8Array, Utf8Array},
+        datatypes::{DataType, Field},
+    },
+    re_data_store, re_entity_db, re_log_types, re_types,
+};
+
+use crate::{history, trajectory::Pose};
+
+pub const COMPONENT_NAME: &str = "cartographer.components.PoseGraphConstraint";
+
+/// Identifies a submap or a node within a trajectory.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct TrajectoryIndex {
+    pub trajectory_id: i32,
+    pub index: i32,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ConstraintKind {
+    /// Between a submap and a node inserted into it.
+    IntraSubmap,
+
+    /// Between a submap and a node from elsewhere, i.e. a loop closure.
+    InterSubmap,
+}
+
+/// Where the poses a constraint relates are logged.
+#[derive(Clone, Debug, PartialEq)]
+pub struct PosePaths {
+    /// Entity whose transform is the node pose, with the node index as its time
+    /// on [`Self::node_timeline`].
+    pub node_entity_path: re_log_types::EntityPath,
+    pub node_timeline: String,
+
+    /// Entity whose transform is the submap pose.
+    pub submap_entity_path: re_log_types::EntityPath,
+}
+
+/// A pose graph constraint, as in Cartographer's `PoseGraph::Constraint`.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Constraint {
+    pub submap: TrajectoryIndex,
+    pub node: TrajectoryIndex,
+    pub kind: ConstraintKind,
+
+    /// Pose of the node relative to the submap.
+    pub relative_pose: Pose,
+
+    pub translation_weight: f64,
+    pub rotation_weight: f64,
+
+    /// `None` if the poses were not logged, or the constraint was logged without them.
+    pub poses: Option<PosePaths>,
+}
+
+const FIELDS: [(&str, DataType); 14] = [
+    ("submap_trajectory_id", DataType::Int32),
+    ("submap_index", DataType::Int32),
+    ("node_trajectory_id", DataType::Int32),
+    ("node_index", DataType::Int32),
+    ("tag", DataType::UInt8),
+    ("tx", DataType::Float64),
+    ("ty", DataType::Float64),
+    ("tz", DataType::Float64),
+    ("qx", DataType::Float64),
+    ("qy", DataType::Float64),
+    ("qz", DataType::Float64),
+    ("qw", DataType::Float64),
+    ("translation_weight", DataType::Float64),
+    ("rotation_weight", DataType::Float64),
+];
+
+/// Fields of [`PosePaths`], null for constraints without them.
+const PATH_FIELDS: [&str; 3] = ["node_entity_path", "node_timeline", "submap_entity_path"];
+
+fn data_type() -> DataType {
+    DataType::Struct(std::sync::Arc::new(
+        FIELDS
+            .iter()
+            .map(|(name, data_type)| Field::new(*name, data_type.clone(), false))
+            .chain(
+                PATH_FIELDS
+                    .iter()
+                    .map(|name| Field::new(*name, DataType::Utf8, true)),
+            )
+            .collect(),
+    ))
+}
+
+pub fn to_arrow(constraints: &[Constraint]) -> Box<dyn Array> {
+    let ints = |f: fn(&Constraint) -> i32| -> Box<dyn Array> {
+        Int32Array::from_vec(constraints.iter().map(f).collect()).boxed()
+    };
+    let floats = |f: fn(&Constraint) -> f64| -> Box<dyn Array> {
+        Float64Array::from_vec(constraints.iter().map(f).collect()).boxed()
+    };
+    let paths = |f: fn(&PosePaths) -> String| -> Box<dyn Array> {
+        Utf8Array::<i32>::from(
+            constraints
+                .iter()
+                .map(|c| c.poses.as_ref().map(f))
+                .collect::<Vec<_>>(),
+        )
+        .boxed()
+    };
+
+    let values = vec![
+        ints(|c| c.submap.trajectory_id),
+        ints(|c| c.submap.index),
+        ints(|c| c.node.trajectory_id),
+        ints(|c| c.node.index),
+        UInt8Array::from_vec(
+            constraints
+                .iter()
+                .map(|c| match c.kind {
+                    ConstraintKind::IntraSubmap => 0,
+                    ConstraintKind::InterSubmap => 1,
+                })
+                .collect(),
+        )
+        .boxed(),
+        floats(|c| c.relative_pose.translation.x),
+        floats(|c| c.relative_pose.translation.y),
+        floats(|c| c.relative_pose.translation.z),
+        floats(|c| c.relative_pose.rotation.x),
+        floats(|c| c.relative_pose.rotation.y),
+        floats(|c| c.relative_pose.rotation.z),
+        floats(|c| c.relative_pose.rotation.w),
+        floats(|c| c.translation_weight),
+        floats(|c| c.rotation_weight),
+        paths(|p| p.node_entity_path.to_string()),
+        paths(|p| p.node_timeline.clone()),
+        paths(|p| p.submap_entity_path.to_string()),
+    ];
+    StructArray::new(data_type(), values, None).boxed()
+}
+
+pub fn from_arrow(array: &dyn Array) -> Option<Vec<Constraint>> {
+    let array = array.as_any().downcast_ref::<StructArray>()?;
+
+    let submap_trajectory_id: &Int32Array = history::struct_field(array, "submap_trajectory_id")?;
+    let submap_index: &Int32Array = history::struct_field(array, "submap_index")?;
+    let node_trajectory_id: &Int32Array = history::struct_field(array, "node_trajectory_id")?;
+    let node_index: &Int32Array = history::struct_field(array, "node_index")?;
+    let tags: &UInt8Array = history::struct_field(array, "tag")?;
+    let floats = |name| history::struct_field::<Float64Array>(array, name);
+    let (tx, ty, tz) = (floats("tx")?, floats("ty")?, floats("tz")?);
+    let (qx, qy, qz, qw) = (floats("qx")?, floats("qy")?, floats("qz")?, floats("qw")?);
+    let translation_weight = floats("translation_weight")?;
+    let rotation_weight = floats("rotation_weight")?;
+    // Missing in recordings from before the paths were logged:
+    let path = |name| history::struct_field::<Utf8Array<i32>>(array, name);
+    let (node_entity_path, node_timeline, submap_entity_path) = (
+        path("node_entity_path"),
+        path("node_timeline"),
+        path("submap_entity_path"),
+    );
+    fn value(paths: Option<&Utf8Array<i32>>, i: usize) -> Option<&str> {
+        paths?.get(i)
+    }
+    let poses = |i: usize| {
+        Some(PosePaths {
+            node_entity_path: value(node_entity_path, i)?.into(),
+            node_timeline: value(node_timeline, i)?.to_owned(),
+            submap_entity_path: value(submap_entity_path, i)?.into(),
+        })
+    };
+
+    Some(
+        (0..array.len())
+            .map(|i| Constraint {
+                submap: TrajectoryIndex {
+                    trajectory_id: submap_trajectory_id.value(i),
+                    index: submap_index.value(i),
+                },
+                node: TrajectoryIndex {
+                    trajectory_id: node_trajectory_id.value(i),
+                    index: node_index.value(i),
+                },
+                kind: if tags.value(i) == 0 {
+                    ConstraintKind::IntraSubmap
+                } else {
+                    ConstraintKind::InterSubmap
+                },
+                relative_pose: Pose {
+                    translation: glam::dvec3(tx.value(i), ty.value(i), tz.value(i)),
+                    rotation: glam::DQuat::from_xyzw(
+                        qx.value(i),
+                        qy.value(i),
+                        qz.value(i),
+                        qw.value(i),
+                    ),
+                },
+                translation_weight: translation_weight.value(i),
+                rotation_weight: rotation_weight.value(i),
+                poses: poses(i),
+            })
+            .collect(),
+    )
+}
+
+/// A batch of constraints, for logging with the SDK.
+pub struct ConstraintBatch<'a>(pub &'a [Constraint]);
+
+impl re_types::LoggableBatch for ConstraintBatch<'_> {
+    type Name = re_types::ComponentName;
+
+    fn name(&self) -> Self::Name {
+        COMPONENT_NAME.into()
+    }
+
+    fn num_instances(&self) -> usize {
+        self.0.len()
+    }
+
+    fn arrow_field(&self) -> arrow2::datatypes::Field {
+        Field::new(COMPONENT_NAME, data_type(), false)
+    }
+
+    fn to_arrow(&self) -> re_types::SerializationResult<Box<dyn Array>> {
+        Ok(to_arrow(self.0))
+    }
+}
+
+impl re_types::ComponentBatch for ConstraintBatch<'_> {}
+
+/// A constraint and where it was logged.
+#[derive(Clone, Debug)]
+pub struct LoggedConstraint {
+    pub entity_path: re_log_types::EntityPath,
+
+    /// Instance of the constraint component.
+    pub instance: usize,
+
+    pub constraint: Constraint,
+}
+
+/// All constraints in the log database, at the time of the query.
+pub fn all_constraints(
+    entity_db: &re_entity_db::EntityDb,
+    query: &re_data_store::LatestAtQuery,
+) -> Vec<LoggedConstraint> {
+    let component_name = re_types::ComponentName::from(COMPONENT_NAME);
+
+    let mut all = Vec::new();
+    for entity_path in entity_db.entity_paths() {
+        let Some(data) = history::latest_at(entity_db, query, entity_path, component_name) else {
+            continue;
+        };
+        for (instance, constraint) in from_arrow(&*data).into_iter().flatten().enumerate() {
+            all.push(LoggedConstraint {
+                entity_path: entity_path.clone(),
+                instance,
+                constraint,
+            });
+        }
+    }
+    all
+}
@@ -0,0 +1,408 @@
+//! A local HTTP server for driving the viewer from scripts, e.g. on test rigs.
+//!
+//! Commands are JSON objects posted to the server, and are run by the viewer on its next frame.
+//! The server makes up a token when it starts and logs it; requests need it in an
+//! `Authorization: Bearer` (or `X-Cartographer-Token`) header:
+//!
+//! ```sh
+//! alias control='curl -H "Content-Type: application/json" -H "Authorization: Bearer $TOKEN"'
+//! control -d '{"command": "open", "path": "/data/run.pbstream"}' http://127.0.0.1:9877
+//! control -d '{"command": "set_time", "timeline": "node_index", "time": 120}' http://127.0.0.1:9877
+//! control -d '{"command": "select", "entity_path": "trajectories/0/pose"}' http://127.0.0.1:9877
+//! control -d '{"command": "screenshot", "path": "/tmp/map.png"}' http://127.0.0.1:9877
+//! ```
+//!
+//! Each command is answered with `{"ok": true, "result": …}` once it ran,
+//! or with `{"ok": false, "error": "…"}` and status 400.
+//! Requests without the token get status 401, and requests from web pages
+//! (with an `Origin` header) get 403, so that websites can't drive the viewer.
+
+use std::{
+    net::SocketAddr,
+    path::{Path, PathBuf},
+    sync::mpsc,
+    time::Duration,
+};
+
+use re_viewer::external::{egui, re_log};
+
+/// How long to wait for the viewer to run a command, e.g. while it is minimized.
+const REPLY_TIMEOUT: Duration = Duration::from_secs(30);
+
+#[derive(Debug, serde::Deserialize)]
+#[serde(tag = "command", rename_all = "snake_case")]
+pub enum Command {
+    /// List all recordings, and which one is shown.
+    Recordings,
+
+    /// Open a recording (`.rrd`) or a Cartographer state (`.pbstream`) file.
+    Open { path: PathBuf },
+
+    /// Show the recording with this store id.
+    ActivateRecording { store_id: String },
+
+    /// Switch the viewer to the timeline, and pause at `time` if given.
+    ///
+    /// Times are in nanoseconds on time timelines. Only takes effect while the viewport
+    /// has a grid view, as in the Cartographer blueprint.
+    SetTime { timeline: String, time: Option<i64> },
+
+    /// Select an entity in the viewer and the Cartographer panel.
+    Select { entity_path: String },
+
+    /// Save the window as a PNG.
+    Screenshot { path: PathBuf },
+
+    /// Save all values of a component, in the format given by the extension of `path`
+    /// (`.csv`, `.ndjson` or `.parquet`).
+    Export {
+        entity_path: String,
+        component: String,
+
+        /// Defaults to `log_time`.
+        timeline: Option<String>,
+
+        path: PathBuf,
+    },
+}
+
+pub type CommandResult = Result<serde_json::Value, String>;
+
+/// Answers the client waiting for a [`Command`].
+pub struct Reply(mpsc::Sender<CommandResult>);
+
+impl Reply {
+    pub fn send(self, result: CommandResult) {
+        // The client may have timed out:
+        self.0.send(result).ok();
+    }
+}
+
+pub struct ControlServer {
+    commands: mpsc::Receiver<(Command, Reply)>,
+    addr: SocketAddr,
+    token: String,
+}
+
+impl ControlServer {
+    /// Listen on `addr`, e.g. `127.0.0.1:9877`, waking up the viewer for each command.
+    pub fn start(
+        addr: &str,
+        egui_ctx: egui::Context,
+    ) -> Result<Self, Box<dyn std::error::Error + Send + Sync>> {
+        let server = tiny_http::Server::http(addr)
+            .map_err(|err| format!("Failed to start the control server on {addr}: {err}"))?;
+        let addr = server
+            .server_addr()
+            .to_ip()
+            .ok_or("The control server is not listening on an IP address")?;
+        let token = uuid::Uuid::new_v4().simple().to_string();
+        re_log::info!("Control server listening on http://{addr}, with token {token}");
+
+        let (tx, commands) = mpsc::channel();
+        let server_token = token.clone();
+        std::thread::Builder::new()
+            .name("control server".to_owned())
+            .spawn(move || {
+                // Each request waits for the viewer, or for a slow client,
+                // so one doesn't hold up the others:
+                for request in server.incoming_requests() {
+                    let (token, tx, egui_ctx) =
+                        (server_token.clone(), tx.clone(), egui_ctx.clone());
+                    let spawned = std::thread::Builder::new()
+                        .name("control request".to_owned())
+                        .spawn(move || handle_request(request, &token, &tx, &egui_ctx));
+                    if let Err(err) = spawned {
+                        re_log::warn!("Failed to handle a control request: {err}");
+                    }
+                }
+            })?;
+
+        Ok(Self {
+            commands,
+            addr,
+            token,
+        })
+    }
+
+    /// Where the server listens, e.g. to find the port when started on port 0.
+    pub fn addr(&self) -> SocketAddr {
+        self.addr
+    }
+
+    /// The token clients need to send, made up at startup.
+    pub fn token(&self) -> &str {
+        &self.token
+    }
+
+    /// The next command sent by a client, if any.
+    pub fn try_recv(&self) -> Option<(Command, Reply)> {
+        self.commands.try_recv().ok()
+    }
+}
+
+/// Only let through JSON posted by clients with the token.
+///
+/// Browsers add an `Origin` header to requests from web pages, and can't post JSON
+/// to other sites without one, so together this keeps websites from sending commands.
+fn check_request(request: &tiny_http::Request, token: &str) -> Result<(), (u16, &'static str)> {
+    let header = |name: &'static str| {
+        request
+            .headers()
+            .iter()
+            .find(|header| header.field.equiv(name))
+            .map(|header| header.value.as_str())
+    };
+
+    if header("Origin").is_some() {
+        return Err((403, "Requests from web pages are not allowed"));
+    }
+    if *request.method() != tiny_http::Method::Post {
+        return Err((400, "Post commands as JSON"));
+    }
+    let is_json = header("Content-Type")
+        .and_then(|content_type| content_type.split(';').next())
+        .is_some_and(|mime| mime.trim().eq_ignore_ascii_case("application/json"));
+    if !is_json {
+        return Err((400, "Expected Content-Type: application/json"));
+    }
+    let client_token = header("Authorization")
+        .and_then(|value| value.strip_prefix("Bearer "))
+        .or_else(|| header("X-Cartographer-Token"));
+    if client_token.map(str::trim) != Some(token) {
+        return Err((401, "Missing or wrong token, see the log of the viewer"));
+    }
+    Ok(())
+}
+
+fn handle_request(
+    mut request: tiny_http::Request,
+    token: &str,
+    commands: &mpsc::Sender<(Command, Reply)>,
+    egui_ctx: &egui::Context,
+) {
+    let result = check_request(&request, token)
+        .map_err(|(status, err)| (status, err.to_owned()))
+        .and_then(|()| {
+            let mut body = String::new();
+            request
+                .as_reader()
+                .read_to_string(&mut body)
+                .map_err(|err| format!("Failed to read the request: {err}"))
+                .and_then(|_| {
+                    serde_json::from_str::<Command>(&body)
+                        .map_err(|err| format!("Bad command: {err}"))
+                })
+                .and_then(|command| run_in_viewer(command, commands, egui_ctx))
+                .map_err(|err| (400, err))
+        });
+
+    let (status, body) = match result {
+        Ok(result) => (200, serde_json::json!({ "ok": true, "result": result })),
+        Err((status, err)) => (status, serde_json::json!({ "ok": false, "error": err })),
+    };
+    let response = tiny_http::Response::from_string(body.to_string())
+        .with_status_code(status)
+        .with_header(
+            tiny_http::Header::from_bytes("Content-Type", "application/json")
+                .expect("valid header"),
+        );
+    if let Err(err) = request.respond(response) {
+        re_log::debug!("Failed to answer a control client: {err}");
+    }
+}
+
+fn run_in_viewer(
+    command: Command,
+    commands: &mpsc::Sender<(Command, Reply)>,
+    egui_ctx: &egui::Context,
+) -> CommandResult {
+    let (tx, rx) = mpsc::channel();
+    commands
+        .send((command, Reply(tx)))
+        .map_err(|_| "The viewer is shutting down".to_owned())?;
+    egui_ctx.request_repaint();
+    rx.recv_timeout(REPLY_TIMEOUT)
+        .map_err(|_| "The viewer did not run the command in time".to_owned())?
+}
+
+/// Save a screenshot of the window, from [`egui::Event::Screenshot`].
+pub fn save_screenshot(path: &Path, image: &egui::ColorImage) -> CommandResult {
+    let [width, height] = image.size;
+    let rgba: Vec<u8> = image
+        .pixels
+        .iter()
+        .flat_map(|pixel| pixel.to_array())
+        .collect();
+    image::save_buffer_with_format(
+        path,
+        &rgba,
+        width as u32,
+        height as u32,
+        image::ColorType::Rgba8,
+        image::ImageFormat::Png,
+    )
+    .map_err(|err| format!("Failed to save {}: {err}", path.display()))?;
+    Ok(serde_json::json!({ "path": path, "width": width, "height": height }))
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{
+        io::{Read as _, Write as _},
+        net::TcpStream,
+    };
+
+    use super::*;
+
+    /// Post `body` with the given extra headers, returning the status and the JSON answer.
+    fn post(addr: SocketAddr, headers: &str, body: &str) -> (u16, serde_json::Value) {
+        let mut stream = TcpStream::connect(addr).unwrap();
+        write!(
+            stream,
+            "POST / HTTP/1.1\r\nHost: {addr}\r\nConnection: close\r\n{headers}\
+             Content-Length: {}\r\n\r\n{body}",
+            body.len()
+        )
+        .unwrap();
+        let mut response = String::new();
+        stream.read_to_string(&mut response).unwrap();
+
+        let status = response.split(' ').nth(1).unwrap().parse().unwrap();
+        let (_, body) = response.split_once("\r\n\r\n").unwrap();
+        (status, serde_json::from_str(body).unwrap())
+    }
+
+    fn start() -> (ControlServer, String, String) {
+        let server = ControlServer::start("127.0.0.1:0", egui::Context::default()).unwrap();
+        let json = "Content-Type: application/json\r\n".to_owned();
+        let authorized = format!("{json}Authorization: Bearer {}\r\n", server.token());
+        (server, json, authorized)
+    }
+
+    #[test]
+    fn rejects_requests() {
+        let (server, json, authorized) = start();
+        let addr = server.addr();
+        let command = r#"{"command": "recordings"}"#;
+
+        let (status, answer) = post(
+            addr,
+            &format!("{json}Authorization: Bearer wrong\r\n"),
+            command,
+        );
+        assert_eq!(status, 401);
+        assert_eq!(answer["ok"], false);
+        let (status, _) = post(
+            addr,
+            &format!("{json}X-Cartographer-Token: wrong\r\n"),
+            command,
+        );
+        assert_eq!(status, 401);
+        let (status, _) = post(addr, &json, command);
+        assert_eq!(status, 401);
+
+        for content_type in [
+            "text/plain",
+            "application/x-www-form-urlencoded",
+            "multipart/form-data",
+        ] {
+            let (status, answer) = post(
+                addr,
+                &authorized.replace("application/json", content_type),
+                command,
+            );
+            assert_eq!(status, 400, "{content_type}");
+            assert_eq!(answer["ok"], false);
+        }
+        let without_content_type = authorized.replace(&json, "");
+        assert_eq!(post(addr, &without_content_type, command).0, 400);
+
+        // Even with the token, as a web page could have gotten hold of it:
+        for origin in ["https://example.com", "null", "http://127.0.0.1"] {
+            let (status, answer) =
+                post(addr, &format!("{authorized}Origin: {origin}\r\n"), command);
+            assert_eq!(status, 403, "{origin}");
+            assert_eq!(answer["ok"], false);
+        }
+
+        // None of them got to the viewer:
+        assert!(server.try_recv().is_none());
+    }
+
+    #[test]
+    fn answers_while_the_viewer_is_busy() {
+        let (server, _, authorized) = start();
+        let addr = server.addr();
+
+        let waiting = {
+            let authorized = authorized.clone();
+            std::thread::spawn(move || post(addr, &authorized, r#"{"command": "recordings"}"#))
+        };
+        let reply = loop {
+            if let Some((_, reply)) = server.try_recv() {
+                break reply;
+            }
+            std::thread::sleep(Duration::from_millis(1));
+        };
+
+        // While the first command waits for the viewer:
+        assert_eq!(post(addr, "", "{}").0, 400);
+        let token = format!(
+            "Content-Type: application/json\r\nX-Cartographer-Token: {}\r\n",
+            server.token()
+        );
+        let second = std::thread::spawn(move || post(addr, &token, r#"{"command": "recordings"}"#));
+        let second_reply = loop {
+            if let Some((_, reply)) = server.try_recv() {
+                break reply;
+            }
+            std::thread::sleep(Duration::from_millis(1));
+        };
+        second_reply.send(Ok(serde_json::json!(2)));
+        assert_eq!(second.join().unwrap().1["result"], 2);
+
+        reply.send(Ok(serde_json::json!(1)));
+        assert_eq!(waiting.join().unwrap().1["result"], 1);
+    }
+
+    #[test]
+    fn answers_commands() {
+        let (server, _, authorized) = start();
+        let addr = server.addr();
+
+        let client = std::thread::spawn(move || {
+            [
+                post(addr, &authorized, r#"{"command": "recordings"}"#),
+                post(addr, &authorized, r#"{"command": "fly_to_the_moon"}"#),
+                post(addr, &authorized, "{"),
+            ]
+        });
+
+        // Play the viewer, until the client got all its answers:
+        let mut num_commands = 0;
+        while !client.is_finished() {
+            if let Some((command, reply)) = server.try_recv() {
+                assert!(matches!(command, Command::Recordings));
+                reply.send(Ok(serde_json::json!({ "recordings": [] })));
+                num_commands += 1;
+            }
+            std::thread::sleep(Duration::from_millis(1));
+        }
+        let [ok, unknown, malformed] = client.join().unwrap();
+
+        assert_eq!(num_commands, 1);
+        assert_eq!(
+            ok,
+            (
+                200,
+                serde_json::json!({ "ok": true, "result": { "recordings": [] } })
+            )
+        );
+        for (status, answer) in [unknown, malformed] {
+            assert_eq!(status, 400);
+            assert_eq!(answer["ok"], false);
+            assert!(answer["error"].is_string());
+        }
+    }
+}
@@ -0,0 +1,403 @@
+use std::collections::{BTreeSet, HashSet};
+
+use re_viewer::external::{egui, re_entity_db, re_log_types};
+
+/// Search and filter settings of the entity tree.
+#[derive(Default)]
+pub struct EntityTreeState {
+    /// Fuzzy match on the entity path.
+    search: String,
+
+    /// Only show entities with a component whose name contains this.
+    component_filter: String,
+
+    /// Entities passing the filters, and their ancestors.
+    ///
+    /// Computed lazily, and only while a filter is active.
+    visible: Option<(FilterKey, FilteredEntities)>,
+
+    /// Entities the user expanded, outside of filtering.
+    expanded: BTreeSet<re_log_types::EntityPath>,
+
+    /// Entities expanded in an earlier session, to expand once they show up.
+    to_expand: BTreeSet<re_log_types::EntityPath>,
+}
+
+/// The part of [`EntityTreeState`] kept across sessions.
+#[derive(Default, serde::Deserialize, serde::Serialize)]
+#[serde(default)]
+pub struct PersistedEntityTree {
+    search: String,
+    component_filter: String,
+    expanded: Vec<String>,
+}
+
+/// The entities shown while filtering.
+#[derive(Default)]
+struct FilteredEntities {
+    /// Entities passing the filters, and their ancestors.
+    visible: HashSet<re_log_types::EntityPath>,
+
+    /// Ancestors of entities passing the filters, expanded to show them.
+    /// The matches themselves stay collapsed, as their data can be large.
+    ancestors: HashSet<re_log_types::EntityPath>,
+}
+
+impl FilteredEntities {
+    fn insert(&mut self, entity_path: &re_log_types::EntityPath) {
+        self.visible.insert(entity_path.clone());
+        let mut parent = entity_path.parent();
+        while let Some(path) = parent {
+            if !self.ancestors.insert(path.clone()) {
+                break; // So are its ancestors.
+            }
+            self.visible.insert(path.clone());
+            parent = path.parent();
+        }
+    }
+}
+
+/// Everything the set of visible entities depends on.
+#[derive(Clone, PartialEq)]
+struct FilterKey {
+    search: String,
+    component_filter: String,
+    timeline: re_log_types::Timeline,
+    store_id: re_log_types::StoreId,
+    num_entities: usize,
+}
+
+impl EntityTreeState {
+    pub fn persisted(&self) -> PersistedEntityTree {
+        PersistedEntityTree {
+            search: self.search.clone(),
+            component_filter: self.component_filter.clone(),
+            expanded: self
+                .expanded
+                .iter()
+                .chain(&self.to_expand)
+                .map(|entity_path| entity_path.to_string())
+                .collect(),
+        }
+    }
+
+    pub fn restore(&mut self, persisted: PersistedEntityTree) {
+        self.search = persisted.search;
+        self.component_filter = persisted.component_filter;
+        self.to_expand = persisted
+            .expanded
+            .iter()
+            .map(|entity_path| re_log_types::EntityPath::from(entity_path.as_str()))
+            .collect();
+    }
+
+    fn is_filtering(&self) -> bool {
+        !self.search.is_empty() || !self.component_filter.is_empty()
+    }
+
+    fn visible_entities(
+        &mut self,
+        entity_db: &re_entity_db::EntityDb,
+        timeline: re_log_types::Timeline,
+    ) -> &FilteredEntities {
+        let key = FilterKey {
+            search: self.search.clone(),
+            component_filter: self.component_filter.clone(),
+            timeline,
+            store_id: entity_db.store_id().clone(),
+            num_entities: entity_db.entity_paths().len(),
+        };
+
+        if self.visible.as_ref().map(|(k, _)| k) != Some(&key) {
+            let visible = self.compute_visible_entities(entity_db, timeline);
+            self.visible = Some((key, visible));
+        }
+
+        &self.visible.as_ref().expect("set above").1
+    }
+
+    fn compute_visible_entities(
+        &self,
+        entity_db: &re_entity_db::EntityDb,
+        timeline: re_log_types::Timeline,
+    ) -> FilteredEntities {
+        let search = self.search.to_lowercase();
+        let component_filter = self.component_filter.to_lowercase();
+
+        let mut filtered = FilteredEntities::default();
+        for entity_path in entity_db.entity_paths() {
+            if !fuzzy_match(&search, &entity_path.to_string().to_lowercase()) {
+                continue;
+            }
+
+            if !component_filter.is_empty() {
+                let has_component = entity_db
+                    .store()
+                    .all_components(&timeline, entity_path)
+                    .is_some_and(|components| {
+                        components.iter().any(|component| {
+                            component
+                                .as_str()
+                                .to_lowercase()
+                                .contains(&component_filter)
+                        })
+                    });
+                if !has_component {
+                    continue;
+                }
+            }
+
+            filtered.insert(entity_path);
+        }
+        filtered
+    }
+}
+
+/// Does `text` contain all characters of `pattern`, in order?
+fn fuzzy_match(pattern: &str, text: &str) -> bool {
+    let mut text = text.chars();
+    pattern.chars().all(|p| text.any(|t| t == p))
+}
+
+/// Show the entities of the log database as a tree following their paths.
+///
+/// Children are only visited when their parent is expanded,
+/// so large recordings stay responsive.
+/// `entity_contents_ui` is called to show the data of each expanded entity.
+/// Clicking an entity makes it the `selection`.
+pub fn entity_tree_ui(
+    ui: &mut egui::Ui,
+    entity_db: &re_entity_db::EntityDb,
+    timeline: re_log_types::Timeline,
+    state: &mut EntityTreeState,
+    selection: &mut Option<re_log_types::EntityPath>,
+    mut entity_contents_ui: impl FnMut(&mut egui::Ui, &re_log_types::EntityPath),
+) {
+    ui.horizontal(|ui| {
+        ui.label("Search:");
+        ui.add(egui::TextEdit::singleline(&mut state.search).hint_text("/submaps/0"));
+    });
+    ui.horizontal(|ui| {
+        ui.label("Component:");
+        ui.add(egui::TextEdit::singleline(&mut state.component_filter).hint_text("Position3D"));
+    });
+
+    if state.is_filtering() {
+        let filtered = state.visible_entities(entity_db, timeline);
+        if filtered.visible.is_empty() {
+            ui.label("No matching entities.");
+            return;
+        }
+        // Filtering expands down to the matches, which says nothing about what the user expanded:
+        let mut expanded = Expanded::default();
+        entity_tree_scroll_ui(
+            ui,
+            entity_db,
+            Some(filtered),
+            selection,
+            &mut expanded,
+            &mut entity_contents_ui,
+        );
+    } else {
+        let mut expanded = Expanded {
+            expanded: BTreeSet::new(),
+            to_expand: std::mem::take(&mut state.to_expand),
+        };
+        entity_tree_scroll_ui(
+            ui,
+            entity_db,
+            None,
+            selection,
+            &mut expanded,
+            &mut entity_contents_ui,
+        );
+        // Restored entities below collapsed parents are expanded once their parent is:
+        state.expanded = expanded.expanded;
+        state.to_expand = expanded.to_expand;
+    }
+}
+
+/// Which entities are expanded, found while showing the tree.
+#[derive(Default)]
+struct Expanded {
+    expanded: BTreeSet<re_log_types::EntityPath>,
+    to_expand: BTreeSet<re_log_types::EntityPath>,
+}
+
+fn entity_tree_scroll_ui(
+    ui: &mut egui::Ui,
+    entity_db: &re_entity_db::EntityDb,
+    filtered: Option<&FilteredEntities>,
+    selection: &mut Option<re_log_types::EntityPath>,
+    expanded: &mut Expanded,
+    entity_contents_ui: &mut impl FnMut(&mut egui::Ui, &re_log_types::EntityPath),
+) {
+    egui::ScrollArea::vertical()
+        .auto_shrink([false, true])
+        .show(ui, |ui| {
+            for child in entity_db.tree().children.values() {
+                tree_node_ui(ui, child, filtered, selection, expanded, entity_contents_ui);
+            }
+        });
+}
+
+fn tree_node_ui(
+    ui: &mut egui::Ui,
+    tree: &re_entity_db::EntityTree,
+    filtered: Option<&FilteredEntities>,
+    selection: &mut Option<re_log_types::EntityPath>,
+    expanded: &mut Expanded,
+    entity_contents_ui: &mut impl FnMut(&mut egui::Ui, &re_log_types::EntityPath),
+) {
+    if filtered.is_some_and(|filtered| !filtered.visible.contains(&tree.path)) {
+        return;
+    }
+
+    let name = tree
+        .path
+        .last()
+        .map_or_else(|| "/".to_owned(), |part| part.ui_string());
+
+    // Expand down to the matches while filtering, but remember
+    // what the user had expanded for when the filter is cleared again.
+    let is_filtering = filtered.is_some();
+    let id = ui.make_persistent_id((&tree.path, is_filtering));
+    let default_open = filtered.is_some_and(|filtered| filtered.ancestors.contains(&tree.path));
+    let mut state = egui::collapsing_header::CollapsingState::load_with_default_open(
+        ui.ctx(),
+        id,
+        default_open,
+    );
+    if expanded.to_expand.remove(&tree.path) {
+        state.set_open(true);
+    }
+    let is_selected = selection.as_ref() == Some(&tree.path);
+    let (_, header, body) = state
+        .show_header(ui, |ui| ui.selectable_label(is_selected, name))
+        .body(|ui| {
+            entity_contents_ui(ui, &tree.path);
+
+            for child in tree.children.values() {
+                tree_node_ui(ui, child, filtered, selection, expanded, entity_contents_ui);
+            }
+        });
+    if body.is_some() {
+        expanded.expanded.insert(tree.path.clone());
+    }
+    if header.inner.on_hover_text(tree.path.to_string()).clicked() {
+        *selection = Some(tree.path.clone());
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use re_viewer::external::re_types::components;
+
+    use super::*;
+
+    #[test]
+    fn fuzzy() {
+        assert!(fuzzy_match("", "/submaps/0/1"));
+        assert!(fuzzy_match("sm01", "/submaps/0/1"));
+        assert!(fuzzy_match("/submaps/0/1", "/submaps/0/1"));
+        // In order only:
+        assert!(!fuzzy_match("10", "/submaps/0/1"));
+        assert!(!fuzzy_match("submapss", "/submaps/0/1"));
+    }
+
+    fn entity_db() -> re_entity_db::EntityDb {
+        let mut entity_db = re_entity_db::EntityDb::new(re_log_types::StoreId::random(
+            re_log_types::StoreKind::Recording,
+        ));
+        let timepoint = [(re_log_types::Timeline::new_sequence("frame"), 1)];
+        for entity_path in ["submaps/0/0", "submaps/0/1", "trajectories/0/pose"] {
+            let row = re_log_types::DataRow::from_cells1_sized(
+                re_log_types::RowId::new(),
+                entity_path,
+                timepoint,
+                [components::Position3D::new(1.0, 2.0, 3.0)],
+            )
+            .unwrap();
+            entity_db.add_data_row(row).unwrap();
+        }
+        let row = re_log_types::DataRow::from_cells1_sized(
+            re_log_types::RowId::new(),
+            "submaps/0/1/grid",
+            timepoint,
+            [components::Text::from("grid")],
+        )
+        .unwrap();
+        entity_db.add_data_row(row).unwrap();
+        entity_db
+    }
+
+    fn filter(search: &str, component_filter: &str) -> FilteredEntities {
+        let state = EntityTreeState {
+            search: search.to_owned(),
+            component_filter: component_filter.to_owned(),
+            ..Default::default()
+        };
+        state.compute_visible_entities(&entity_db(), re_log_types::Timeline::new_sequence("frame"))
+    }
+
+    fn paths(set: &HashSet<re_log_types::EntityPath>) -> BTreeSet<String> {
+        set.iter().map(|path| path.to_string()).collect()
+    }
+
+    #[test]
+    fn visibility_filter() {
+        let filtered = filter("sub01", "");
+        // The matches, all their ancestors, and nothing else:
+        assert_eq!(
+            paths(&filtered.visible),
+            BTreeSet::from(
+                [
+                    "/",
+                    "/submaps",
+                    "/submaps/0",
+                    "/submaps/0/1",
+                    "/submaps/0/1/grid"
+                ]
+                .map(str::to_owned)
+            )
+        );
+        // Only ancestors are expanded, the matching leaf stays collapsed:
+        assert_eq!(
+            paths(&filtered.ancestors),
+            BTreeSet::from(["/", "/submaps", "/submaps/0", "/submaps/0/1"].map(str::to_owned))
+        );
+    }
+
+    #[test]
+    fn component_filter() {
+        let filtered = filter("", "text");
+        assert_eq!(
+            paths(&filtered.visible),
+            BTreeSet::from(
+                [
+                    "/",
+                    "/submaps",
+                    "/submaps/0",
+                    "/submaps/0/1",
+                    "/submaps/0/1/grid"
+                ]
+                .map(str::to_owned)
+            )
+        );
+
+        let filtered = filter("traj", "position");
+        assert_eq!(
+            paths(&filtered.visible),
+            BTreeSet::from(
+                [
+                    "/",
+                    "/trajectories",
+                    "/trajectories/0",
+                    "/trajectories/0/pose"
+                ]
+                .map(str::to_owned)
+            )
+        );
+        assert!(filter("traj", "text").visible.is_empty());
+    }
+}
@@ -0,0 +1,572 @@
+//! Absolute and relative trajectory error (ATE / RPE) against a reference trajectory,
+//! e.g. ground truth from motion capture or RTK.
+
+use re_viewer::external::{
+    egui, re_entity_db, re_log, re_log_types,
+    re_log_types::{EntityPath, LogMsg},
+    re_types,
+};
+
+use crate::{
+    stats::Stats,
+    time_cursor,
+    trajectory::{self, Pose, TimedPose},
+};
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Alignment {
+    /// Rotation and translation.
+    Se3,
+
+    /// Rotation, translation and scale, e.g. for monocular SLAM.
+    Sim3,
+}
+
+pub struct EvaluationState {
+    pub estimated: Option<EntityPath>,
+    pub reference: Option<EntityPath>,
+
+    /// Maximum time difference between associated poses,
+    /// in seconds on time timelines or in steps on sequence timelines.
+    pub tolerance: f64,
+
+    pub alignment: Alignment,
+
+    result: Option<Result<Evaluation, String>>,
+}
+
+impl Default for EvaluationState {
+    fn default() -> Self {
+        Self {
+            estimated: None,
+            reference: None,
+            tolerance: 0.02,
+            alignment: Alignment::Se3,
+            result: None,
+        }
+    }
+}
+
+/// A similarity transform `p ↦ scale * rotation * p + translation`.
+#[derive(Clone, Copy, Debug)]
+pub struct Similarity {
+    pub rotation: glam::DQuat,
+    pub translation: glam::DVec3,
+    pub scale: f64,
+}
+
+impl Similarity {
+    pub fn apply(&self, pose: &Pose) -> Pose {
+        Pose {
+            translation: self.scale * (self.rotation * pose.translation) + self.translation,
+            rotation: self.rotation * pose.rotation,
+        }
+    }
+}
+
+/// Least-squares alignment of `source` onto `target` (Umeyama, 1991).
+///
+/// Returns `None` if there are fewer than three point pairs or the points are degenerate.
+pub fn umeyama(
+    source: &[glam::DVec3],
+    target: &[glam::DVec3],
+    alignment: Alignment,
+) -> Option<Similarity> {
+    use nalgebra::{Matrix3, Vector3};
+
+    if source.len() != target.len() || source.len() < 3 {
+        return None;
+    }
+    let n = source.len() as f64;
+    let to_na = |v: &glam::DVec3| Vector3::new(v.x, v.y, v.z);
+
+    let mean_source = source.iter().map(to_na).sum::<Vector3<f64>>() / n;
+    let mean_target = target.iter().map(to_na).sum::<Vector3<f64>>() / n;
+
+    let mut covariance = Matrix3::zeros();
+    let mut variance_source = 0.0;
+    for (s, t) in source.iter().zip(target) {
+        let s = to_na(s) - mean_source;
+        let t = to_na(t) - mean_target;
+        covariance += t * s.transpose();
+        variance_source += s.norm_squared();
+    }
+    covariance /= n;
+    variance_source /= n;
+    if variance_source <= f64::EPSILON {
+        return None;
+    }
+
+    let svd = covariance.svd(true, true);
+    let (u, v_t) = (svd.u?, svd.v_t?);
+
+    // Make sure we get a rotation, not a reflection:
+    let mut sign = Matrix3::identity();
+    if u.determinant() * v_t.determinant() < 0.0 {
+        sign[(2, 2)] = -1.0;
+    }
+    let rotation = u * sign * v_t;
+
+    let scale = match alignment {
+        Alignment::Se3 => 1.0,
+        Alignment::Sim3 => {
+            (Matrix3::from_diagonal(&svd.singular_values) * sign).trace() / variance_source
+        }
+    };
+    let translation = mean_target - scale * rotation * mean_source;
+
+    let rotation = glam::DMat3::from_cols_array(&[
+        rotation[(0, 0)],
+        rotation[(1, 0)],
+        rotation[(2, 0)],
+        rotation[(0, 1)],
+        rotation[(1, 1)],
+        rotation[(2, 1)],
+        rotation[(0, 2)],
+        rotation[(1, 2)],
+        rotation[(2, 2)],
+    ]);
+    Some(Similarity {
+        rotation: glam::DQuat::from_mat3(&rotation).normalize(),
+        translation: glam::dvec3(translation.x, translation.y, translation.z),
+        scale,
+    })
+}
+
+/// Pair estimated and reference poses less than `tolerance` apart, closest first,
+/// using each pose at most once (as `associate.py` of the TUM benchmark).
+///
+/// Both trajectories must be sorted by time, and so are the pairs.
+pub fn associate(
+    timeline: re_log_types::Timeline,
+    estimated: &[TimedPose],
+    reference: &[TimedPose],
+    tolerance: f64,
+) -> Vec<(TimedPose, TimedPose)> {
+    let time = |pose: &TimedPose| time_cursor::time_as_f64(timeline, pose.time);
+
+    let mut candidates = Vec::new();
+    for (i, est) in estimated.iter().enumerate() {
+        let t = time(est);
+        let first = reference.partition_point(|r| time(r) < t - tolerance);
+        for (j, r) in reference.iter().enumerate().skip(first) {
+            let difference = (time(r) - t).abs();
+            if tolerance < difference {
+                break;
+            }
+            candidates.push((difference, i, j));
+        }
+    }
+    candidates.sort_by(|(a, ..), (b, ..)| a.total_cmp(b));
+
+    let mut estimated_used = vec![false; estimated.len()];
+    let mut reference_used = vec![false; reference.len()];
+    let mut pairs = Vec::new();
+    for (_, i, j) in candidates {
+        if !estimated_used[i] && !reference_used[j] {
+            estimated_used[i] = true;
+            reference_used[j] = true;
+            pairs.push((i, j));
+        }
+    }
+    pairs.sort_unstable();
+    pairs
+        .into_iter()
+        .map(|(i, j)| (estimated[i], reference[j]))
+        .collect()
+}
+
+/// The result of comparing an estimated trajectory with a reference.
+#[derive(Clone, Debug)]
+pub struct Evaluation {
+    pub num_pairs: usize,
+    pub alignment: Similarity,
+
+    /// Translation error of each associated pose after alignment, in meters.
+    pub ate: Vec<(re_log_types::TimeInt, f64)>,
+
+    /// Translation error of the motion between consecutive associated poses, in meters.
+    pub rpe: Vec<(re_log_types::TimeInt, f64)>,
+}
+
+impl Evaluation {
+    pub fn new(
+        timeline: re_log_types::Timeline,
+        estimated: &[TimedPose],
+        reference: &[TimedPose],
+        tolerance: f64,
+        alignment: Alignment,
+    ) -> Result<Self, String> {
+        let pairs = associate(timeline, estimated, reference, tolerance);
+        if pairs.len() < 3 {
+            return Err(format!(
+                "Only {} poses could be associated; try a larger tolerance",
+                pairs.len()
+            ));
+        }
+
+        let source: Vec<_> = pairs.iter().map(|(e, _)| e.pose.translation).collect();
+        let target: Vec<_> = pairs.iter().map(|(_, r)| r.pose.translation).collect();
+        let similarity =
+            umeyama(&source, &target, alignment).ok_or("Degenerate trajectory, cannot align")?;
+
+        let aligned: Vec<(re_log_types::TimeInt, Pose, Pose)> = pairs
+            .iter()
+            .map(|(e, r)| (e.time, similarity.apply(&e.pose), r.pose))
+            .collect();
+
+        let ate = aligned
+            .iter()
+            .map(|(time, est, reference)| (*time, est.translation.distance(reference.translation)))
+            .collect();
+
+        let rpe = aligned
+            .windows(2)
+            .map(|w| {
+                let (_, est0, ref0) = w[0];
+                let (time, est1, ref1) = w[1];
+                let est_delta = est0.inverse() * est1;
+                let ref_delta = ref0.inverse() * ref1;
+                (time, (ref_delta.inverse() * est_delta).translation.length())
+            })
+            .collect();
+
+        Ok(Self {
+            num_pairs: pairs.len(),
+            alignment: similarity,
+            ate,
+            rpe,
+        })
+    }
+
+    /// Log the per-pose errors next to the estimated trajectory, into the given recording.
+    pub fn to_log_msgs(
+        &self,
+        entity_db: &re_entity_db::EntityDb,
+        timeline: re_log_types::Timeline,
+        estimated: &EntityPath,
+    ) -> Result<Vec<LogMsg>, Box<dyn std::error::Error>> {
+        let app_id = entity_db
+            .app_id()
+            .map_or_else(|| "cartographer".to_owned(), |app_id| app_id.to_string());
+        let (rec, storage) = re_sdk::RecordingStreamBuilder::new(app_id)
+            .recording_id(entity_db.store_id().to_string())
+            .memory()?;
+
+        for (name, errors) in [("ate", &self.ate), ("rpe", &self.rpe)] {
+            let entity_path = estimated.join(&EntityPath::from(format!("evaluation/{name}")));
+            for (time, error) in errors {
+                match timeline.typ() {
+                    re_log_types::TimeType::Time => {
+                        rec.set_time_nanos(timeline.name().as_str(), time.as_i64());
+                    }
+                    re_log_types::TimeType::Sequence => {
+                        rec.set_time_sequence(timeline.name().as_str(), time.as_i64());
+                    }
+                }
+                rec.log(
+                    entity_path.clone(),
+                    &re_types::archetypes::Scalar::new(*error),
+                )?;
+            }
+        }
+        rec.flush_blocking();
+
+        // The recording already exists, so only send the data:
+        Ok(storage
+            .take()
+            .into_iter()
+            .filter(|msg| !matches!(msg, LogMsg::SetStoreInfo(_)))
+            .collect())
+    }
+}
+
+/// Pick two trajectories, compare them, and show the statistics.
+///
+/// Returns messages to add to the recording when the user asks to log the errors.
+pub fn evaluation_ui(
+    ui: &mut egui::Ui,
+    entity_db: &re_entity_db::EntityDb,
+    timeline: re_log_types::Timeline,
+    state: &mut EvaluationState,
+) -> Option<Vec<LogMsg>> {
+    let trajectories: Vec<EntityPath> = entity_db
+        .entity_paths()
+        .into_iter()
+        .filter(|entity_path| {
+            entity_db
+                .store()
+                .all_components(&timeline, entity_path)
+                .is_some_and(|components| {
+                    components.contains(&trajectory::transform_component_name())
+                })
+        })
+        .cloned()
+        .collect();
+
+    egui::Grid::new("trajectory_evaluation")
+        .num_columns(2)
+        .show(ui, |ui| {
+            for (label, selected) in [
+                ("Estimated", &mut state.estimated),
+                ("Reference", &mut state.reference),
+            ] {
+                ui.label(label);
+                egui::ComboBox::from_id_source(label)
+                    .selected_text(
+                        selected
+                            .as_ref()
+                            .map_or_else(String::new, |p| p.to_string()),
+                    )
+                    .show_ui(ui, |ui| {
+                        for entity_path in &trajectories {
+                            ui.selectable_value(
+                                selected,
+                                Some(entity_path.clone()),
+                                entity_path.to_string(),
+                            );
+                        }
+                    });
+                ui.end_row();
+            }
+
+            ui.label("Tolerance");
+            let suffix = match timeline.typ() {
+                re_log_types::TimeType::Time => " s",
+                re_log_types::TimeType::Sequence => "",
+            };
+            ui.add(
+                egui::DragValue::new(&mut state.tolerance)
+                    .speed(0.001)
+                    .range(0.0..=f64::INFINITY)
+                    .suffix(suffix),
+            );
+            ui.end_row();
+
+            ui.label("Alignment");
+            ui.horizontal(|ui| {
+                ui.radio_value(&mut state.alignment, Alignment::Se3, "SE(3)");
+                ui.radio_value(&mut state.alignment, Alignment::Sim3, "Sim(3)");
+            });
+            ui.end_row();
+        });
+
+    let (Some(estimated), Some(reference)) = (&state.estimated, &state.reference) else {
+        return None;
+    };
+
+    if ui.button("Evaluate").clicked() {
+        state.result = Some(Evaluation::new(
+            timeline,
+            &trajectory::pose_history(entity_db, timeline, estimated),
+            &trajectory::pose_history(entity_db, timeline, reference),
+            state.tolerance,
+            state.alignment,
+        ));
+    }
+
+    let mut log_msgs = None;
+    match &state.result {
+        Some(Ok(evaluation)) => {
+            ui.label(format!(
+                "{} associated poses, scale {:.4}",
+                evaluation.num_pairs, evaluation.alignment.scale
+            ));
+            let errors = |errors: &[(re_log_types::TimeInt, f64)]| {
+                Stats::new(errors.iter().map(|(_, e)| *e))
+            };
+            egui::Grid::new("trajectory_errors")
+                .num_columns(5)
+                .striped(true)
+                .show(ui, |ui| {
+                    for header in ["", "RMSE", "Mean", "Median", "Max"] {
+                        ui.strong(header);
+                    }
+                    ui.end_row();
+                    for (name, stats) in [
+                        ("ATE [m]", errors(&evaluation.ate)),
+                        ("RPE [m]", errors(&evaluation.rpe)),
+                    ] {
+                        ui.label(name);
+                        if let Some(stats) = stats {
+                            for value in [stats.rmse, stats.mean, stats.median, stats.max] {
+                                ui.monospace(format!("{value:.4}"));
+                            }
+                        }
+                        ui.end_row();
+                    }
+                });
+
+            if ui
+                .button("Log errors")
+                .on_hover_text(format!("Log per-pose errors under {estimated}/evaluation"))
+                .clicked()
+            {
+                match evaluation.to_log_msgs(entity_db, timeline, estimated) {
+                    Ok(msgs) => log_msgs = Some(msgs),
+                    Err(err) => re_log::error!("Failed to log trajectory errors: {err}"),
+                }
+            }
+        }
+        Some(Err(err)) => {
+            ui.colored_label(ui.visuals().error_fg_color, err);
+        }
+        None => {}
+    }
+    log_msgs
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn points() -> Vec<glam::DVec3> {
+        vec![
+            glam::dvec3(0.0, 0.0, 0.0),
+            glam::dvec3(4.0, 0.0, 0.0),
+            glam::dvec3(4.0, 3.0, 0.5),
+            glam::dvec3(1.0, 5.0, 1.0),
+            glam::dvec3(-2.0, 1.0, 0.2),
+        ]
+    }
+
+    fn known_transform(scale: f64) -> Similarity {
+        Similarity {
+            rotation: glam::DQuat::from_axis_angle(glam::dvec3(1.0, 2.0, 3.0).normalize(), 0.7),
+            translation: glam::dvec3(10.0, -5.0, 2.0),
+            scale,
+        }
+    }
+
+    fn transformed(similarity: &Similarity, points: &[glam::DVec3]) -> Vec<glam::DVec3> {
+        points
+            .iter()
+            .map(|p| similarity.scale * (similarity.rotation * *p) + similarity.translation)
+            .collect()
+    }
+
+    fn assert_recovers(actual: &Similarity, expected: &Similarity) {
+        assert!(
+            actual.rotation.angle_between(expected.rotation) < 1e-9,
+            "{actual:?}"
+        );
+        assert!(
+            actual.translation.distance(expected.translation) < 1e-9,
+            "{actual:?}"
+        );
+        assert!((actual.scale - expected.scale).abs() < 1e-9, "{actual:?}");
+    }
+
+    #[test]
+    fn umeyama_recovers_rigid_transform() {
+        let expected = known_transform(1.0);
+        let source = points();
+        let target = transformed(&expected, &source);
+        let actual = umeyama(&source, &target, Alignment::Se3).unwrap();
+        assert_recovers(&actual, &expected);
+    }
+
+    #[test]
+    fn umeyama_recovers_scale() {
+        let expected = known_transform(2.5);
+        let source = points();
+        let target = transformed(&expected, &source);
+        let actual = umeyama(&source, &target, Alignment::Sim3).unwrap();
+        assert_recovers(&actual, &expected);
+
+        // SE(3) alignment keeps the scale at 1, whatever the data:
+        let rigid = umeyama(&source, &target, Alignment::Se3).unwrap();
+        assert_eq!(rigid.scale, 1.0);
+    }
+
+    #[test]
+    fn umeyama_degenerate() {
+        let source = vec![glam::DVec3::ONE; 4];
+        assert!(umeyama(&source, &points()[..4], Alignment::Se3).is_none());
+        assert!(umeyama(&points()[..2], &points()[..2], Alignment::Se3).is_none());
+    }
+
+    fn poses(times: &[i64]) -> Vec<TimedPose> {
+        times
+            .iter()
+            .map(|&time| TimedPose {
+                time: re_log_types::TimeInt::new_temporal(time),
+                pose: Pose {
+                    translation: glam::dvec3(time as f64, 0.0, 0.0),
+                    rotation: glam::DQuat::IDENTITY,
+                },
+            })
+            .collect()
+    }
+
+    fn times(pairs: &[(TimedPose, TimedPose)]) -> Vec<(i64, i64)> {
+        pairs
+            .iter()
+            .map(|(e, r)| (e.time.as_i64(), r.time.as_i64()))
+            .collect()
+    }
+
+    #[test]
+    fn associate_one_to_one() {
+        let timeline = re_log_types::Timeline::new_sequence("frame");
+        let estimated = poses(&[10, 11, 20, 30]);
+        let reference = poses(&[10, 21, 29, 31, 50]);
+
+        // 11 is as close to 10 as it gets, but 10 is taken by the closer estimate.
+        // 30 is equally close to 29 and 31, and 50 is too far from everything:
+        let pairs = associate(timeline, &estimated, &reference, 2.0);
+        assert_eq!(times(&pairs), [(10, 10), (20, 21), (30, 29)]);
+
+        // Each reference pose is used once, even with a large tolerance:
+        let pairs = associate(timeline, &estimated, &reference, 100.0);
+        assert_eq!(times(&pairs), [(10, 10), (11, 31), (20, 21), (30, 29)]);
+    }
+
+    #[test]
+    fn associate_time_timeline() {
+        // Tolerances are in seconds on time timelines:
+        let timeline = re_log_types::Timeline::new_temporal("sensor_time");
+        let estimated = poses(&[1_000_000_000, 2_000_000_000]);
+        let reference = poses(&[1_010_000_000, 2_100_000_000]);
+        let pairs = associate(timeline, &estimated, &reference, 0.02);
+        assert_eq!(times(&pairs), [(1_000_000_000, 1_010_000_000)]);
+    }
+
+    #[test]
+    fn evaluation_of_scaled_trajectory() {
+        let timeline = re_log_types::Timeline::new_sequence("frame");
+        let reference: Vec<TimedPose> = points()
+            .into_iter()
+            .enumerate()
+            .map(|(i, translation)| TimedPose {
+                time: re_log_types::TimeInt::new_temporal(i as i64),
+                pose: Pose {
+                    translation,
+                    rotation: glam::DQuat::IDENTITY,
+                },
+            })
+            .collect();
+        // The estimate is in another frame and at half the scale:
+        let to_estimate = known_transform(0.5);
+        let estimated: Vec<TimedPose> = reference
+            .iter()
+            .map(|r| TimedPose {
+                time: r.time,
+                pose: to_estimate.apply(&r.pose),
+            })
+            .collect();
+
+        let evaluation =
+            Evaluation::new(timeline, &estimated, &reference, 0.0, Alignment::Sim3).unwrap();
+        assert_eq!(evaluation.num_pairs, 5);
+        assert!((evaluation.alignment.scale - 2.0).abs() < 1e-9);
+        let ate = Stats::new(evaluation.ate.iter().map(|(_, e)| *e)).unwrap();
+        assert!(ate.max < 1e-9, "{ate:?}");
+
+        // Without scale, the errors remain:
+        let evaluation =
+            Evaluation::new(timeline, &estimated, &reference, 0.0, Alignment::Se3).unwrap();
+        let ate = Stats::new(evaluation.ate.iter().map(|(_, e)| *e)).unwrap();
+        assert!(0.1 < ate.rmse, "{ate:?}");
+    }
+}
@@ -0,0 +1,705 @@
+use std::{collections::HashMap, path::Path};
+
+use base64::prelude::{Engine as _, BASE64_STANDARD};
+
+use re_viewer::external::{
+    arrow2::{
+        self,
+        array::{
+            Array, BinaryArray, BooleanArray, FixedSizeBinaryArray, FixedSizeListArray,
+            Float64Array, Int64Array, PrimitiveArray, StructArray, UInt64Array, Utf8Array,
+        },
+    },
+    egui, re_entity_db, re_log, re_log_types,
+    re_types::ComponentName,
+};
+
+use crate::{arrow_format, history};
+
+/// File formats a component can be exported to.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ExportFormat {
+    Csv,
+
+    /// Newline-delimited JSON, one object per row.
+    Json,
+
+    Parquet,
+}
+
+impl ExportFormat {
+    pub const ALL: [Self; 3] = [Self::Csv, Self::Json, Self::Parquet];
+
+    pub fn name(self) -> &'static str {
+        match self {
+            Self::Csv => "CSV",
+            Self::Json => "JSON",
+            Self::Parquet => "Parquet",
+        }
+    }
+
+    pub fn extension(self) -> &'static str {
+        match self {
+            Self::Csv => "csv",
+            Self::Json => "ndjson",
+            Self::Parquet => "parquet",
+        }
+    }
+}
+
+/// Buttons for exporting all values of a component on the timeline.
+pub fn export_ui(
+    ui: &mut egui::Ui,
+    entity_db: &re_entity_db::EntityDb,
+    timeline: re_log_types::Timeline,
+    entity_path: &re_log_types::EntityPath,
+    component_name: ComponentName,
+) {
+    ui.horizontal(|ui| {
+        ui.label("Export:");
+        for format in ExportFormat::ALL {
+            let clicked = ui
+                .small_button(format.name())
+                .on_hover_text(format!(
+                    "Save all values on the {} timeline as {}",
+                    timeline.name(),
+                    format.name()
+                ))
+                .clicked();
+            if !clicked {
+                continue;
+            }
+
+            let file_name = format!(
+                "{}_{}.{}",
+                entity_path.to_string().trim_matches('/').replace('/', "_"),
+                component_name.short_name(),
+                format.extension()
+            );
+            let Some(path) = rfd::FileDialog::new()
+                .set_file_name(file_name)
+                .add_filter(format.name(), &[format.extension()])
+                .save_file()
+            else {
+                continue;
+            };
+
+            let table = ExportTable::from_history(
+                timeline,
+                component_name,
+                &history::component_history(entity_db, timeline, entity_path, component_name),
+            );
+            match table.write(format, &path) {
+                Ok(()) => re_log::info!("Exported {} rows to {}", table.num_rows, path.display()),
+                Err(err) => re_log::error!("Failed to export to {}: {err}", path.display()),
+            }
+        }
+    });
+}
+
+/// A component flattened into a table with one row per instance and time.
+///
+/// Struct fields and fixed-size list elements (e.g. the xyz of a `Position3D`)
+/// get their own columns. Binary blobs are written as base64, and other values
+/// without a column type (lists, unions) as text.
+pub struct ExportTable {
+    pub columns: Vec<Column>,
+    pub num_rows: usize,
+}
+
+pub struct Column {
+    pub name: String,
+    pub values: Values,
+}
+
+/// The values of a column.
+///
+/// A column that gets a value it can't hold is widened: integers of either sign to
+/// floats, and anything else to text.
+pub enum Values {
+    Int(Vec<Option<i64>>),
+    UInt(Vec<Option<u64>>),
+    Float(Vec<Option<f64>>),
+    Bool(Vec<Option<bool>>),
+    Text(Vec<Option<String>>),
+}
+
+/// A single leaf value of a flattened component.
+enum Cell {
+    Int(i64),
+    UInt(u64),
+    Float(f64),
+    Bool(bool),
+    Text(String),
+}
+
+impl Cell {
+    fn as_f64(&self) -> Option<f64> {
+        match self {
+            Self::Int(v) => Some(*v as f64),
+            Self::UInt(v) => Some(*v as f64),
+            Self::Float(v) => Some(*v),
+            Self::Bool(_) | Self::Text(_) => None,
+        }
+    }
+
+    fn into_text(self) -> String {
+        match self {
+            Self::Int(v) => v.to_string(),
+            Self::UInt(v) => v.to_string(),
+            Self::Float(v) => v.to_string(),
+            Self::Bool(v) => v.to_string(),
+            Self::Text(v) => v,
+        }
+    }
+}
+
+impl Values {
+    fn new(cell: &Cell) -> Self {
+        match cell {
+            Cell::Int(_) => Self::Int(Vec::new()),
+            Cell::UInt(_) => Self::UInt(Vec::new()),
+            Cell::Float(_) => Self::Float(Vec::new()),
+            Cell::Bool(_) => Self::Bool(Vec::new()),
+            Cell::Text(_) => Self::Text(Vec::new()),
+        }
+    }
+
+    fn len(&self) -> usize {
+        match self {
+            Self::Int(values) => values.len(),
+            Self::UInt(values) => values.len(),
+            Self::Float(values) => values.len(),
+            Self::Bool(values) => values.len(),
+            Self::Text(values) => values.len(),
+        }
+    }
+
+    fn push(&mut self, cell: Option<Cell>) {
+        let Some(cell) = cell else {
+            match self {
+                Self::Int(values) => values.push(None),
+                Self::UInt(values) => values.push(None),
+                Self::Float(values) => values.push(None),
+                Self::Bool(values) => values.push(None),
+                Self::Text(values) => values.push(None),
+            }
+            return;
+        };
+
+        // Widening ends at text at the latest, which holds anything:
+        let mut cell = cell;
+        while let Err(rejected) = self.try_push(cell) {
+            *self = self.widened_for(&rejected);
+            cell = rejected;
+        }
+    }
+
+    /// Push `cell` if this column can hold it, or hand it back.
+    fn try_push(&mut self, cell: Cell) -> Result<(), Cell> {
+        match (self, cell) {
+            (Self::Int(values), Cell::Int(v)) => values.push(Some(v)),
+            (Self::Int(values), Cell::UInt(v)) => {
+                values.push(Some(i64::try_from(v).map_err(|_| Cell::UInt(v))?));
+            }
+            (Self::UInt(values), Cell::UInt(v)) => values.push(Some(v)),
+            (Self::UInt(values), Cell::Int(v)) => {
+                values.push(Some(u64::try_from(v).map_err(|_| Cell::Int(v))?));
+            }
+            (Self::Float(values), cell) => values.push(Some(cell.as_f64().ok_or(cell)?)),
+            (Self::Bool(values), Cell::Bool(v)) => values.push(Some(v)),
+            (Self::Text(values), cell) => values.push(Some(cell.into_text())),
+            (_, cell) => return Err(cell),
+        }
+        Ok(())
+    }
+
+    /// A column with the values so far, that can hold more than this one:
+    /// floats for numbers, and text for anything else.
+    fn widened_for(&self, cell: &Cell) -> Self {
+        match self {
+            Self::Int(values) if cell.as_f64().is_some() => {
+                Self::Float(values.iter().map(|v| v.map(|v| v as f64)).collect())
+            }
+            Self::UInt(values) if cell.as_f64().is_some() => {
+                Self::Float(values.iter().map(|v| v.map(|v| v as f64)).collect())
+            }
+            _ => Self::Text((0..self.len()).map(|row| self.text(row)).collect()),
+        }
+    }
+
+    /// Cell at `row` as text.
+    fn text(&self, row: usize) -> Option<String> {
+        match self {
+            Self::Int(values) => values[row].map(|v| v.to_string()),
+            Self::UInt(values) => values[row].map(|v| v.to_string()),
+            Self::Float(values) => values[row].map(|v| v.to_string()),
+            Self::Bool(values) => values[row].map(|v| v.to_string()),
+            Self::Text(values) => values[row].clone(),
+        }
+    }
+
+    /// Cell at `row` as JSON.
+    fn json(&self, row: usize) -> serde_json::Value {
+        match self {
+            Self::Int(values) => values[row].into(),
+            Self::UInt(values) => values[row].into(),
+            Self::Float(values) => values[row].into(),
+            Self::Bool(values) => values[row].into(),
+            Self::Text(values) => values[row].clone().into(),
+        }
+    }
+
+    /// Cell at `row` as CSV, quoted if needed.
+    fn csv(&self, row: usize) -> String {
+        self.text(row)
+            .map(|text| match self {
+                Self::Text(_) => csv_quote(&text),
+                _ => text,
+            })
+            .unwrap_or_default()
+    }
+
+    fn to_arrow(&self) -> Box<dyn Array> {
+        match self {
+            Self::Int(values) => Int64Array::from(values.as_slice()).boxed(),
+            Self::UInt(values) => UInt64Array::from(values.as_slice()).boxed(),
+            Self::Float(values) => Float64Array::from(values.as_slice()).boxed(),
+            Self::Bool(values) => BooleanArray::from(values.as_slice()).boxed(),
+            Self::Text(values) => Utf8Array::<i32>::from(values.as_slice()).boxed(),
+        }
+    }
+}
+
+fn csv_quote(text: &str) -> String {
+    if text.contains([',', '"', '\n', '\r']) {
+        format!("\"{}\"", text.replace('"', "\"\""))
+    } else {
+        text.to_owned()
+    }
+}
+
+impl ExportTable {
+    pub fn from_history(
+        timeline: re_log_types::Timeline,
+        component_name: ComponentName,
+        history: &[history::Sample],
+    ) -> Self {
+        let mut table = Self {
+            columns: vec![
+                Column {
+                    name: timeline.name().to_string(),
+                    values: Values::Int(Vec::new()),
+                },
+                Column {
+                    name: "instance".to_owned(),
+                    values: Values::Int(Vec::new()),
+                },
+            ],
+            num_rows: 0,
+        };
+        let mut column_indices: HashMap<String, usize> = HashMap::default();
+
+        let mut cells = Vec::new();
+        for sample in history {
+            for instance in 0..sample.data.len() {
+                table.columns[0]
+                    .values
+                    .push(Some(Cell::Int(sample.time.as_i64())));
+                table.columns[1]
+                    .values
+                    .push(Some(Cell::Int(instance as i64)));
+
+                cells.clear();
+                flatten(
+                    &*sample.data,
+                    instance,
+                    component_name.short_name().to_owned(),
+                    &mut cells,
+                );
+                for (name, cell) in cells.drain(..) {
+                    let index = *column_indices.entry(name.clone()).or_insert_with(|| {
+                        table.columns.push(Column {
+                            name,
+                            values: Values::new(&cell),
+                        });
+                        table.columns.len() - 1
+                    });
+                    let values = &mut table.columns[index].values;
+                    // Pad columns that are new, or that were missing in earlier rows:
+                    while values.len() < table.num_rows {
+                        values.push(None);
+                    }
+                    values.push(Some(cell));
+                }
+
+                table.num_rows += 1;
+            }
+        }
+
+        for column in &mut table.columns {
+            while column.values.len() < table.num_rows {
+                column.values.push(None);
+            }
+        }
+
+        table
+    }
+
+    pub fn write(
+        &self,
+        format: ExportFormat,
+        path: &Path,
+    ) -> Result<(), Box<dyn std::error::Error>> {
+        let file = std::fs::File::create(path)?;
+        match format {
+            ExportFormat::Csv => self.write_csv(std::io::BufWriter::new(file)),
+            ExportFormat::Json => self.write_json(std::io::BufWriter::new(file)),
+            ExportFormat::Parquet => self.write_parquet(file),
+        }
+    }
+
+    pub fn write_csv(
+        &self,
+        mut out: impl std::io::Write,
+    ) -> Result<(), Box<dyn std::error::Error>> {
+        let header: Vec<String> = self
+            .columns
+            .iter()
+            .map(|column| csv_quote(&column.name))
+            .collect();
+        writeln!(out, "{}", header.join(","))?;
+
+        for row in 0..self.num_rows {
+            let cells: Vec<String> = self
+                .columns
+                .iter()
+                .map(|column| column.values.csv(row))
+                .collect();
+            writeln!(out, "{}", cells.join(","))?;
+        }
+        out.flush()?;
+        Ok(())
+    }
+
+    pub fn write_json(
+        &self,
+        mut out: impl std::io::Write,
+    ) -> Result<(), Box<dyn std::error::Error>> {
+        for row in 0..self.num_rows {
+            let object: serde_json::Map<String, serde_json::Value> = self
+                .columns
+                .iter()
+                .map(|column| (column.name.clone(), column.values.json(row)))
+                .collect();
+            serde_json::to_writer(&mut out, &object)?;
+            writeln!(out)?;
+        }
+        out.flush()?;
+        Ok(())
+    }
+
+    pub fn write_parquet(
+        &self,
+        out: impl std::io::Write,
+    ) -> Result<(), Box<dyn std::error::Error>> {
+        use arrow2::io::parquet::write::{
+            transverse, CompressionOptions, Encoding, FileWriter, RowGroupIterator, Version,
+            WriteOptions,
+        };
+
+        let arrays: Vec<Box<dyn Array>> = self
+            .columns
+            .iter()
+            .map(|column| column.values.to_arrow())
+            .collect();
+        let schema = arrow2::datatypes::Schema::from(
+            self.columns
+                .iter()
+                .zip(&arrays)
+                .map(|(column, array)| {
+                    arrow2::datatypes::Field::new(&column.name, array.data_type().clone(), true)
+                })
+                .collect::<Vec<_>>(),
+        );
+
+        let options = WriteOptions {
+            write_statistics: true,
+            compression: CompressionOptions::Snappy,
+            version: Version::V2,
+            data_pagesize_limit: None,
+        };
+        let encodings = schema
+            .fields
+            .iter()
+            .map(|field| transverse(&field.data_type, |_| Encoding::Plain))
+            .collect();
+        let row_groups = RowGroupIterator::try_new(
+            std::iter::once(Ok(arrow2::chunk::Chunk::new(arrays))),
+            &schema,
+            options,
+            encodings,
+        )?;
+
+        let mut writer = FileWriter::try_new(out, schema, options)?;
+        for row_group in row_groups {
+            writer.write(row_group?)?;
+        }
+        writer.end(None)?;
+        Ok(())
+    }
+}
+
+/// Split the value at `index` into its leaves, named after the path to them.
+fn flatten(array: &dyn Array, index: usize, name: String, out: &mut Vec<(String, Cell)>) {
+    if array.is_null(index) {
+        return;
+    }
+
+    let any = array.as_any();
+    if let Some(array) = any.downcast_ref::<StructArray>() {
+        for (field, values) in array.fields().iter().zip(array.values()) {
+            flatten(&**values, index, format!("{name}.{}", field.name), out);
+        }
+    } else if let Some(array) = any.downcast_ref::<FixedSizeListArray>() {
+        let values = array.value(index);
+        for i in 0..values.len() {
+            flatten(&*values, i, format!("{name}[{i}]"), out);
+        }
+    } else if let Some(cell) = primitive_cell(array, index) {
+        out.push((name, cell));
+    } else if let Some(bytes) = binary(array, index) {
+        out.push((name, Cell::Text(BASE64_STANDARD.encode(bytes))));
+    } else {
+        // Variable-length lists, unions, …
+        let formatted = arrow_format::format_value(array, index, &NESTED_FORMAT);
+        out.push((name, Cell::Text(formatted.text)));
+    }
+}
+
+/// Lists and strings are exported in full. Blobs within them are cut short,
+/// as they would otherwise be written as hex, twice the size of the blob itself.
+const NESTED_FORMAT: arrow_format::FormatOptions = arrow_format::FormatOptions {
+    max_list_items: usize::MAX,
+    max_string_chars: usize::MAX,
+    max_binary_bytes: 64,
+};
+
+fn binary(array: &dyn Array, index: usize) -> Option<&[u8]> {
+    let any = array.as_any();
+    if let Some(array) = any.downcast_ref::<BinaryArray<i32>>() {
+        Some(array.value(index))
+    } else if let Some(array) = any.downcast_ref::<BinaryArray<i64>>() {
+        Some(array.value(index))
+    } else {
+        any.downcast_ref::<FixedSizeBinaryArray>()
+            .map(|array| array.value(index))
+    }
+}
+
+fn primitive_cell(array: &dyn Array, index: usize) -> Option<Cell> {
+    fn value<T: arrow2::types::NativeType>(array: &dyn Array, index: usize) -> Option<T> {
+        array
+            .as_any()
+            .downcast_ref::<PrimitiveArray<T>>()
+            .map(|array| array.value(index))
+    }
+
+    let any = array.as_any();
+    let cell = if let Some(array) = any.downcast_ref::<BooleanArray>() {
+        Cell::Bool(array.value(index))
+    } else if let Some(array) = any.downcast_ref::<Utf8Array<i32>>() {
+        Cell::Text(array.value(index).to_owned())
+    } else if let Some(array) = any.downcast_ref::<Utf8Array<i64>>() {
+        Cell::Text(array.value(index).to_owned())
+    } else if let Some(v) = value::<f32>(array, index) {
+        Cell::Float(v.into())
+    } else if let Some(v) = value::<f64>(array, index) {
+        Cell::Float(v)
+    } else if let Some(v) = value::<arrow2::types::f16>(array, index) {
+        Cell::Float(v.to_f32().into())
+    } else if let Some(v) = value::<i8>(array, index) {
+        Cell::Int(v.into())
+    } else if let Some(v) = value::<i16>(array, index) {
+        Cell::Int(v.into())
+    } else if let Some(v) = value::<i32>(array, index) {
+        Cell::Int(v.into())
+    } else if let Some(v) = value::<i64>(array, index) {
+        Cell::Int(v)
+    } else if let Some(v) = value::<u8>(array, index) {
+        Cell::Int(v.into())
+    } else if let Some(v) = value::<u16>(array, index) {
+        Cell::Int(v.into())
+    } else if let Some(v) = value::<u32>(array, index) {
+        Cell::Int(v.into())
+    } else if let Some(v) = value::<u64>(array, index) {
+        Cell::UInt(v)
+    } else {
+        return None;
+    };
+    Some(cell)
+}
+
+#[cfg(test)]
+mod tests {
+    use re_viewer::external::arrow2::array::{Int32Array, ListArray};
+
+    use super::*;
+
+    fn export_table(name: &str, values: Vec<Box<dyn Array>>) -> ExportTable {
+        let history: Vec<history::Sample> = values
+            .into_iter()
+            .enumerate()
+            .map(|(time, data)| history::Sample {
+                time: re_log_types::TimeInt::new_temporal(time as i64),
+                data,
+            })
+            .collect();
+        ExportTable::from_history(
+            re_log_types::Timeline::new_sequence("frame"),
+            ComponentName::from(name),
+            &history,
+        )
+    }
+
+    fn csv(table: &ExportTable) -> String {
+        let mut out = Vec::new();
+        table.write_csv(&mut out).unwrap();
+        String::from_utf8(out).unwrap()
+    }
+
+    #[test]
+    fn widens_columns() {
+        // Unsigned values that fit stay integers:
+        let table = export_table(
+            "value",
+            vec![
+                Int64Array::from_slice([-1]).boxed(),
+                UInt64Array::from_slice([2]).boxed(),
+            ],
+        );
+        assert!(matches!(&table.columns[2].values, Values::Int(v) if *v == [Some(-1), Some(2)]));
+
+        // Integers of either sign that don't fit become floats:
+        let table = export_table(
+            "value",
+            vec![
+                UInt64Array::from_slice([u64::MAX]).boxed(),
+                Int64Array::from_slice([-1]).boxed(),
+                Float64Array::from_slice([0.5]).boxed(),
+            ],
+        );
+        assert!(matches!(
+            &table.columns[2].values,
+            Values::Float(v) if *v == [Some(u64::MAX as f64), Some(-1.0), Some(0.5)]
+        ));
+
+        // Anything else becomes text, keeping missing values:
+        let table = export_table(
+            "value",
+            vec![
+                Float64Array::from(vec![None]).boxed(),
+                Float64Array::from_slice([1.5]).boxed(),
+                BooleanArray::from_slice([true]).boxed(),
+            ],
+        );
+        assert!(matches!(
+            &table.columns[2].values,
+            Values::Text(v) if *v == [None, Some("1.5".to_owned()), Some("true".to_owned())]
+        ));
+    }
+
+    #[test]
+    fn csv_quoting() {
+        let table = export_table(
+            "note, quoted",
+            vec![
+                Utf8Array::<i32>::from_slice(["plain", "a,b", "say \"hi\"", "two\nlines"]).boxed(),
+            ],
+        );
+        assert_eq!(
+            csv(&table),
+            "frame,instance,\"note, quoted\"\n\
+             0,0,plain\n\
+             0,1,\"a,b\"\n\
+             0,2,\"say \"\"hi\"\"\"\n\
+             0,3,\"two\nlines\"\n"
+        );
+    }
+
+    #[test]
+    fn ndjson() {
+        let fields = vec![
+            arrow2::datatypes::Field::new("x", arrow2::datatypes::DataType::Float64, false),
+            arrow2::datatypes::Field::new("label", arrow2::datatypes::DataType::Utf8, true),
+        ];
+        let array = StructArray::new(
+            arrow2::datatypes::DataType::Struct(std::sync::Arc::new(fields)),
+            vec![
+                Float64Array::from_slice([1.5, 2.0]).boxed(),
+                Utf8Array::<i32>::from(vec![Some("a"), None]).boxed(),
+            ],
+            None,
+        );
+        let table = export_table("point", vec![array.boxed()]);
+
+        let mut out = Vec::new();
+        table.write_json(&mut out).unwrap();
+        let lines: Vec<serde_json::Value> = String::from_utf8(out)
+            .unwrap()
+            .lines()
+            .map(|line| serde_json::from_str(line).unwrap())
+            .collect();
+        assert_eq!(
+            lines,
+            [
+                serde_json::json!({"frame": 0, "instance": 0, "point.x": 1.5, "point.label": "a"}),
+                serde_json::json!({"frame": 0, "instance": 1, "point.x": 2.0, "point.label": null}),
+            ]
+        );
+    }
+
+    #[test]
+    fn blobs() {
+        let blob = vec![0_u8; 1000];
+        let table = export_table(
+            "blob",
+            vec![BinaryArray::<i32>::from_slice([&[0xde_u8, 0xad, 0xbe, 0xef][..], &blob]).boxed()],
+        );
+        let Values::Text(values) = &table.columns[2].values else {
+            panic!("blobs are text");
+        };
+        assert_eq!(values[0].as_deref(), Some("3q2+7w=="));
+        assert_eq!(
+            values[1].as_deref(),
+            Some(BASE64_STANDARD.encode(&blob).as_str())
+        );
+
+        // Lists are written in full, blobs within them cut short:
+        let list = ListArray::<i32>::new(
+            ListArray::<i32>::default_datatype(arrow2::datatypes::DataType::Binary),
+            arrow2::offset::Offsets::try_from(vec![0, 1])
+                .unwrap()
+                .into(),
+            BinaryArray::<i32>::from_slice([&blob]).boxed(),
+            None,
+        );
+        let ints = ListArray::<i32>::new(
+            ListArray::<i32>::default_datatype(arrow2::datatypes::DataType::Int32),
+            arrow2::offset::Offsets::try_from(vec![0, 100])
+                .unwrap()
+                .into(),
+            Int32Array::from_vec((0..100).collect()).boxed(),
+            None,
+        );
+        let table = export_table("list", vec![list.boxed(), ints.boxed()]);
+        let Values::Text(values) = &table.columns[2].values else {
+            panic!("lists are text");
+        };
+        assert_eq!(
+            values[0].as_deref(),
+            Some(format!("[1000 bytes: {}…]", "00".repeat(64)).as_str())
+        );
+        assert_eq!(values[1].as_ref().unwrap().matches(", ").count(), 99);
+    }
+}
@@ -0,0 +1,284 @@
+use std::borrow::Cow;
+
+use re_viewer::external::{
+    arrow2::{
+        self,
+        array::{Array, Float32Array, Float64Array, PrimitiveArray, StructArray},
+        datatypes::{DataType, Field},
+    },
+    re_data_store, re_entity_db, re_log_types,
+    re_types::{self, datatypes::TensorBuffer, Loggable as _},
+};
+
+use crate::history;
+
+/// Name of the component 2D grids are logged as, e.g. by [`crate::pbstream`].
+pub fn tensor_component_name() -> re_types::ComponentName {
+    re_types::components::TensorData::name()
+}
+
+/// Logged next to the tensor of a Cartographer grid, to place its cells in the world.
+pub const LIMITS_COMPONENT_NAME: &str = "cartographer.components.GridLimits";
+
+/// Logged next to the tensor of a TSDF grid, whose values are signed distances in meters.
+pub const TRUNCATION_COMPONENT_NAME: &str = "cartographer.components.TruncationDistance";
+
+/// Grids are shown by [`crate::grid_view`], which only picks up entities with this
+/// archetype's indicator, so it has to be logged next to the tensor.
+pub struct GridArchetype;
+
+impl re_types::Archetype for GridArchetype {
+    type Indicator = GridArchetypeIndicator;
+
+    fn name() -> re_types::ArchetypeName {
+        "cartographer.archetypes.OccupancyGrid".into()
+    }
+
+    fn display_name() -> &'static str {
+        "Occupancy grid"
+    }
+
+    fn indicator() -> re_types::MaybeOwnedComponentBatch<'static> {
+        static INDICATOR: GridArchetypeIndicator = GridArchetypeIndicator::DEFAULT;
+        re_types::MaybeOwnedComponentBatch::Ref(&INDICATOR)
+    }
+
+    fn required_components() -> Cow<'static, [re_types::ComponentName]> {
+        vec![tensor_component_name()].into()
+    }
+}
+
+pub type GridArchetypeIndicator = re_types::GenericIndicatorComponent<GridArchetype>;
+
+/// Where a grid logged by [`crate::pbstream`] lies in the world,
+/// as in Cartographer's `MapLimits`.
+///
+/// Cartographer grids are laid out with x and y swapped and flipped:
+/// row `r` is at `max_x - (r + 0.5) * resolution`,
+/// and column `c` at `max_y - (c + 0.5) * resolution`.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct GridLimits {
+    /// Size of a cell, in meters.
+    pub resolution: f64,
+
+    /// Corner of the grid with the largest coordinates.
+    pub max: [f64; 2],
+}
+
+impl GridLimits {
+    /// The limits logged next to the grid at `entity_path`, if any.
+    pub fn latest_at(
+        entity_db: &re_entity_db::EntityDb,
+        query: &re_data_store::LatestAtQuery,
+        entity_path: &re_log_types::EntityPath,
+    ) -> Option<Self> {
+        history::latest_at(entity_db, query, entity_path, LIMITS_COMPONENT_NAME.into())
+            .and_then(|data| limits_from_arrow(&*data))
+            .and_then(|limits| limits.into_iter().next())
+    }
+
+    /// World position of the center of a cell.
+    pub fn cell_center(&self, row: usize, col: usize) -> [f64; 2] {
+        [
+            self.max[0] - (row as f64 + 0.5) * self.resolution,
+            self.max[1] - (col as f64 + 0.5) * self.resolution,
+        ]
+    }
+}
+
+const LIMITS_FIELDS: [&str; 3] = ["resolution", "max_x", "max_y"];
+
+fn limits_data_type() -> DataType {
+    DataType::Struct(std::sync::Arc::new(
+        LIMITS_FIELDS
+            .iter()
+            .map(|name| Field::new(*name, DataType::Float64, false))
+            .collect(),
+    ))
+}
+
+pub fn limits_to_arrow(limits: &[GridLimits]) -> Box<dyn Array> {
+    let floats = |f: fn(&GridLimits) -> f64| -> Box<dyn Array> {
+        Float64Array::from_vec(limits.iter().map(f).collect()).boxed()
+    };
+    let values = vec![
+        floats(|l| l.resolution),
+        floats(|l| l.max[0]),
+        floats(|l| l.max[1]),
+    ];
+    StructArray::new(limits_data_type(), values, None).boxed()
+}
+
+pub fn limits_from_arrow(array: &dyn Array) -> Option<Vec<GridLimits>> {
+    let array = array.as_any().downcast_ref::<StructArray>()?;
+    let floats = |name| history::struct_field::<Float64Array>(array, name);
+    let (resolution, max_x, max_y) = (floats("resolution")?, floats("max_x")?, floats("max_y")?);
+    Some(
+        (0..array.len())
+            .map(|i| GridLimits {
+                resolution: resolution.value(i),
+                max: [max_x.value(i), max_y.value(i)],
+            })
+            .collect(),
+    )
+}
+
+/// A batch of grid limits, for logging with the SDK.
+pub struct GridLimitsBatch<'a>(pub &'a [GridLimits]);
+
+impl re_types::LoggableBatch for GridLimitsBatch<'_> {
+    type Name = re_types::ComponentName;
+
+    fn name(&self) -> Self::Name {
+        LIMITS_COMPONENT_NAME.into()
+    }
+
+    fn num_instances(&self) -> usize {
+        self.0.len()
+    }
+
+    fn arrow_field(&self) -> arrow2::datatypes::Field {
+        Field::new(LIMITS_COMPONENT_NAME, limits_data_type(), false)
+    }
+
+    fn to_arrow(&self) -> re_types::SerializationResult<Box<dyn Array>> {
+        Ok(limits_to_arrow(self.0))
+    }
+}
+
+impl re_types::ComponentBatch for GridLimitsBatch<'_> {}
+
+/// The truncation distance of a TSDF grid, in meters, for logging with the SDK.
+pub struct TruncationDistanceBatch(pub f32);
+
+impl re_types::LoggableBatch for TruncationDistanceBatch {
+    type Name = re_types::ComponentName;
+
+    fn name(&self) -> Self::Name {
+        TRUNCATION_COMPONENT_NAME.into()
+    }
+
+    fn num_instances(&self) -> usize {
+        1
+    }
+
+    fn arrow_field(&self) -> arrow2::datatypes::Field {
+        Field::new(TRUNCATION_COMPONENT_NAME, DataType::Float32, false)
+    }
+
+    fn to_arrow(&self) -> re_types::SerializationResult<Box<dyn Array>> {
+        Ok(Float32Array::from_vec(vec![self.0]).boxed())
+    }
+}
+
+impl re_types::ComponentBatch for TruncationDistanceBatch {}
+
+/// A 2D probability or TSDF grid, row major.
+pub struct Grid {
+    pub width: usize,
+    pub height: usize,
+
+    /// Probability of each cell being occupied, or the signed distance to the closest
+    /// surface for TSDF grids. `NaN` for unknown cells.
+    pub values: Vec<f32>,
+
+    /// Set for TSDF grids, see [`Self::values`].
+    pub truncation_distance: Option<f32>,
+
+    /// Set for grids logged by Cartographer, `None` for other grids,
+    /// e.g. ROS `OccupancyGrid`s.
+    pub limits: Option<GridLimits>,
+
+    /// Row 0 is at the lowest y, as in ROS `OccupancyGrid`s,
+    /// rather than at the top as in images.
+    pub bottom_up: bool,
+}
+
+impl Grid {
+    /// The grid logged to `entity_path` at the time of the query, if any.
+    pub fn latest_at(
+        entity_db: &re_entity_db::EntityDb,
+        query: &re_data_store::LatestAtQuery,
+        entity_path: &re_log_types::EntityPath,
+    ) -> Result<Self, String> {
+        let data = history::latest_at(entity_db, query, entity_path, tensor_component_name())
+            .ok_or("No grid at this time")?;
+        let tensor = re_types::components::TensorData::from_arrow(&*data)
+            .map_err(|err| err.to_string())?
+            .into_iter()
+            .next()
+            .ok_or("Empty grid")?;
+        let mut grid = Self::from_tensor(&tensor.0)?;
+
+        grid.limits = GridLimits::latest_at(entity_db, query, entity_path);
+        grid.truncation_distance = history::latest_at(
+            entity_db,
+            query,
+            entity_path,
+            TRUNCATION_COMPONENT_NAME.into(),
+        )
+        .and_then(|data| {
+            let data = data.as_any().downcast_ref::<PrimitiveArray<f32>>()?;
+            data.iter().next().flatten().copied()
+        });
+        Ok(grid)
+    }
+
+    /// Interpret a tensor as a probability grid,
+    /// without [`Self::limits`] and [`Self::truncation_distance`].
+    ///
+    /// Supported encodings:
+    /// * floats: occupancy probability, `NaN` for unknown (as logged by [`crate::pbstream`]),
+    /// * `i8`: ROS `OccupancyGrid` values, i.e. percent with `-1` for unknown,
+    /// * `u8`: grayscale image with black as occupied and white as free.
+    pub fn from_tensor(tensor: &re_types::datatypes::TensorData) -> Result<Self, String> {
+        let (height, width) = match tensor.shape.as_slice() {
+            [height, width] => (height.size, width.size),
+            [height, width, channels] if channels.size == 1 => (height.size, width.size),
+            _ => {
+                return Err(format!(
+                    "Expected a 2D tensor, got shape {:?}",
+                    tensor.shape
+                ))
+            }
+        };
+
+        let values: Vec<f32> = match &tensor.buffer {
+            TensorBuffer::F32(values) => values.to_vec(),
+            TensorBuffer::F64(values) => values.iter().map(|&v| v as f32).collect(),
+            TensorBuffer::I8(values) => values
+                .iter()
+                .map(|&v| {
+                    if v < 0 {
+                        f32::NAN
+                    } else {
+                        f32::from(v) / 100.0
+                    }
+                })
+                .collect(),
+            TensorBuffer::U8(values) => {
+                values.iter().map(|&v| 1.0 - f32::from(v) / 255.0).collect()
+            }
+            _ => {
+                return Err(format!(
+                    "Unsupported grid data type {}",
+                    tensor.buffer.dtype()
+                ))
+            }
+        };
+
+        let (width, height) = (width as usize, height as usize);
+        if values.len() != width * height {
+            return Err("Grid size does not match its shape".to_owned());
+        }
+
+        Ok(Self {
+            width,
+            height,
+            values,
+            truncation_distance: None,
+            limits: None,
+            bottom_up: matches!(tensor.buffer, TensorBuffer::I8(_)),
+        })
+    }
+}
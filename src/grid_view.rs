@@ -0,0 +1,444 @@
+//! A space view for 2D probability and TSDF grids, with colormaps and a cursor read-out.
+//!
+//! Rerun shows grids as plain images, which hides e.g. the difference between unknown
+//! cells and cells with a probability of 0.5. This view shows the values themselves.
+
+use re_viewer::external::{
+    egui, re_data_store, re_entity_db,
+    re_log_types::{EntityPath, RowId},
+    re_renderer,
+    re_types::{self, SpaceViewClassIdentifier},
+    re_ui, re_viewer_context,
+};
+use re_viewer_context::{
+    IdentifiedViewSystem, PerSystemEntities, SpaceViewClass, SpaceViewClassLayoutPriority,
+    SpaceViewClassRegistryError, SpaceViewId, SpaceViewSpawnHeuristics, SpaceViewState,
+    SpaceViewStateExt as _, SpaceViewSystemExecutionError, SpaceViewSystemRegistrator,
+    SystemExecutionOutput, ViewContext, ViewContextCollection, ViewQuery, ViewSystemIdentifier,
+    ViewerContext, VisualizerQueryInfo, VisualizerSystem,
+};
+
+use crate::{
+    grid::{self, Grid, GridArchetype},
+    history, viewer_state,
+};
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum Colormap {
+    /// Grayscale, white for free and black for occupied, like ROS maps.
+    Probability,
+
+    /// Log-odds `ln(p / (1 - p))`, blue for misses and red for hits.
+    Odds,
+}
+
+impl Colormap {
+    const ALL: [Self; 2] = [Self::Probability, Self::Odds];
+
+    fn name(self) -> &'static str {
+        match self {
+            Self::Probability => "Probability",
+            Self::Odds => "Hit/miss odds",
+        }
+    }
+}
+
+/// Settings of one grid view.
+pub struct GridViewState {
+    /// For probability grids. TSDF grids are always shown as signed distances,
+    /// blue behind and red in front of surfaces, saturated at the truncation distance.
+    colormap: Colormap,
+
+    /// Log-odds shown at full saturation by [`Colormap::Odds`].
+    max_log_odds: f32,
+
+    /// Draw unknown cells transparent instead of gray.
+    mask_unknown: bool,
+
+    /// The grid shown when there is more than one.
+    selected: Option<EntityPath>,
+
+    /// The grid shown, decoded only when it changes.
+    grid: Option<(GridKey, Result<Grid, String>)>,
+
+    texture: Option<(TextureKey, egui::TextureHandle)>,
+}
+
+impl Default for GridViewState {
+    fn default() -> Self {
+        Self {
+            colormap: Colormap::Probability,
+            max_log_odds: 4.0,
+            mask_unknown: true,
+            selected: None,
+            grid: None,
+            texture: None,
+        }
+    }
+}
+
+impl SpaceViewState for GridViewState {
+    fn as_any(&self) -> &dyn std::any::Any {
+        self
+    }
+
+    fn as_any_mut(&mut self) -> &mut dyn std::any::Any {
+        self
+    }
+}
+
+/// Identifies a logged grid: it only changes when logged again, in a new row.
+#[derive(Clone, Debug, PartialEq)]
+struct GridKey {
+    entity_path: EntityPath,
+    tensor_row_id: RowId,
+    limits_row_id: Option<RowId>,
+}
+
+/// Everything the texture depends on.
+#[derive(Clone, PartialEq)]
+struct TextureKey {
+    grid: GridKey,
+    colormap: Colormap,
+    max_log_odds: f32,
+    mask_unknown: bool,
+}
+
+impl GridViewState {
+    fn color(&self, grid: &Grid, value: f32) -> egui::Color32 {
+        if value.is_nan() {
+            return if self.mask_unknown {
+                egui::Color32::TRANSPARENT
+            } else {
+                egui::Color32::from_gray(128)
+            };
+        }
+        if let Some(truncation_distance) = grid.truncation_distance {
+            return diverging(value / truncation_distance);
+        }
+        match self.colormap {
+            Colormap::Probability => {
+                egui::Color32::from_gray(((1.0 - value.clamp(0.0, 1.0)) * 255.0) as u8)
+            }
+            Colormap::Odds => {
+                let p = value.clamp(1e-6, 1.0 - 1e-6);
+                diverging((p / (1.0 - p)).ln() / self.max_log_odds)
+            }
+        }
+    }
+
+    /// The grid logged in the rows of `key`, decoding it if it changed.
+    fn update_grid(
+        &mut self,
+        entity_db: &re_entity_db::EntityDb,
+        query: &re_data_store::LatestAtQuery,
+        key: &GridKey,
+    ) {
+        if self.grid.as_ref().map(|(cached, _)| cached) != Some(key) {
+            let grid = Grid::latest_at(entity_db, query, &key.entity_path);
+            self.grid = Some((key.clone(), grid));
+        }
+    }
+
+    fn texture(&mut self, ctx: &egui::Context, key: &GridKey, grid: &Grid) -> egui::TextureId {
+        let key = TextureKey {
+            grid: key.clone(),
+            colormap: self.colormap,
+            max_log_odds: self.max_log_odds,
+            mask_unknown: self.mask_unknown,
+        };
+        if let Some((cached, texture)) = &self.texture {
+            if *cached == key {
+                return texture.id();
+            }
+        }
+
+        let image = egui::ColorImage {
+            size: [grid.width, grid.height],
+            pixels: grid.values.iter().map(|&v| self.color(grid, v)).collect(),
+        };
+        let texture = ctx.load_texture(
+            format!("occupancy_grid:{}", key.grid.entity_path),
+            image,
+            egui::TextureOptions::NEAREST,
+        );
+        let id = texture.id();
+        self.texture = Some((key, texture));
+        id
+    }
+}
+
+/// Blue for `-1`, white for `0`, red for `1`.
+fn diverging(t: f32) -> egui::Color32 {
+    let t = t.clamp(-1.0, 1.0);
+    let fade = (255.0 * (1.0 - t.abs())) as u8;
+    if t < 0.0 {
+        egui::Color32::from_rgb(fade, fade, 255)
+    } else {
+        egui::Color32::from_rgb(255, fade, fade)
+    }
+}
+
+/// Finds the grids to show, leaving the decoding to the view.
+#[derive(Default)]
+pub struct GridVisualizer {
+    grids: Vec<GridKey>,
+    query: Option<re_data_store::LatestAtQuery>,
+}
+
+impl IdentifiedViewSystem for GridVisualizer {
+    fn identifier() -> ViewSystemIdentifier {
+        "OccupancyGrid".into()
+    }
+}
+
+impl VisualizerSystem for GridVisualizer {
+    fn visualizer_query_info(&self) -> VisualizerQueryInfo {
+        VisualizerQueryInfo::from_archetype::<GridArchetype>()
+    }
+
+    fn execute(
+        &mut self,
+        ctx: &ViewContext<'_>,
+        query: &ViewQuery<'_>,
+        _context_systems: &ViewContextCollection,
+    ) -> Result<Vec<re_renderer::QueueableDrawData>, SpaceViewSystemExecutionError> {
+        let latest_at = ctx.current_query();
+        for data_result in query.iter_visible_data_results(ctx, Self::identifier()) {
+            let entity_path = &data_result.entity_path;
+            let Some(tensor_row_id) = history::latest_at_row_id(
+                ctx.recording(),
+                &latest_at,
+                entity_path,
+                grid::tensor_component_name(),
+            ) else {
+                continue;
+            };
+            self.grids.push(GridKey {
+                entity_path: entity_path.clone(),
+                tensor_row_id,
+                limits_row_id: history::latest_at_row_id(
+                    ctx.recording(),
+                    &latest_at,
+                    entity_path,
+                    grid::LIMITS_COMPONENT_NAME.into(),
+                ),
+            });
+        }
+        self.query = Some(latest_at);
+        Ok(Vec::new())
+    }
+
+    fn as_any(&self) -> &dyn std::any::Any {
+        self
+    }
+
+    fn as_fallback_provider(&self) -> &dyn re_viewer_context::ComponentFallbackProvider {
+        self
+    }
+}
+
+re_viewer_context::impl_component_fallback_provider!(GridVisualizer => []);
+
+/// Shows probability and TSDF grids, e.g. Cartographer submaps or ROS occupancy grids.
+#[derive(Default)]
+pub struct OccupancyGridSpaceView;
+
+impl SpaceViewClass for OccupancyGridSpaceView {
+    fn identifier() -> SpaceViewClassIdentifier {
+        "OccupancyGrid".into()
+    }
+
+    fn display_name(&self) -> &'static str {
+        "Occupancy grid"
+    }
+
+    fn icon(&self) -> &'static re_ui::Icon {
+        &re_ui::icons::SPACE_VIEW_2D
+    }
+
+    fn help_text(&self, _egui_ctx: &egui::Context) -> egui::WidgetText {
+        "Shows 2D probability grids with a colormap, and TSDF grids as signed distances.\n\n\
+         Hover a cell to read its coordinates and value. \
+         Unknown cells can be masked out."
+            .into()
+    }
+
+    fn on_register(
+        &self,
+        system_registry: &mut SpaceViewSystemRegistrator<'_>,
+    ) -> Result<(), SpaceViewClassRegistryError> {
+        system_registry.register_visualizer::<GridVisualizer>()
+    }
+
+    fn new_state(&self) -> Box<dyn SpaceViewState> {
+        Box::<GridViewState>::default()
+    }
+
+    fn layout_priority(&self) -> SpaceViewClassLayoutPriority {
+        SpaceViewClassLayoutPriority::High
+    }
+
+    fn on_frame_start(
+        &self,
+        ctx: &ViewerContext<'_>,
+        _state: &mut dyn SpaceViewState,
+        _ent_paths: &PerSystemEntities,
+    ) {
+        // Called for every grid view in the viewport, shown or not:
+        viewer_state::on_frame_start(ctx);
+    }
+
+    fn spawn_heuristics(&self, _ctx: &ViewerContext<'_>) -> SpaceViewSpawnHeuristics {
+        // Grids are images too, so only show this view when asked to, e.g. by our blueprint.
+        SpaceViewSpawnHeuristics::default()
+    }
+
+    fn selection_ui(
+        &self,
+        _ctx: &ViewerContext<'_>,
+        ui: &mut egui::Ui,
+        state: &mut dyn SpaceViewState,
+        _space_origin: &EntityPath,
+        _space_view_id: SpaceViewId,
+    ) -> Result<(), SpaceViewSystemExecutionError> {
+        let state = state.downcast_mut::<GridViewState>()?;
+
+        egui::Grid::new("occupancy_grid_settings")
+            .num_columns(2)
+            .show(ui, |ui| {
+                ui.label("Colormap")
+                    .on_hover_text("TSDF grids are always shown as signed distances");
+                egui::ComboBox::from_id_source("occupancy_grid_colormap")
+                    .selected_text(state.colormap.name())
+                    .show_ui(ui, |ui| {
+                        for colormap in Colormap::ALL {
+                            ui.selectable_value(&mut state.colormap, colormap, colormap.name());
+                        }
+                    });
+                ui.end_row();
+
+                match state.colormap {
+                    Colormap::Probability => {}
+                    Colormap::Odds => {
+                        ui.label("Max log-odds");
+                        ui.add(
+                            egui::DragValue::new(&mut state.max_log_odds)
+                                .speed(0.1)
+                                .range(0.1..=20.0),
+                        );
+                        ui.end_row();
+                    }
+                }
+
+                ui.label("Unknown cells");
+                ui.checkbox(&mut state.mask_unknown, "Mask");
+                ui.end_row();
+            });
+        Ok(())
+    }
+
+    fn ui(
+        &self,
+        ctx: &ViewerContext<'_>,
+        ui: &mut egui::Ui,
+        state: &mut dyn SpaceViewState,
+        _query: &ViewQuery<'_>,
+        system_output: SystemExecutionOutput,
+    ) -> Result<(), SpaceViewSystemExecutionError> {
+        let visualizer = system_output.view_systems.get::<GridVisualizer>()?;
+        let grids = &visualizer.grids;
+        let state = state.downcast_mut::<GridViewState>()?;
+
+        let Some(latest_at) = &visualizer.query else {
+            return Ok(());
+        };
+        if grids.is_empty() {
+            ui.centered_and_justified(|ui| {
+                ui.label("No 2D grids in this view.");
+            });
+            return Ok(());
+        }
+
+        if 1 < grids.len() {
+            let selected = state
+                .selected
+                .get_or_insert_with(|| grids[0].entity_path.clone());
+            egui::ComboBox::from_id_source("occupancy_grid_selected")
+                .selected_text(selected.to_string())
+                .show_ui(ui, |ui| {
+                    for key in grids {
+                        let entity_path = &key.entity_path;
+                        ui.selectable_value(selected, entity_path.clone(), entity_path.to_string());
+                    }
+                });
+        }
+        let key = grids
+            .iter()
+            .find(|key| Some(&key.entity_path) == state.selected.as_ref())
+            .unwrap_or(&grids[0]);
+        let entity_path = &key.entity_path;
+
+        // Take the grid out of the state so we can update the texture while showing it:
+        state.update_grid(ctx.recording(), latest_at, key);
+        let Some((grid_key, grid)) = state.grid.take() else {
+            return Ok(());
+        };
+        let grid = match grid {
+            Ok(grid) => grid,
+            Err(err) => {
+                // Tensors that aren't 2D grids can't be shown:
+                ui.label(format!("{entity_path}: {err}"));
+                state.grid = Some((grid_key, Err(err)));
+                return Ok(());
+            }
+        };
+
+        // Fit the grid into the view, keeping square cells:
+        let available = ui.available_rect_before_wrap();
+        let cell_size = (available.width() / grid.width as f32)
+            .min((available.height() - ui.spacing().interact_size.y) / grid.height as f32)
+            .max(f32::EPSILON);
+        let size = egui::vec2(grid.width as f32, grid.height as f32) * cell_size;
+        let (rect, response) = ui.allocate_exact_size(size, egui::Sense::hover());
+
+        let texture = state.texture(ui.ctx(), key, &grid);
+        ui.painter().image(
+            texture,
+            rect,
+            egui::Rect::from_min_max(egui::Pos2::ZERO, egui::pos2(1.0, 1.0)),
+            egui::Color32::WHITE,
+        );
+
+        let readout = response.hover_pos().and_then(|pos| {
+            let cell = (pos - rect.min) / cell_size;
+            let (x, y) = (cell.x.floor() as usize, cell.y.floor() as usize);
+            let value = *grid
+                .values
+                .get(y * grid.width + x)
+                .filter(|_| x < grid.width)?;
+            let position = match grid.limits {
+                Some(limits) => {
+                    let [world_x, world_y] = limits.cell_center(y, x);
+                    format!("cell ({x}, {y}) at x {world_x:.2} m, y {world_y:.2} m")
+                }
+                None => format!("cell ({x}, {y})"),
+            };
+            Some(if value.is_nan() {
+                format!("{position}: unknown")
+            } else if grid.truncation_distance.is_some() {
+                format!("{position}: {value:.3} m")
+            } else {
+                format!("{position}: {value:.3}")
+            })
+        });
+        ui.label(
+            readout.unwrap_or_else(|| {
+                format!("{entity_path}: {} × {} cells", grid.width, grid.height)
+            }),
+        );
+
+        state.grid = Some((grid_key, Ok(grid)));
+        Ok(())
+    }
+}
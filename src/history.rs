@@ -0,0 +1,133 @@
+use re_viewer::external::{
+    arrow2, re_data_store, re_entity_db, re_log_types, re_types::ComponentName,
+};
+
+/// The value of a component at the time of the query.
+pub fn latest_at(
+    entity_db: &re_entity_db::EntityDb,
+    query: &re_data_store::LatestAtQuery,
+    entity_path: &re_log_types::EntityPath,
+    component_name: ComponentName,
+) -> Option<Box<dyn arrow2::array::Array>> {
+    let results =
+        entity_db
+            .query_caches()
+            .latest_at(entity_db.store(), query, entity_path, [component_name]);
+    results
+        .components
+        .get(&component_name)
+        .and_then(|result| result.raw(entity_db.resolver(), component_name))
+}
+
+/// The row the value of [`latest_at`] was logged in, to tell cheaply whether it changed.
+pub fn latest_at_row_id(
+    entity_db: &re_entity_db::EntityDb,
+    query: &re_data_store::LatestAtQuery,
+    entity_path: &re_log_types::EntityPath,
+    component_name: ComponentName,
+) -> Option<re_log_types::RowId> {
+    let results =
+        entity_db
+            .query_caches()
+            .latest_at(entity_db.store(), query, entity_path, [component_name]);
+    results
+        .components
+        .get(&component_name)
+        .map(|result| result.index().1)
+}
+
+/// One logged value of a component.
+pub struct Sample {
+    pub time: re_log_types::TimeInt,
+
+    /// All instances of the component, e.g. all points of a point cloud.
+    pub data: Box<dyn arrow2::array::Array>,
+}
+
+/// Every value of a component logged on the given timeline, in time order.
+pub fn component_history(
+    entity_db: &re_entity_db::EntityDb,
+    timeline: re_log_types::Timeline,
+    entity_path: &re_log_types::EntityPath,
+    component_name: ComponentName,
+) -> Vec<Sample> {
+    component_history_in(
+        entity_db,
+        timeline,
+        re_log_types::ResolvedTimeRange::EVERYTHING,
+        entity_path,
+        component_name,
+    )
+}
+
+/// The values of a component logged within `time_range`, in time order.
+pub fn component_history_in(
+    entity_db: &re_entity_db::EntityDb,
+    timeline: re_log_types::Timeline,
+    time_range: re_log_types::ResolvedTimeRange,
+    entity_path: &re_log_types::EntityPath,
+    component_name: ComponentName,
+) -> Vec<Sample> {
+    let query = re_data_store::RangeQuery::new(timeline, time_range);
+
+    entity_db
+        .store()
+        .range(&query, entity_path, [component_name])
+        .filter_map(|(time, _row_id, [cell])| {
+            Some(Sample {
+                time,
+                data: cell?.to_arrow(),
+            })
+        })
+        .collect()
+}
+
+/// The field called `name` of a struct array, if it has the expected type.
+pub fn struct_field<'a, T: arrow2::array::Array>(
+    array: &'a arrow2::array::StructArray,
+    name: &str,
+) -> Option<&'a T> {
+    let index = array.fields().iter().position(|field| field.name == name)?;
+    array.values()[index].as_any().downcast_ref()
+}
+
+/// The value at `index` as a number, if it is one.
+///
+/// Single-field structs and one-element lists are unwrapped,
+/// so e.g. a `Scalar` component works as well as a plain `f64`.
+pub fn scalar(array: &dyn arrow2::array::Array, index: usize) -> Option<f64> {
+    use arrow2::array::{FixedSizeListArray, PrimitiveArray, StructArray};
+
+    fn value<T: arrow2::types::NativeType + Into<f64>>(
+        array: &dyn arrow2::array::Array,
+        index: usize,
+    ) -> Option<f64> {
+        use arrow2::array::Array as _;
+
+        let array = array.as_any().downcast_ref::<PrimitiveArray<T>>()?;
+        array.is_valid(index).then(|| array.value(index).into())
+    }
+
+    let any = array.as_any();
+    if let Some(array) = any.downcast_ref::<StructArray>() {
+        match array.values() {
+            [values] => scalar(&**values, index),
+            _ => None,
+        }
+    } else if let Some(array) = any.downcast_ref::<FixedSizeListArray>() {
+        if array.size() == 1 {
+            scalar(&*array.value(index), 0)
+        } else {
+            None
+        }
+    } else {
+        value::<f64>(array, index)
+            .or_else(|| value::<f32>(array, index))
+            .or_else(|| value::<i32>(array, index))
+            .or_else(|| value::<u32>(array, index))
+            .or_else(|| value::<i16>(array, index))
+            .or_else(|| value::<u16>(array, index))
+            .or_else(|| value::<i8>(array, index))
+            .or_else(|| value::<u8>(array, index))
+    }
+}
@@ -0,0 +1,340 @@
+//! Live statistics of the data arriving at the viewer, per client and per entity.
+
+use std::{
+    collections::{BTreeMap, VecDeque},
+    sync::{Arc, Mutex},
+    time::{Duration, Instant},
+};
+
+use re_viewer::external::{
+    egui,
+    re_log_types::{self, DataRow, EntityPath},
+    re_types::SizeBytes as _,
+};
+
+/// Rates are averaged over this long.
+const WINDOW: Duration = Duration::from_secs(5);
+
+/// One message (per client) or one row (per entity).
+struct Event {
+    received: Instant,
+    bytes: u64,
+
+    /// From log time to arrival at the viewer, if the data has a log time.
+    latency: Option<Duration>,
+}
+
+#[derive(Default)]
+struct EventWindow {
+    events: VecDeque<Event>,
+}
+
+impl EventWindow {
+    fn push(&mut self, event: Event) {
+        self.events.push_back(event);
+        self.prune(Instant::now());
+    }
+
+    fn prune(&mut self, now: Instant) {
+        while self
+            .events
+            .front()
+            .is_some_and(|event| WINDOW < now - event.received)
+        {
+            self.events.pop_front();
+        }
+    }
+}
+
+/// Rates and latency over the last [`WINDOW`].
+#[derive(Clone, Copy, Debug, Default)]
+pub struct Rates {
+    pub per_second: f64,
+    pub bytes_per_second: f64,
+    pub mean_latency: Option<Duration>,
+    pub max_latency: Option<Duration>,
+}
+
+impl Rates {
+    fn new(window: &EventWindow) -> Self {
+        let seconds = WINDOW.as_secs_f64();
+        let latencies: Vec<Duration> = window.events.iter().filter_map(|e| e.latency).collect();
+        Self {
+            per_second: window.events.len() as f64 / seconds,
+            bytes_per_second: window.events.iter().map(|e| e.bytes as f64).sum::<f64>() / seconds,
+            mean_latency: (!latencies.is_empty())
+                .then(|| latencies.iter().sum::<Duration>() / latencies.len() as u32),
+            max_latency: latencies.iter().max().copied(),
+        }
+    }
+}
+
+/// Rates per client or per entity.
+pub type RatesPer<K> = Vec<(K, Rates)>;
+
+#[derive(Default)]
+struct Windows {
+    clients: BTreeMap<String, EventWindow>,
+    entities: BTreeMap<EntityPath, EventWindow>,
+}
+
+/// Counts what SDK clients send through the [`crate::recordings::Recordings`] watcher.
+#[derive(Clone, Default)]
+pub struct IngestMonitor {
+    windows: Arc<Mutex<Windows>>,
+}
+
+impl IngestMonitor {
+    /// Only data sent by SDK clients over TCP is counted.
+    ///
+    /// Files and `.pbstream`s are loaded in one go and were logged long ago,
+    /// so their rates and latency mean nothing.
+    pub fn is_monitored(source: &re_smart_channel::SmartMessageSource) -> bool {
+        matches!(
+            source,
+            re_smart_channel::SmartMessageSource::TcpClient { .. }
+        )
+    }
+
+    /// Note a message from `source`, given its decoded rows.
+    pub fn record(&self, source: &re_smart_channel::SmartMessageSource, rows: &[DataRow]) {
+        let now = Instant::now();
+        let now_nanos = re_log_types::Time::now().nanos_since_epoch();
+        let latency_of = |timepoint: &re_log_types::TimePoint| {
+            let log_time = timepoint.get(&re_log_types::Timeline::log_time())?;
+            u64::try_from(now_nanos - log_time.as_i64())
+                .ok()
+                .map(Duration::from_nanos)
+        };
+
+        let mut windows = self.windows.lock().unwrap();
+        let mut message_bytes = 0;
+        let mut message_latency = None;
+        for row in rows {
+            let bytes = row.total_size_bytes();
+            let latency = latency_of(&row.timepoint);
+            message_bytes += bytes;
+            message_latency = message_latency.max(latency);
+            windows
+                .entities
+                .entry(row.entity_path.clone())
+                .or_default()
+                .push(Event {
+                    received: now,
+                    bytes,
+                    latency,
+                });
+        }
+        windows
+            .clients
+            .entry(source.to_string())
+            .or_default()
+            .push(Event {
+                received: now,
+                bytes: message_bytes,
+                latency: message_latency,
+            });
+    }
+
+    /// Current rates per client and per entity.
+    pub fn rates(&self) -> (RatesPer<String>, RatesPer<EntityPath>) {
+        let now = Instant::now();
+        let mut windows = self.windows.lock().unwrap();
+        let Windows { clients, entities } = &mut *windows;
+        let clients = clients
+            .iter_mut()
+            .map(|(client, window)| {
+                window.prune(now);
+                (client.clone(), Rates::new(window))
+            })
+            .collect();
+        let entities = entities
+            .iter_mut()
+            .map(|(entity_path, window)| {
+                window.prune(now);
+                (entity_path.clone(), Rates::new(window))
+            })
+            .collect();
+        (clients, entities)
+    }
+}
+
+/// Expected rates to warn about, set in the panel.
+pub struct IngestSettings {
+    /// Expected rows per second for some entities.
+    pub expected_rates: BTreeMap<EntityPath, f64>,
+
+    /// Warn when an entity arrives at less than this fraction of its expected rate.
+    pub tolerance: f64,
+}
+
+impl Default for IngestSettings {
+    fn default() -> Self {
+        Self {
+            expected_rates: Default::default(),
+            tolerance: 0.8,
+        }
+    }
+}
+
+/// [`IngestSettings`] as kept across sessions.
+#[derive(serde::Deserialize, serde::Serialize)]
+pub struct PersistedIngestSettings {
+    expected_rates: BTreeMap<String, f64>,
+    tolerance: f64,
+}
+
+impl IngestSettings {
+    pub fn persisted(&self) -> PersistedIngestSettings {
+        PersistedIngestSettings {
+            expected_rates: self
+                .expected_rates
+                .iter()
+                .map(|(entity_path, rate)| (entity_path.to_string(), *rate))
+                .collect(),
+            tolerance: self.tolerance,
+        }
+    }
+
+    pub fn restore(&mut self, persisted: PersistedIngestSettings) {
+        self.expected_rates = persisted
+            .expected_rates
+            .iter()
+            .map(|(entity_path, rate)| (EntityPath::from(entity_path.as_str()), *rate))
+            .collect();
+        self.tolerance = persisted.tolerance;
+    }
+}
+
+fn format_latency(latency: Option<Duration>) -> String {
+    latency.map_or_else(
+        || "–".to_owned(),
+        |latency| format!("{:.1} ms", latency.as_secs_f64() * 1e3),
+    )
+}
+
+/// Tables of message rates, data rates and latency, with warnings for slow entities.
+pub fn ingest_ui(ui: &mut egui::Ui, monitor: &IngestMonitor, settings: &mut IngestSettings) {
+    // Rates change even without input:
+    ui.ctx().request_repaint_after(Duration::from_millis(500));
+
+    let (clients, entities) = monitor.rates();
+    let slow: Vec<bool> = entities
+        .iter()
+        .map(|(entity_path, rates)| {
+            settings
+                .expected_rates
+                .get(entity_path)
+                .is_some_and(|expected| rates.per_second < settings.tolerance * expected)
+        })
+        .collect();
+
+    let num_slow = slow.iter().filter(|&&slow| slow).count();
+    if 0 < num_slow {
+        ui.colored_label(
+            ui.visuals().warn_fg_color,
+            format!("⚠ {num_slow} entities below their expected rate"),
+        );
+    }
+
+    ui.strong("Clients");
+    egui::Grid::new("ingest_clients")
+        .num_columns(4)
+        .striped(true)
+        .show(ui, |ui| {
+            for header in ["Source", "msg/s", "Data", "Latency"] {
+                ui.strong(header);
+            }
+            ui.end_row();
+            for (client, rates) in &clients {
+                ui.label(client);
+                ui.monospace(format!("{:.1}", rates.per_second));
+                ui.monospace(format!(
+                    "{}/s",
+                    re_format::format_bytes(rates.bytes_per_second)
+                ));
+                ui.monospace(format_latency(rates.mean_latency));
+                ui.end_row();
+            }
+        });
+
+    ui.horizontal(|ui| {
+        ui.strong("Entities");
+        ui.label("warn below");
+        ui.add(
+            egui::DragValue::new(&mut settings.tolerance)
+                .speed(0.01)
+                .range(0.0..=1.0)
+                .custom_formatter(|v, _| format!("{:.0}%", v * 100.0)),
+        )
+        .on_hover_text("Fraction of the expected rate");
+    });
+
+    let row_height = ui.spacing().interact_size.y;
+    egui_extras::TableBuilder::new(ui)
+        .striped(true)
+        .resizable(true)
+        .max_scroll_height(300.0)
+        .column(egui_extras::Column::remainder().clip(true))
+        .columns(egui_extras::Column::auto(), 5)
+        .header(row_height, |mut header| {
+            for name in ["Entity", "rows/s", "Data", "Latency", "Max", "Expected"] {
+                header.col(|ui| {
+                    ui.strong(name);
+                });
+            }
+        })
+        .body(|body| {
+            body.rows(row_height, entities.len(), |mut row| {
+                let (entity_path, rates) = &entities[row.index()];
+                let slow = slow[row.index()];
+                row.col(|ui| {
+                    ui.label(entity_path.to_string());
+                });
+                row.col(|ui| {
+                    let text = format!("{:.1}", rates.per_second);
+                    if slow {
+                        ui.colored_label(ui.visuals().warn_fg_color, text);
+                    } else {
+                        ui.monospace(text);
+                    }
+                });
+                row.col(|ui| {
+                    ui.monospace(format!(
+                        "{}/s",
+                        re_format::format_bytes(rates.bytes_per_second)
+                    ));
+                });
+                row.col(|ui| {
+                    ui.monospace(format_latency(rates.mean_latency));
+                });
+                row.col(|ui| {
+                    ui.monospace(format_latency(rates.max_latency));
+                });
+                row.col(|ui| {
+                    let mut expected = settings
+                        .expected_rates
+                        .get(entity_path)
+                        .copied()
+                        .unwrap_or(0.0);
+                    let response = ui
+                        .add(
+                            egui::DragValue::new(&mut expected)
+                                .speed(0.1)
+                                .range(0.0..=f64::INFINITY)
+                                .suffix(" Hz"),
+                        )
+                        .on_hover_text("Expected rate, 0 for none");
+                    if response.changed() {
+                        if 0.0 < expected {
+                            settings
+                                .expected_rates
+                                .insert(entity_path.clone(), expected);
+                        } else {
+                            settings.expected_rates.remove(entity_path);
+                        }
+                    }
+                });
+            });
+        });
+}
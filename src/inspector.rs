@@ -0,0 +1,154 @@
+//! Browsing the logged data, for panels that want to show it the same way as the
+//! built-in entity list.
+
+use re_viewer::external::{egui, re_data_store, re_entity_db, re_log_types, re_types};
+
+use crate::{
+    component_table, entity_tree, export, grid, history, panel, point_cloud, ros_map, scalar_plot,
+    time_cursor::TimeCursor, trajectory, trajectory_export,
+};
+
+type PinnedComponents = Vec<(re_log_types::EntityPath, re_types::ComponentName)>;
+
+/// Show the content of the log database, below the pinned components.
+pub fn entity_db_ui(
+    ui: &mut egui::Ui,
+    entity_db: &re_entity_db::EntityDb,
+    time_cursor: &TimeCursor,
+    entity_tree: &mut entity_tree::EntityTreeState,
+    selection: &mut Option<re_log_types::EntityPath>,
+    pinned_components: &mut PinnedComponents,
+) {
+    if let Some(store_info) = entity_db.store_info() {
+        ui.label(format!("Application ID: {}", store_info.application_id));
+    }
+
+    let query = time_cursor.latest_at_query();
+
+    ui.separator();
+
+    if !pinned_components.is_empty() {
+        ui.strong("Pinned:");
+        let mut unpinned = None;
+        for (index, (entity_path, component_name)) in pinned_components.iter().enumerate() {
+            let id = ui.make_persistent_id((entity_path, component_name, "pinned"));
+            egui::collapsing_header::CollapsingState::load_with_default_open(ui.ctx(), id, true)
+                .show_header(ui, |ui| {
+                    ui.label(format!("{entity_path}: {}", component_name.short_name()));
+                    if ui.small_button("Unpin").clicked() {
+                        unpinned = Some(index);
+                    }
+                })
+                .body(|ui| {
+                    component_ui(ui, entity_db, &query, entity_path, *component_name);
+                });
+        }
+        if let Some(index) = unpinned {
+            pinned_components.remove(index);
+        }
+        ui.separator();
+    }
+
+    ui.strong("Entities:");
+
+    entity_tree::entity_tree_ui(
+        ui,
+        entity_db,
+        time_cursor.timeline,
+        entity_tree,
+        selection,
+        |ui, entity_path| entity_ui(ui, entity_db, &query, entity_path, pinned_components),
+    );
+}
+
+/// Everything logged to an entity at the time of the query, with exports and statistics
+/// for the kinds of data Cartographer logs.
+///
+/// Each component can be pinned, to keep it in view above the entities.
+pub fn entity_ui(
+    ui: &mut egui::Ui,
+    entity_db: &re_entity_db::EntityDb,
+    query: &re_data_store::LatestAtQuery,
+    entity_path: &re_log_types::EntityPath,
+    pinned_components: &mut PinnedComponents,
+) {
+    // Each entity can have many components (e.g. position, color, radius, …):
+    if let Some(components) = entity_db
+        .store()
+        .all_components(&query.timeline(), entity_path)
+    {
+        // Occupancy grids can be saved as maps for navigation:
+        if components.contains(&grid::tensor_component_name()) {
+            ros_map::export_ui(ui, entity_db, query, entity_path);
+        }
+
+        // Point clouds get statistics, to spot degraded scans:
+        if components.contains(&point_cloud::position_component_name()) {
+            egui::CollapsingHeader::new("Point cloud statistics")
+                .id_source((entity_path, "point_cloud_statistics"))
+                .show(ui, |ui| {
+                    point_cloud::statistics_ui(ui, entity_db, query, entity_path);
+                });
+        }
+
+        // Poses can be saved for trajectory evaluation tools:
+        if components.contains(&trajectory::transform_component_name()) {
+            trajectory_export::export_ui(ui, entity_db, query, entity_path);
+        }
+
+        for component in components {
+            let is_pinned = pinned_components.contains(&(entity_path.clone(), component));
+            let id = ui.make_persistent_id((entity_path, component));
+            egui::collapsing_header::CollapsingState::load_with_default_open(ui.ctx(), id, false)
+                .show_header(ui, |ui| {
+                    ui.label(component.to_string());
+                    if ui
+                        .small_button(if is_pinned { "Unpin" } else { "Pin" })
+                        .on_hover_text("Keep this component at the top of the tab")
+                        .clicked()
+                    {
+                        panel::toggle_pinned(pinned_components, entity_path, component);
+                    }
+                })
+                .body(|ui| {
+                    component_ui(ui, entity_db, query, entity_path, component);
+                });
+        }
+    }
+}
+
+/// The value of one component at the time of the query, with its history.
+pub fn component_ui(
+    ui: &mut egui::Ui,
+    entity_db: &re_entity_db::EntityDb,
+    query: &re_data_store::LatestAtQuery,
+    entity_path: &re_log_types::EntityPath,
+    component_name: re_types::ComponentName,
+) {
+    if let Some(data) = history::latest_at(entity_db, query, entity_path, component_name) {
+        export::export_ui(ui, entity_db, query.timeline(), entity_path, component_name);
+
+        // Numeric components (e.g. scan matcher scores) get a plot of their history:
+        if data.len() == 1 && history::scalar(&*data, 0).is_some() {
+            egui::CollapsingHeader::new("Plot")
+                .id_source((entity_path, component_name, "plot"))
+                .show(ui, |ui| {
+                    let at = query.at();
+                    let cursor = (at != re_log_types::TimeInt::MAX).then_some(at);
+                    scalar_plot::scalar_plot_ui(
+                        ui,
+                        entity_db,
+                        query.timeline(),
+                        entity_path,
+                        component_name,
+                        cursor,
+                    );
+                });
+        }
+
+        // Show all the instances (e.g. all the points in the point cloud):
+        component_table::component_table_ui(ui, (entity_path, component_name), &*data);
+    } else {
+        ui.weak("Not logged at this time");
+    }
+}
@@ -0,0 +1,54 @@
+//! Rerun viewer with Cartographer-specific panels.
+//!
+//! Embed it in other tools with [`CartographerRerun::builder`], adding receivers for the
+//! data and your own panels implementing [`CartographerPanel`]:
+//!
+//! ```no_run
+//! # fn main() -> Result<(), Box<dyn std::error::Error>> {
+//! let rx = re_sdk_comms::serve("0.0.0.0", 9876, Default::default())?;
+//! cartographer_rerun::CartographerRerun::builder()
+//!     .with_receiver(rx)
+//!     .run("Cartographer - Rerun")?;
+//! # Ok(())
+//! # }
+//! ```
+
+mod app;
+mod arrow_format;
+mod blueprint;
+mod builtin_panels;
+mod component_table;
+mod constraint_inspector;
+mod constraints;
+pub mod control;
+pub mod entity_tree;
+mod evaluation;
+mod export;
+mod grid;
+mod grid_view;
+mod history;
+mod ingest;
+pub mod inspector;
+pub mod loader;
+mod memory;
+mod panel;
+mod pbstream;
+mod persistence;
+mod point_cloud;
+pub mod recorder;
+mod recordings;
+mod ros_map;
+mod scalar_plot;
+mod stats;
+mod submaps;
+pub mod time_cursor;
+mod trajectory;
+mod trajectory_export;
+mod viewer_state;
+
+pub use app::{CartographerRerun, CartographerRerunBuilder};
+pub use panel::{CartographerPanel, PanelContext};
+
+/// Panels get `egui` and the Rerun types through this, e.g. `re_viewer::external::egui`,
+/// so that they use the same versions as the viewer.
+pub use re_viewer;
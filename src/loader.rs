@@ -0,0 +1,58 @@
+use std::path::{Path, PathBuf};
+
+use re_viewer::external::{re_log, re_log_types::LogMsg};
+
+/// Load a recording (`.rrd`) or a Cartographer state (`.pbstream`) file.
+pub fn load_file(
+    path: &Path,
+) -> Result<re_smart_channel::Receiver<LogMsg>, Box<dyn std::error::Error>> {
+    match path.extension().and_then(|ext| ext.to_str()) {
+        Some("pbstream") => crate::pbstream::load_pbstream_file(path),
+        _ => load_rrd_file(path),
+    }
+}
+
+/// Stream the contents of an `.rrd` file to the viewer.
+///
+/// The file is decoded on a background thread, so this returns immediately.
+/// Errors while decoding are logged, and whatever was read up to that point is kept.
+pub fn load_rrd_file(
+    path: &Path,
+) -> Result<re_smart_channel::Receiver<LogMsg>, Box<dyn std::error::Error>> {
+    let file = std::fs::File::open(path)
+        .map_err(|err| format!("Failed to open {}: {err}", path.display()))?;
+    let decoder = re_log_encoding::decoder::Decoder::new(
+        re_log_encoding::decoder::VersionPolicy::Warn,
+        std::io::BufReader::new(file),
+    )
+    .map_err(|err| format!("Failed to read {}: {err}", path.display()))?;
+
+    let path: PathBuf = path.to_owned();
+    let (tx, rx) = re_smart_channel::smart_channel(
+        re_smart_channel::SmartMessageSource::File(path.clone()),
+        re_smart_channel::SmartChannelSource::File(path.clone()),
+    );
+
+    std::thread::Builder::new()
+        .name(format!("load {}", path.display()))
+        .spawn(move || {
+            for msg in decoder {
+                match msg {
+                    Ok(msg) => {
+                        if tx.send(msg).is_err() {
+                            // The viewer hung up.
+                            return;
+                        }
+                    }
+                    Err(err) => {
+                        re_log::warn!("Failed to decode {}: {err}", path.display());
+                        break;
+                    }
+                }
+            }
+            re_log::debug!("Finished loading {}", path.display());
+            tx.quit(None).ok();
+        })?;
+
+    Ok(rx)
+}
@@ -1,6 +1,8 @@
-use re_viewer::external::{
-    arrow2, eframe, egui, re_data_store, re_entity_db, re_log, re_log_types, re_memory, re_types,
-};
+use std::path::PathBuf;
+
+use cartographer_rerun::{recorder, CartographerRerun};
+use clap::Parser as _;
+use re_viewer::external::{re_log, re_memory};
 
 // By using `re_memory::AccountingAllocator` Rerun can keep track of exactly how much memory it is using,
 // and prune the data store when it goes above a certain limit.
@@ -9,6 +11,70 @@ use re_viewer::external::{
 static GLOBAL: re_memory::AccountingAllocator<mimalloc::MiMalloc> =
     re_memory::AccountingAllocator::new(mimalloc::MiMalloc);
 
+/// Rerun viewer with Cartographer-specific panels.
+#[derive(Debug, clap::Parser)]
+#[clap(author, version, about)]
+struct Args {
+    /// What address to listen on for connections from the logging SDKs.
+    #[clap(long, default_value = "0.0.0.0")]
+    bind: String,
+
+    /// What TCP port to listen on for connections from the logging SDKs.
+    #[clap(long, default_value_t = re_sdk_comms::DEFAULT_SERVER_PORT)]
+    port: u16,
+
+    /// Don't log when SDK clients connect or disconnect.
+    #[clap(long)]
+    quiet: bool,
+
+    /// Maximum number of seconds incoming log messages may be buffered before they are
+    /// flushed to the viewer.
+    #[clap(long, default_value_t = re_sdk_comms::ServerOptions::default().max_latency_sec)]
+    max_latency_sec: f32,
+
+    /// An upper limit on how much memory the viewer should use.
+    ///
+    /// When this limit is reached, the oldest data will be dropped.
+    /// Examples: `--memory-limit 16GB` or `--memory-limit 50%`.
+    #[clap(long, default_value = "75%")]
+    memory_limit: String,
+
+    /// Title of the viewer window.
+    #[clap(long, default_value = "Cartographer - Rerun")]
+    window_title: String,
+
+    /// Run without a window, writing all incoming data to `.rrd` files in this directory.
+    #[clap(long, value_name = "DIR")]
+    record_to: Option<PathBuf>,
+
+    /// In `--record-to` mode: start a new file once the current one reaches this size,
+    /// e.g. `--rotate-size 2GB`.
+    #[clap(long, requires = "record_to")]
+    rotate_size: Option<String>,
+
+    /// In `--record-to` mode: start a new file after this many seconds.
+    #[clap(long, requires = "record_to")]
+    rotate_secs: Option<u64>,
+
+    /// In `--record-to` mode: only keep this many files, deleting the oldest ones.
+    #[clap(long, requires = "record_to")]
+    max_files: Option<usize>,
+
+    /// Keep Rerun's heuristic layout instead of sending the Cartographer blueprint
+    /// for each new application.
+    #[clap(long)]
+    no_default_blueprint: bool,
+
+    /// Take commands from scripts (open, set time, select, screenshot, export) as JSON
+    /// posted to this port on 127.0.0.1, with the token the viewer logs at startup.
+    #[clap(long, value_name = "PORT", conflicts_with = "record_to")]
+    control_port: Option<u16>,
+
+    /// `.rrd` recordings or Cartographer `.pbstream` files to open at startup.
+    #[clap(conflicts_with = "record_to")]
+    files: Vec<PathBuf>,
+}
+
 fn main() -> Result<(), Box<dyn std::error::Error>> {
     // Direct calls using the `log` crate to stderr. Control with `RUST_LOG=debug` etc.
     re_log::setup_logging();
@@ -17,176 +83,47 @@ fn main() -> Result<(), Box<dyn std::error::Error>> {
     // them to Rerun analytics (if the `analytics` feature is on in `Cargo.toml`).
     re_crash_handler::install_crash_handlers(re_viewer::build_info());
 
+    let args = Args::parse();
+
     // Listen for TCP connections from Rerun's logging SDKs.
     // There are other ways of "feeding" the viewer though - all you need is a `re_smart_channel::Receiver`.
-    let rx = re_sdk_comms::serve(
-        "0.0.0.0",
-        re_sdk_comms::DEFAULT_SERVER_PORT,
-        Default::default(),
-    )?;
-
-    let native_options = eframe::NativeOptions {
-        viewport: egui::ViewportBuilder::default().with_app_id("cartographer_rerun"),
-        ..re_viewer::native::eframe_options(None)
+    let server_options = re_sdk_comms::ServerOptions {
+        quiet: args.quiet,
+        max_latency_sec: args.max_latency_sec,
     };
-
-    let startup_options = re_viewer::StartupOptions::default();
-
-    // This is used for analytics, if the `analytics` feature is on in `Cargo.toml`
-    let app_env = re_viewer::AppEnvironment::Custom("Cartographer Rerun Wrapper".to_owned());
-
-    let window_title = "Cartographer - Rerun";
-    eframe::run_native(
-        window_title,
-        native_options,
-        Box::new(move |cc| {
-            re_viewer::customize_eframe_and_setup_renderer(cc)?;
-
-            let mut rerun_app = re_viewer::App::new(
-                re_viewer::build_info(),
-                &app_env,
-                startup_options,
-                cc.egui_ctx.clone(),
-                cc.storage,
-            );
-            rerun_app.add_receiver(rx);
-            Ok(Box::new(CartographerRerun { rerun_app }))
-        }),
-    )?;
-
-    Ok(())
-}
-
-struct CartographerRerun {
-    rerun_app: re_viewer::App,
-}
-
-impl eframe::App for CartographerRerun {
-    fn save(&mut self, storage: &mut dyn eframe::Storage) {
-        // Store viewer state on disk
-        self.rerun_app.save(storage);
+    if let Some(dir) = args.record_to {
+        let max_file_bytes = args
+            .rotate_size
+            .map(|size| {
+                re_format::parse_bytes(&size)
+                    .and_then(|bytes| u64::try_from(bytes).ok())
+                    .ok_or_else(|| format!("Bad --rotate-size: {size:?}"))
+            })
+            .transpose()?;
+        let options = recorder::RecorderOptions {
+            dir,
+            max_file_bytes,
+            max_file_duration: args.rotate_secs.map(std::time::Duration::from_secs),
+            max_files: args.max_files,
+        };
+        let rx = re_sdk_comms::serve(&args.bind, args.port, server_options)?;
+        return recorder::run(&rx, &options);
     }
 
-    /// Called whenever we need repainting, which could be 60 Hz.
-    fn update(&mut self, ctx: &egui::Context, frame: &mut eframe::Frame) {
-        // First add our panel(s):
-        egui::SidePanel::right("Cartographer")
-            .default_width(200.0)
-            .show(ctx, |ui| {
-                self.ui(ui);
-            });
-
-        // Now show the Rerun Viewer in the remaining space:
-        self.rerun_app.update(ctx, frame);
-    }
-}
-
-impl CartographerRerun {
-    fn ui(&mut self, ui: &mut egui::Ui) {
-        ui.add_space(4.0);
-        ui.vertical_centered(|ui| {
-            ui.strong("Cartographer");
-        });
-        ui.separator();
-
+    let memory_limit = re_memory::MemoryLimit::parse(&args.memory_limit)
+        .map_err(|err| format!("Bad --memory-limit: {err}"))?;
 
-        if let Some(entity_db) = self.rerun_app.recording_db() {
-            entity_db_ui(ui, entity_db);
-        } else {
-            ui.label("No log database loaded yet.");
-        }
+    let mut builder = CartographerRerun::builder()
+        .with_memory_limit(memory_limit)
+        .with_default_blueprint(!args.no_default_blueprint)
+        .with_receiver(re_sdk_comms::serve(&args.bind, args.port, server_options)?);
+    for path in &args.files {
+        builder = builder.with_file(path)?;
     }
-}
-
-/// Show the content of the log database.
-fn entity_db_ui(ui: &mut egui::Ui, entity_db: &re_entity_db::EntityDb) {
-    if let Some(store_info) = entity_db.store_info() {
-        ui.label(format!("Application ID: {}", store_info.application_id));
+    if let Some(port) = args.control_port {
+        builder = builder.with_control_server(format!("127.0.0.1:{port}"));
     }
+    builder.run(&args.window_title)?;
 
-    // There can be many timelines, but the `log_time` timeline is always there:
-    let timeline = re_log_types::Timeline::log_time();
-
-    ui.separator();
-
-    ui.strong("Entities:");
-
-    egui::ScrollArea::vertical()
-        .auto_shrink([false, true])
-        .show(ui, |ui| {
-            for entity_path in entity_db.entity_paths() {
-                ui.collapsing(entity_path.to_string(), |ui| {
-                    entity_ui(ui, entity_db, timeline, entity_path);
-                });
-            }
-        });
-}
-
-fn entity_ui(
-    ui: &mut egui::Ui,
-    entity_db: &re_entity_db::EntityDb,
-    timeline: re_log_types::Timeline,
-    entity_path: &re_log_types::EntityPath,
-) {
-    // Each entity can have many components (e.g. position, color, radius, …):
-    if let Some(components) = entity_db.store().all_components(&timeline, entity_path) {
-        for component in components {
-            ui.collapsing(component.to_string(), |ui| {
-                component_ui(ui, entity_db, timeline, entity_path, component);
-            });
-        }
-    }
-}
-
-fn component_ui(
-    ui: &mut egui::Ui,
-    entity_db: &re_entity_db::EntityDb,
-    timeline: re_log_types::Timeline,
-    entity_path: &re_log_types::EntityPath,
-    component_name: re_types::ComponentName,
-) {
-    // You can query the data for any time point, but for now
-    // just show the last value logged for each component:
-    let query = re_data_store::LatestAtQuery::latest(timeline);
-
-    let results = entity_db.query_caches().latest_at(
-        entity_db.store(),
-        &query,
-        entity_path,
-        [component_name],
-    );
-    let component = results
-        .components
-        .get(&component_name)
-        .and_then(|result| result.raw(entity_db.resolver(), component_name));
-
-    if let Some(data) = component {
-        egui::ScrollArea::vertical()
-            .auto_shrink([false, true])
-            .show(ui, |ui| {
-                // Iterate over all the instances (e.g. all the points in the point cloud):
-
-                let num_instances = data.len();
-                for i in 0..num_instances {
-                    ui.label(format_arrow(&*data.sliced(i, 1)));
-                }
-            });
-    };
+    Ok(())
 }
-
-fn format_arrow(value: &dyn arrow2::array::Array) -> String {
-    use re_types::SizeBytes as _;
-
-    let bytes = value.total_size_bytes();
-    if bytes < 256 {
-        // Print small items:
-        let mut string = String::new();
-        let display = arrow2::array::get_display(value, "null");
-        if display(&mut string, 0).is_ok() {
-            return string;
-        }
-    }
-
-    // Fallback:
-    format!("{bytes} bytes")
-}
\ No newline at end of file
@@ -0,0 +1,263 @@
+//! Memory use, and pruning of incoming data so long sessions stay within the memory limit.
+//!
+//! When the memory limit (`--memory-limit`) is reached, the viewer drops the oldest data of
+//! all recordings. The [`PruningPolicy`] thins out chosen entities as they arrive instead,
+//! so the data that matters is kept for longer.
+
+use std::{
+    collections::{BTreeMap, BTreeSet, HashMap},
+    sync::{Arc, Mutex},
+};
+
+use re_viewer::external::{
+    egui, re_entity_db, re_log, re_log_types,
+    re_log_types::{ArrowMsg, DataRow, DataTable, EntityPath},
+    re_memory,
+};
+
+/// How to thin out incoming data, per entity.
+#[derive(Clone, Debug, Default)]
+pub struct PruningPolicy {
+    /// Keep only every Nth row of these entities, e.g. every 10th scan.
+    pub keep_every_nth: BTreeMap<EntityPath, u32>,
+
+    /// Keep only the latest row of these entities, by logging them as static.
+    ///
+    /// Static data is shown at all times, and hides the rows of the entity stored before.
+    /// Those are not freed right away, but are the oldest data, so are the first to go
+    /// when the memory limit is reached.
+    pub latest_only: BTreeSet<EntityPath>,
+}
+
+impl PruningPolicy {
+    fn is_empty(&self) -> bool {
+        self.keep_every_nth.is_empty() && self.latest_only.is_empty()
+    }
+
+    fn affects(&self, entity_path: &EntityPath) -> bool {
+        self.keep_every_nth.contains_key(entity_path) || self.latest_only.contains(entity_path)
+    }
+}
+
+/// Applies the [`PruningPolicy`] in the [`crate::recordings::Recordings`] watcher.
+#[derive(Clone, Default)]
+pub struct Pruner {
+    policy: Arc<Mutex<PruningPolicy>>,
+
+    /// Rows seen so far per entity, for [`PruningPolicy::keep_every_nth`].
+    counters: Arc<Mutex<HashMap<EntityPath, u64>>>,
+}
+
+impl Pruner {
+    pub fn policy(&self) -> PruningPolicy {
+        self.policy.lock().unwrap().clone()
+    }
+
+    pub fn set_policy(&self, policy: PruningPolicy) {
+        *self.policy.lock().unwrap() = policy;
+    }
+
+    /// Is there anything to prune, i.e. is it worth decoding messages for [`Self::apply`]?
+    pub fn is_active(&self) -> bool {
+        !self.policy.lock().unwrap().is_empty()
+    }
+
+    /// Drop or rewrite rows of the message as the policy says.
+    ///
+    /// `rows` are the decoded rows of `arrow_msg`, shared with the [`crate::ingest::IngestMonitor`].
+    /// Returns `None` if nothing is left of the message.
+    pub fn apply(&self, arrow_msg: ArrowMsg, rows: Vec<DataRow>) -> Option<ArrowMsg> {
+        let policy = self.policy.lock().unwrap().clone();
+        if !rows.iter().any(|row| policy.affects(&row.entity_path)) {
+            return Some(arrow_msg);
+        }
+
+        let mut counters = self.counters.lock().unwrap();
+        let rows: Vec<_> = rows
+            .into_iter()
+            .filter_map(|mut row| {
+                if let Some(&n) = policy.keep_every_nth.get(&row.entity_path) {
+                    let counter = counters.entry(row.entity_path.clone()).or_default();
+                    let keep = *counter % u64::from(n.max(1)) == 0;
+                    *counter += 1;
+                    if !keep {
+                        return None;
+                    }
+                }
+                if policy.latest_only.contains(&row.entity_path) {
+                    row.timepoint = re_log_types::TimePoint::default();
+                }
+                Some(row)
+            })
+            .collect();
+        if rows.is_empty() {
+            return None;
+        }
+
+        let table = DataTable::from_rows(re_log_types::TableId::new(), rows);
+        match table.to_arrow_msg() {
+            Ok(pruned) => Some(pruned),
+            Err(err) => {
+                re_log::warn!("Failed to prune message: {err}");
+                Some(arrow_msg)
+            }
+        }
+    }
+}
+
+/// Per-entity sizes, measured on request since it means going through all the data.
+#[derive(Default)]
+pub struct MemoryState {
+    sizes: Option<(re_log_types::StoreId, Vec<(EntityPath, u64)>)>,
+}
+
+/// Estimated size of all the data of the entity: its static data, and its temporal data
+/// on the timeline with the most of it.
+///
+/// Rows logged on several timelines share their data, so the timelines don't add up.
+fn entity_size(entity_db: &re_entity_db::EntityDb, entity_path: &EntityPath) -> u64 {
+    let store = entity_db.store();
+    let stats = |timeline| store.entity_stats(timeline, entity_path.hash());
+    let temporal = entity_db
+        .timelines()
+        .map(|timeline| stats(*timeline).size_bytes)
+        .max()
+        .unwrap_or(0);
+    // The static data is the same on every timeline:
+    stats(re_log_types::Timeline::log_time()).static_size_bytes + temporal
+}
+
+/// Allocator usage against the memory limit, per-entity sizes and the pruning policy.
+pub fn memory_ui(
+    ui: &mut egui::Ui,
+    entity_db: Option<&re_entity_db::EntityDb>,
+    memory_limit: re_memory::MemoryLimit,
+    pruner: &Pruner,
+    state: &mut MemoryState,
+) {
+    let memory_use = re_memory::MemoryUse::capture();
+    let counted = memory_use.counted.or(memory_use.resident);
+    match (counted, memory_limit.max_bytes) {
+        (Some(used), Some(limit)) => {
+            ui.add(
+                egui::ProgressBar::new(used as f32 / limit as f32).text(format!(
+                    "{} of {}",
+                    re_format::format_bytes(used as f64),
+                    re_format::format_bytes(limit as f64)
+                )),
+            )
+            .on_hover_text(
+                "At the limit, the oldest data of all recordings is dropped.\n\
+                 Set with --memory-limit.",
+            );
+        }
+        (Some(used), None) => {
+            ui.label(format!(
+                "{} used, no limit",
+                re_format::format_bytes(used as f64)
+            ))
+            .on_hover_text("Set one with --memory-limit");
+        }
+        (None, _) => {
+            ui.label("Memory use unknown");
+        }
+    }
+    if let Some(resident) = memory_use.resident {
+        ui.label(format!(
+            "Resident: {}",
+            re_format::format_bytes(resident as f64)
+        ));
+    }
+    if let Some(allocs) = re_memory::accounting_allocator::global_allocs() {
+        ui.label(format!(
+            "Allocations: {} ({})",
+            re_format::format_uint(allocs.count),
+            re_format::format_bytes(allocs.size as f64)
+        ));
+    }
+
+    let Some(entity_db) = entity_db else {
+        return;
+    };
+    ui.separator();
+
+    let is_current = |store_id: &re_log_types::StoreId| store_id == entity_db.store_id();
+    if ui
+        .button("Measure entities")
+        .on_hover_text("Estimate the size of the data of each entity")
+        .clicked()
+        || state.sizes.as_ref().is_some_and(|(id, _)| !is_current(id))
+    {
+        let mut sizes: Vec<(EntityPath, u64)> = entity_db
+            .entity_paths()
+            .into_iter()
+            .map(|entity_path| (entity_path.clone(), entity_size(entity_db, entity_path)))
+            .filter(|(_, size)| 0 < *size)
+            .collect();
+        sizes.sort_by_key(|(_, size)| std::cmp::Reverse(*size));
+        state.sizes = Some((entity_db.store_id().clone(), sizes));
+    }
+    let Some((_, sizes)) = &state.sizes else {
+        return;
+    };
+
+    let mut policy = pruner.policy();
+    let row_height = ui.spacing().interact_size.y;
+    egui_extras::TableBuilder::new(ui)
+        .striped(true)
+        .resizable(true)
+        .max_scroll_height(300.0)
+        .column(egui_extras::Column::remainder().clip(true))
+        .columns(egui_extras::Column::auto(), 3)
+        .header(row_height, |mut header| {
+            for name in ["Entity", "Size", "Keep every", "Latest only"] {
+                header.col(|ui| {
+                    ui.strong(name);
+                });
+            }
+        })
+        .body(|body| {
+            body.rows(row_height, sizes.len(), |mut row| {
+                let (entity_path, size) = &sizes[row.index()];
+                row.col(|ui| {
+                    ui.label(entity_path.to_string());
+                });
+                row.col(|ui| {
+                    ui.monospace(re_format::format_bytes(*size as f64));
+                });
+                row.col(|ui| {
+                    let mut n = policy.keep_every_nth.get(entity_path).copied().unwrap_or(1);
+                    let response = ui
+                        .add(egui::DragValue::new(&mut n).range(1..=1000).prefix("1 in "))
+                        .on_hover_text("Drop all but every Nth incoming row, e.g. for scans");
+                    if response.changed() {
+                        if 1 < n {
+                            policy.keep_every_nth.insert(entity_path.clone(), n);
+                        } else {
+                            policy.keep_every_nth.remove(entity_path);
+                        }
+                    }
+                });
+                row.col(|ui| {
+                    let mut latest_only = policy.latest_only.contains(entity_path);
+                    if ui
+                        .checkbox(&mut latest_only, "")
+                        .on_hover_text(
+                            "Log incoming rows as static, so only the latest is kept.\n\
+                             It is then shown at all times.",
+                        )
+                        .changed()
+                    {
+                        if latest_only {
+                            policy.latest_only.insert(entity_path.clone());
+                        } else {
+                            policy.latest_only.remove(entity_path);
+                        }
+                    }
+                });
+            });
+        });
+    ui.label("Pruning applies to data arriving from now on.");
+
+    pruner.set_policy(policy);
+}
@@ -0,0 +1,255 @@
+use std::collections::BTreeMap;
+
+use re_viewer::external::{
+    egui, re_entity_db, re_log,
+    re_log_types::{EntityPath, LogMsg},
+    re_types::ComponentName,
+    re_viewer_context::{self, SystemCommandSender as _},
+};
+
+use crate::time_cursor::TimeCursor;
+
+/// A tab of the Cartographer side panel, added with
+/// [`crate::CartographerRerunBuilder::with_panel`].
+pub trait CartographerPanel {
+    /// Stable identifier, used to remember whether the tab is open across sessions.
+    fn id(&self) -> &str {
+        self.name()
+    }
+
+    /// Title of the tab.
+    fn name(&self) -> &str;
+
+    /// Show the panel. Called every frame while its tab is active.
+    fn ui(&mut self, ui: &mut egui::Ui, ctx: &mut PanelContext<'_>);
+
+    /// State to keep across sessions, e.g. filters.
+    fn save_state(&self) -> Option<serde_json::Value> {
+        None
+    }
+
+    /// Restore what [`Self::save_state`] returned in an earlier session.
+    fn load_state(&mut self, _state: serde_json::Value) {}
+}
+
+/// What panels get to see of the viewer.
+pub struct PanelContext<'a> {
+    pub rerun_app: &'a re_viewer::App,
+
+    /// The recording the viewer shows, if any.
+    pub entity_db: Option<&'a re_entity_db::EntityDb>,
+
+    /// The point in time the Cartographer panel shows data for.
+    /// Set whenever there is an [`Self::entity_db`].
+    pub time_cursor: Option<TimeCursor>,
+
+    pub(crate) selection: &'a mut Option<EntityPath>,
+
+    pub(crate) pinned_components: &'a mut Vec<(EntityPath, ComponentName)>,
+
+    pub(crate) log_msgs: &'a mut Vec<LogMsg>,
+}
+
+impl PanelContext<'_> {
+    /// Select things in the viewer, change the active recording etc.
+    pub fn command_sender(&self) -> &re_viewer_context::CommandSender {
+        &self.rerun_app.command_sender
+    }
+
+    /// The entity selected in the Cartographer panel, shared by all tabs.
+    pub fn selection(&self) -> Option<&EntityPath> {
+        self.selection.as_ref()
+    }
+
+    /// Select the entity in the Cartographer panel and in the viewer.
+    pub fn select(&mut self, entity_path: EntityPath) {
+        select_in_viewer(self.command_sender(), &entity_path);
+        *self.selection = Some(entity_path);
+    }
+
+    /// Components shown at the top of the entities tab, in the order they were pinned.
+    pub fn pinned_components(&self) -> &[(EntityPath, ComponentName)] {
+        self.pinned_components
+    }
+
+    /// Pin a component, or unpin it if it is pinned.
+    pub fn toggle_pinned(&mut self, entity_path: &EntityPath, component_name: ComponentName) {
+        toggle_pinned(self.pinned_components, entity_path, component_name);
+    }
+
+    /// Add data computed by the panel to the viewer, e.g. trajectory errors.
+    ///
+    /// The messages need a [`re_viewer::external::re_log_types::LogMsg::SetStoreInfo`]
+    /// first unless they go to an existing recording.
+    pub fn send_log_msgs(&mut self, log_msgs: impl IntoIterator<Item = LogMsg>) {
+        self.log_msgs.extend(log_msgs);
+    }
+}
+
+pub(crate) fn toggle_pinned(
+    pinned_components: &mut Vec<(EntityPath, ComponentName)>,
+    entity_path: &EntityPath,
+    component_name: ComponentName,
+) {
+    let len = pinned_components.len();
+    pinned_components.retain(|(e, c)| (e, *c) != (entity_path, component_name));
+    if pinned_components.len() == len {
+        pinned_components.push((entity_path.clone(), component_name));
+    }
+}
+
+/// Make the entity the viewer's selection, as if clicked there.
+pub(crate) fn select_in_viewer(
+    command_sender: &re_viewer_context::CommandSender,
+    entity_path: &EntityPath,
+) {
+    command_sender.send_system(re_viewer_context::SystemCommand::SetSelection(
+        re_viewer_context::Item::InstancePath(re_entity_db::InstancePath::entity_all(
+            entity_path.clone(),
+        )),
+    ));
+}
+
+/// All panels, built-in or not, and which of them are open as tabs.
+pub(crate) struct PanelRegistry {
+    panels: Vec<Box<dyn CartographerPanel>>,
+
+    /// Ids of the open tabs, in the order they are shown.
+    open: Vec<String>,
+
+    /// Id of the tab being shown.
+    active: Option<String>,
+}
+
+impl PanelRegistry {
+    /// All panels start out open.
+    pub fn new(panels: Vec<Box<dyn CartographerPanel>>) -> Self {
+        let open: Vec<String> = panels.iter().map(|p| p.id().to_owned()).collect();
+        let active = open.first().cloned();
+        Self {
+            panels,
+            open,
+            active,
+        }
+    }
+
+    /// Restore the tabs and the state of the panels from an earlier session.
+    pub fn restore(
+        &mut self,
+        open: Option<Vec<String>>,
+        active: Option<String>,
+        mut states: BTreeMap<String, serde_json::Value>,
+    ) {
+        if let Some(open) = open {
+            // Panels may have been removed since:
+            self.open = open
+                .into_iter()
+                .filter(|id| self.panels.iter().any(|p| p.id() == id))
+                .collect();
+            self.active = self.open.first().cloned();
+        }
+        if let Some(active) = active.filter(|id| self.open.contains(id)) {
+            self.active = Some(active);
+        }
+
+        for panel in &mut self.panels {
+            if let Some(state) = states.remove(panel.id()) {
+                panel.load_state(state);
+            }
+        }
+        for id in states.keys() {
+            re_log::debug!("Ignoring the stored state of unknown panel {id:?}");
+        }
+    }
+
+    pub fn open_tabs(&self) -> &[String] {
+        &self.open
+    }
+
+    pub fn active_tab(&self) -> Option<&String> {
+        self.active.as_ref()
+    }
+
+    /// What the panels want to keep, by panel id.
+    pub fn panel_states(&self) -> BTreeMap<String, serde_json::Value> {
+        self.panels
+            .iter()
+            .filter_map(|panel| Some((panel.id().to_owned(), panel.save_state()?)))
+            .collect()
+    }
+
+    fn open_tab(&mut self, id: &str) {
+        if !self.open.iter().any(|open| open == id) {
+            self.open.push(id.to_owned());
+        }
+        self.active = Some(id.to_owned());
+    }
+
+    fn close_tab(&mut self, id: &str) {
+        self.open.retain(|open| open != id);
+        if self.active.as_deref() == Some(id) {
+            self.active = self.open.first().cloned();
+        }
+    }
+
+    /// The tab bar, and the active tab below it.
+    pub fn ui(&mut self, ui: &mut egui::Ui, ctx: &mut PanelContext<'_>) {
+        let mut open_tab = None;
+        let mut close_tab = None;
+
+        ui.horizontal_wrapped(|ui| {
+            for id in &self.open {
+                let Some(panel) = self.panels.iter().find(|p| p.id() == id) else {
+                    continue;
+                };
+                let is_active = self.active.as_ref() == Some(id);
+                let response = ui
+                    .selectable_label(is_active, panel.name())
+                    .on_hover_text("Right-click to close");
+                if response.clicked() {
+                    open_tab = Some(id.clone());
+                }
+                response.context_menu(|ui| {
+                    if ui.button("Close tab").clicked() {
+                        close_tab = Some(id.clone());
+                        ui.close_menu();
+                    }
+                });
+            }
+
+            let closed: Vec<&dyn CartographerPanel> = self
+                .panels
+                .iter()
+                .map(|p| &**p)
+                .filter(|p| !self.open.iter().any(|id| id == p.id()))
+                .collect();
+            if !closed.is_empty() {
+                ui.menu_button("➕", |ui| {
+                    for panel in closed {
+                        if ui.button(panel.name()).clicked() {
+                            open_tab = Some(panel.id().to_owned());
+                            ui.close_menu();
+                        }
+                    }
+                })
+                .response
+                .on_hover_text("Open a closed tab");
+            }
+        });
+
+        if let Some(id) = open_tab {
+            self.open_tab(&id);
+        }
+        if let Some(id) = close_tab {
+            self.close_tab(&id);
+        }
+        ui.separator();
+
+        let active = self.active.as_deref();
+        let Some(panel) = self.panels.iter_mut().find(|p| Some(p.id()) == active) else {
+            ui.label("All tabs are closed.");
+            return;
+        };
+        panel.ui(ui, ctx);
+    }
+}
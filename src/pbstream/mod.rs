@@ -0,0 +1,701 @@
+//! Import Cartographer's serialized SLAM state (`.pbstream` files).
+//!
+//! A `.pbstream` starts with a magic number, followed by size-prefixed,
+//! gzip-compressed protobuf messages: first a [`proto::SerializationHeader`],
+//! then any number of [`proto::SerializedData`].
+
+mod proto;
+
+use std::{
+    collections::BTreeMap,
+    io::Read as _,
+    path::{Path, PathBuf},
+};
+
+use prost::Message as _;
+use re_viewer::external::{re_log, re_log_types::LogMsg, re_types};
+
+use crate::{
+    constraints::{Constraint, ConstraintBatch, ConstraintKind, PosePaths, TrajectoryIndex},
+    grid::{GridArchetypeIndicator, GridLimits, GridLimitsBatch, TruncationDistanceBatch},
+    submaps::{SubmapInfo, SubmapInfoBatch},
+    trajectory::Pose,
+};
+use proto::{pose_graph::constraint::Tag, serialized_data::Data};
+
+const MAGIC: u64 = 0x7b1d1f7b5bf501db;
+
+/// Largest message we read, compressed or not, so that a corrupt size can't exhaust memory.
+const MAX_MESSAGE_BYTES: u64 = 1 << 30;
+
+/// Offset between Cartographer's time (100 ns ticks since 0001-01-01) and the Unix epoch.
+const UNIX_EPOCH_TICKS: i64 = 621_355_968_000_000_000;
+
+const IDENTITY_ROTATION: proto::Quaterniond = proto::Quaterniond {
+    x: 0.0,
+    y: 0.0,
+    z: 0.0,
+    w: 1.0,
+};
+
+/// Name of the timeline the trajectory poses are logged on.
+const SENSOR_TIMELINE: &str = "sensor_time";
+
+/// The trajectory poses are also logged with their node index as time,
+/// so that constraints can find them.
+const NODE_TIMELINE: &str = "node_index";
+
+fn node_entity_path(trajectory_id: i32) -> String {
+    format!("trajectories/{trajectory_id}/pose")
+}
+
+fn submap_entity_path(trajectory_id: i32, submap_index: i32) -> String {
+    format!("submaps/{trajectory_id}/{submap_index}")
+}
+
+/// Everything we need from a `.pbstream`.
+#[derive(Default)]
+struct PbStream {
+    pose_graph: proto::PoseGraph,
+
+    /// Indexed by trajectory id.
+    trajectory_options: Vec<proto::TrajectoryBuilderOptionsWithSensorIds>,
+    submaps: BTreeMap<proto::SubmapId, proto::Submap>,
+}
+
+/// Convert a `.pbstream` to a new recording and stream it to the viewer.
+///
+/// The file is read on a background thread, so this returns immediately.
+pub fn load_pbstream_file(
+    path: &Path,
+) -> Result<re_smart_channel::Receiver<LogMsg>, Box<dyn std::error::Error>> {
+    let path: PathBuf = path.to_owned();
+    let (tx, rx) = re_smart_channel::smart_channel(
+        re_smart_channel::SmartMessageSource::File(path.clone()),
+        re_smart_channel::SmartChannelSource::File(path.clone()),
+    );
+
+    std::thread::Builder::new()
+        .name(format!("load {}", path.display()))
+        .spawn(move || {
+            let messages = read_pbstream(&path).and_then(|pbstream| to_log_msgs(&path, &pbstream));
+            match messages {
+                Ok(messages) => {
+                    for msg in messages {
+                        if tx.send(msg).is_err() {
+                            // The viewer hung up.
+                            return;
+                        }
+                    }
+                    re_log::debug!("Finished loading {}", path.display());
+                    tx.quit(None).ok();
+                }
+                Err(err) => {
+                    re_log::error!("Failed to load {}: {err}", path.display());
+                    let err: Box<dyn std::error::Error + Send + Sync> = err.to_string().into();
+                    tx.quit(Some(err)).ok();
+                }
+            }
+        })?;
+
+    Ok(rx)
+}
+
+fn read_pbstream(path: &Path) -> Result<PbStream, Box<dyn std::error::Error>> {
+    let mut reader = std::io::BufReader::new(std::fs::File::open(path)?);
+
+    if read_u64(&mut reader)? != Some(MAGIC) {
+        return Err("Not a Cartographer .pbstream file".into());
+    }
+
+    let header_bytes = read_message(&mut reader)?.ok_or("Missing serialization header")?;
+    let header = proto::SerializationHeader::decode(header_bytes.as_slice())?;
+    if header.format_version != 2 {
+        re_log::warn!(
+            "Unsupported .pbstream format version {}, trying anyway",
+            header.format_version
+        );
+    }
+
+    let mut pbstream = PbStream::default();
+    while let Some(bytes) = read_message(&mut reader)? {
+        match proto::SerializedData::decode(bytes.as_slice())?.data {
+            Some(Data::PoseGraph(pose_graph)) => pbstream.pose_graph = pose_graph,
+            Some(Data::AllTrajectoryBuilderOptions(options)) => {
+                pbstream.trajectory_options = options.options_with_sensor_ids;
+            }
+            Some(Data::Submap(submap)) => {
+                let id = submap.submap_id.unwrap_or_default();
+                pbstream.submaps.insert(id, *submap);
+            }
+            None => {} // Data we don't import, e.g. sensor data.
+        }
+    }
+    Ok(pbstream)
+}
+
+/// Returns `None` at the end of the file.
+fn read_u64(reader: &mut impl std::io::Read) -> std::io::Result<Option<u64>> {
+    let mut bytes = [0_u8; 8];
+    match reader.read_exact(&mut bytes) {
+        Ok(()) => Ok(Some(u64::from_le_bytes(bytes))),
+        Err(err) if err.kind() == std::io::ErrorKind::UnexpectedEof => Ok(None),
+        Err(err) => Err(err),
+    }
+}
+
+/// Read and decompress the next message, or return `None` at the end of the file.
+fn read_message(reader: &mut impl std::io::Read) -> std::io::Result<Option<Vec<u8>>> {
+    let Some(size) = read_u64(reader)? else {
+        return Ok(None);
+    };
+    if MAX_MESSAGE_BYTES < size {
+        return Err(std::io::Error::new(
+            std::io::ErrorKind::InvalidData,
+            format!("Message of {size} bytes is too large"),
+        ));
+    }
+
+    // Grows with what is actually there, rather than trusting the size:
+    let mut compressed = Vec::new();
+    reader.by_ref().take(size).read_to_end(&mut compressed)?;
+    if (compressed.len() as u64) < size {
+        return Err(std::io::ErrorKind::UnexpectedEof.into());
+    }
+
+    let mut bytes = Vec::new();
+    flate2::read::GzDecoder::new(compressed.as_slice())
+        .take(MAX_MESSAGE_BYTES + 1)
+        .read_to_end(&mut bytes)?;
+    if MAX_MESSAGE_BYTES < bytes.len() as u64 {
+        return Err(std::io::Error::new(
+            std::io::ErrorKind::InvalidData,
+            "Decompressed message is too large",
+        ));
+    }
+    Ok(Some(bytes))
+}
+
+fn to_log_msgs(
+    path: &Path,
+    pbstream: &PbStream,
+) -> Result<Vec<LogMsg>, Box<dyn std::error::Error>> {
+    use re_types::{archetypes, components, datatypes};
+
+    let app_id = path.file_stem().map_or_else(
+        || "pbstream".to_owned(),
+        |stem| stem.to_string_lossy().into_owned(),
+    );
+    let (rec, storage) = re_sdk::RecordingStreamBuilder::new(app_id).memory()?;
+
+    let mut node_poses = BTreeMap::new();
+    let mut submap_poses = BTreeMap::new();
+
+    for trajectory in &pbstream.pose_graph.trajectory {
+        let trajectory_id = trajectory.trajectory_id;
+
+        let mut path = Vec::with_capacity(trajectory.node.len());
+        let mut num_without_time = 0;
+        for node in &trajectory.node {
+            let pose = node.pose.unwrap_or_default();
+            node_poses.insert((trajectory_id, node.node_index), pose);
+            path.push(translation(&pose));
+
+            // Nodes without a valid time are still logged by index, for the constraints:
+            match unix_nanos(node.timestamp) {
+                Some(nanos) => rec.set_time_nanos(SENSOR_TIMELINE, nanos),
+                None => {
+                    num_without_time += 1;
+                    rec.disable_timeline(SENSOR_TIMELINE);
+                }
+            }
+            rec.set_time_sequence(NODE_TIMELINE, node.node_index);
+            rec.log(node_entity_path(trajectory_id), &transform(&pose))?;
+        }
+        rec.reset_time();
+        if 0 < num_without_time {
+            re_log::warn!(
+                "{num_without_time} nodes of trajectory {trajectory_id} have no valid time, \
+                 they are only logged on the {NODE_TIMELINE} timeline"
+            );
+        }
+
+        if let Some(options) = usize::try_from(trajectory_id)
+            .ok()
+            .and_then(|index| pbstream.trajectory_options.get(index))
+        {
+            rec.log_static(
+                format!("trajectories/{trajectory_id}/options"),
+                &archetypes::TextDocument::new(options_text(options)),
+            )?;
+        }
+
+        rec.log_static(
+            format!("trajectories/{trajectory_id}/path"),
+            &archetypes::LineStrips3D::new([path]),
+        )?;
+
+        for submap in &trajectory.submap {
+            let pose = submap.pose.unwrap_or_default();
+            submap_poses.insert((trajectory_id, submap.submap_index), pose);
+            rec.log_static(
+                submap_entity_path(trajectory_id, submap.submap_index),
+                &transform(&pose),
+            )?;
+        }
+    }
+
+    for (id, submap) in &pbstream.submaps {
+        let entity_path = submap_entity_path(id.trajectory_id, id.submap_index);
+
+        let info = if let Some(submap_2d) = &submap.submap_2d {
+            Some(SubmapInfo {
+                num_range_data: submap_2d.num_range_data,
+                finished: submap_2d.finished,
+                resolution: submap_2d
+                    .grid
+                    .as_ref()
+                    .and_then(|grid| grid.limits)
+                    .map_or(f64::NAN, |limits| limits.resolution),
+                local_pose: pose(&submap_2d.local_pose.unwrap_or_default()),
+            })
+        } else {
+            submap.submap_3d.map(|submap_3d| SubmapInfo {
+                num_range_data: submap_3d.num_range_data,
+                finished: submap_3d.finished,
+                resolution: f64::NAN,
+                local_pose: pose(&submap_3d.local_pose.unwrap_or_default()),
+            })
+        };
+        if let Some(info) = info {
+            rec.log_component_batches(
+                entity_path.as_str(),
+                true,
+                [&SubmapInfoBatch(&[info]) as &dyn re_types::ComponentBatch],
+            )?;
+        }
+
+        if let Some(submap_2d) = &submap.submap_2d {
+            let Some(grid) = &submap_2d.grid else {
+                continue;
+            };
+            let Some(limits) = grid.limits else {
+                continue;
+            };
+            let (Some(cell_limits), Some(max)) = (limits.cell_limits, limits.max) else {
+                continue;
+            };
+
+            let shape = vec![
+                datatypes::TensorDimension::height(cell_limits.num_y_cells as u64),
+                datatypes::TensorDimension::width(cell_limits.num_x_cells as u64),
+            ];
+            // TSDF grids are stored like probability grids, with the range of
+            // correspondence costs being the range of distances:
+            let values = match grid.tsdf_2d {
+                Some(_) => grid.signed_distances(),
+                None => grid.probabilities(),
+            };
+            let tensor =
+                datatypes::TensorData::new(shape, datatypes::TensorBuffer::F32(values.into()));
+            let grid_path = format!("{entity_path}/grid");
+            rec.log_static(grid_path.as_str(), &archetypes::Image::new(tensor))?;
+
+            let limits = GridLimitsBatch(&[GridLimits {
+                resolution: limits.resolution,
+                max: [max.x, max.y],
+            }]);
+            let truncation_distance = grid
+                .tsdf_2d
+                .map(|tsdf_2d| TruncationDistanceBatch(tsdf_2d.truncation_distance));
+            let mut batches: Vec<&dyn re_types::ComponentBatch> =
+                vec![&limits, &GridArchetypeIndicator::DEFAULT];
+            if let Some(truncation_distance) = &truncation_distance {
+                batches.push(truncation_distance);
+            }
+            rec.log_component_batches(grid_path.as_str(), true, batches)?;
+        } else if submap.submap_3d.is_some() {
+            re_log::debug!("Skipping 3D grid of submap {entity_path}");
+        }
+    }
+
+    // Line segments from submap to node, and the constraint for each of them:
+    let mut intra_submap = (Vec::new(), Vec::new());
+    let mut inter_submap = (Vec::new(), Vec::new());
+    for constraint in &pbstream.pose_graph.constraint {
+        let (Some(submap_id), Some(node_id)) = (constraint.submap_id, constraint.node_id) else {
+            continue;
+        };
+        let submap_pose = submap_poses.get(&(submap_id.trajectory_id, submap_id.submap_index));
+        let node_pose = node_poses.get(&(node_id.trajectory_id, node_id.node_index));
+        let (Some(submap_pose), Some(node_pose)) = (submap_pose, node_pose) else {
+            continue;
+        };
+
+        let (kind, (segments, constraints)) = match Tag::try_from(constraint.tag) {
+            Ok(Tag::IntraSubmap) => (ConstraintKind::IntraSubmap, &mut intra_submap),
+            Ok(Tag::InterSubmap) => (ConstraintKind::InterSubmap, &mut inter_submap),
+            Err(_) => continue,
+        };
+        segments.push(vec![translation(submap_pose), translation(node_pose)]);
+        constraints.push(Constraint {
+            submap: TrajectoryIndex {
+                trajectory_id: submap_id.trajectory_id,
+                index: submap_id.submap_index,
+            },
+            node: TrajectoryIndex {
+                trajectory_id: node_id.trajectory_id,
+                index: node_id.node_index,
+            },
+            kind,
+            relative_pose: pose(&constraint.relative_pose.unwrap_or_default()),
+            translation_weight: constraint.translation_weight,
+            rotation_weight: constraint.rotation_weight,
+            poses: Some(PosePaths {
+                node_entity_path: node_entity_path(node_id.trajectory_id).into(),
+                node_timeline: NODE_TIMELINE.to_owned(),
+                submap_entity_path: submap_entity_path(
+                    submap_id.trajectory_id,
+                    submap_id.submap_index,
+                )
+                .into(),
+            }),
+        });
+    }
+    for (entity_path, (segments, constraints), color) in [
+        (
+            "constraints/intra_submap",
+            intra_submap,
+            components::Color::from_rgb(80, 160, 255),
+        ),
+        (
+            "constraints/inter_submap",
+            inter_submap,
+            components::Color::from_rgb(255, 200, 0),
+        ),
+    ] {
+        rec.log_static(
+            entity_path,
+            &archetypes::LineStrips3D::new(segments).with_colors([color]),
+        )?;
+        rec.log_component_batches(
+            entity_path,
+            true,
+            [&ConstraintBatch(&constraints) as &dyn re_types::ComponentBatch],
+        )?;
+    }
+
+    rec.flush_blocking();
+    Ok(storage.take())
+}
+
+/// Unix time in nanoseconds of a Cartographer time, or `None` if it is out of range,
+/// e.g. 0 for a missing time.
+fn unix_nanos(timestamp: i64) -> Option<i64> {
+    timestamp.checked_sub(UNIX_EPOCH_TICKS)?.checked_mul(100)
+}
+
+/// A summary of the options a trajectory was built with.
+fn options_text(options: &proto::TrajectoryBuilderOptionsWithSensorIds) -> String {
+    use proto::sensor_id::SensorType;
+
+    let mut lines = Vec::new();
+    if let Some(builder) = &options.trajectory_builder_options {
+        lines.push(
+            if builder.trajectory_builder_3d_options.is_some() {
+                "3D SLAM"
+            } else if builder.trajectory_builder_2d_options.is_some() {
+                "2D SLAM"
+            } else {
+                "Unknown SLAM type"
+            }
+            .to_owned(),
+        );
+        for (set, name) in [
+            (builder.pure_localization, "Pure localization"),
+            (builder.collate_fixed_frame, "Collate fixed frame poses"),
+            (builder.collate_landmarks, "Collate landmarks"),
+        ] {
+            if set {
+                lines.push(name.to_owned());
+            }
+        }
+        if let Some(initial) = &builder.initial_trajectory_pose {
+            let t = initial
+                .relative_pose
+                .unwrap_or_default()
+                .translation
+                .unwrap_or_default();
+            lines.push(format!(
+                "Starts at ({:.3}, {:.3}, {:.3}) relative to trajectory {}",
+                t.x, t.y, t.z, initial.to_trajectory_id
+            ));
+        }
+    }
+    for sensor in &options.sensor_id {
+        let typ = match SensorType::try_from(sensor.r#type) {
+            Ok(SensorType::Range) => "range",
+            Ok(SensorType::Imu) => "IMU",
+            Ok(SensorType::Odometry) => "odometry",
+            Ok(SensorType::FixedFramePose) => "fixed frame pose",
+            Ok(SensorType::Landmark) => "landmark",
+            Ok(SensorType::LocalSlamResult) => "local SLAM result",
+            Err(_) => "unknown",
+        };
+        lines.push(format!("Sensor {:?}: {typ}", sensor.id));
+    }
+    lines.join("\n")
+}
+
+fn translation(pose: &proto::Rigid3d) -> [f32; 3] {
+    let t = pose.translation.unwrap_or_default();
+    [t.x as f32, t.y as f32, t.z as f32]
+}
+
+fn pose(rigid: &proto::Rigid3d) -> Pose {
+    let t = rigid.translation.unwrap_or_default();
+    let r = rigid.rotation.unwrap_or(IDENTITY_ROTATION);
+    Pose {
+        translation: glam::dvec3(t.x, t.y, t.z),
+        rotation: glam::DQuat::from_xyzw(r.x, r.y, r.z, r.w),
+    }
+}
+
+fn transform(pose: &proto::Rigid3d) -> re_types::archetypes::Transform3D {
+    let r = pose.rotation.unwrap_or(IDENTITY_ROTATION);
+    re_types::archetypes::Transform3D::from_translation_rotation(
+        translation(pose),
+        re_types::datatypes::Quaternion::from_xyzw([
+            r.x as f32, r.y as f32, r.z as f32, r.w as f32,
+        ]),
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use std::io::Write as _;
+
+    use re_viewer::external::re_log_types::DataTable;
+
+    use super::*;
+    use proto::{serialized_data::Data, trajectory_proto};
+
+    fn write_message(file: &mut Vec<u8>, message: &impl prost::Message) {
+        let mut encoder = flate2::write::GzEncoder::new(Vec::new(), flate2::Compression::default());
+        encoder.write_all(&message.encode_to_vec()).unwrap();
+        let compressed = encoder.finish().unwrap();
+        file.extend_from_slice(&(compressed.len() as u64).to_le_bytes());
+        file.extend_from_slice(&compressed);
+    }
+
+    fn rigid(x: f64, y: f64) -> proto::Rigid3d {
+        proto::Rigid3d {
+            translation: Some(proto::Vector3d { x, y, z: 0.0 }),
+            rotation: Some(IDENTITY_ROTATION),
+        }
+    }
+
+    fn data(data: Data) -> proto::SerializedData {
+        proto::SerializedData { data: Some(data) }
+    }
+
+    #[test]
+    fn tags_match_cartographer() {
+        // `Node.timestamp = 1` and `Submap.pose = 1` in trajectory.proto:
+        let node = trajectory_proto::Node {
+            timestamp: 5,
+            ..Default::default()
+        };
+        assert_eq!(node.encode_to_vec(), [0x08, 5]);
+        let submap = trajectory_proto::Submap {
+            pose: Some(proto::Rigid3d::default()),
+            ..Default::default()
+        };
+        assert_eq!(submap.encode_to_vec(), [0x0a, 0]);
+    }
+
+    #[test]
+    fn unix_time() {
+        assert_eq!(unix_nanos(UNIX_EPOCH_TICKS + 15), Some(1500));
+        assert_eq!(unix_nanos(0), None);
+        assert_eq!(unix_nanos(i64::MIN), None);
+    }
+
+    #[test]
+    fn grid_values() {
+        let mut grid = proto::Grid2D {
+            cells: vec![0, 1, 16384, 32767, 32768 + 1],
+            tsdf_2d: None,
+            max_correspondence_cost: 0.9,
+            min_correspondence_cost: 0.1,
+            ..Default::default()
+        };
+        let probabilities = grid.probabilities();
+        assert!(probabilities[0].is_nan());
+        for (probability, expected) in probabilities[1..].iter().zip([0.9, 0.5, 0.1, 0.9]) {
+            assert!((probability - expected).abs() < 1e-4, "{probabilities:?}");
+        }
+
+        grid.tsdf_2d = Some(proto::Tsdf2D {
+            truncation_distance: 0.3,
+        });
+        grid.min_correspondence_cost = -0.3;
+        grid.max_correspondence_cost = 0.3;
+        let distances = grid.signed_distances();
+        assert!(distances[0].is_nan());
+        for (distance, expected) in distances[1..].iter().zip([-0.3, 0.0, 0.3, -0.3]) {
+            assert!((distance - expected).abs() < 1e-4, "{distances:?}");
+        }
+    }
+
+    #[test]
+    fn imports_pbstream() {
+        let submap_id = proto::SubmapId {
+            trajectory_id: 0,
+            submap_index: 0,
+        };
+        let node_id = proto::NodeId {
+            trajectory_id: 0,
+            node_index: 1,
+        };
+
+        let mut file = MAGIC.to_le_bytes().to_vec();
+        write_message(&mut file, &proto::SerializationHeader { format_version: 2 });
+        write_message(
+            &mut file,
+            &data(Data::PoseGraph(proto::PoseGraph {
+                constraint: vec![proto::pose_graph::Constraint {
+                    submap_id: Some(submap_id),
+                    node_id: Some(node_id),
+                    relative_pose: Some(rigid(0.5, 0.0)),
+                    tag: Tag::InterSubmap as i32,
+                    translation_weight: 1.0,
+                    rotation_weight: 2.0,
+                }],
+                trajectory: vec![proto::TrajectoryProto {
+                    node: vec![
+                        trajectory_proto::Node {
+                            timestamp: UNIX_EPOCH_TICKS + 10_000_000,
+                            pose: Some(rigid(0.0, 0.0)),
+                            node_index: 0,
+                        },
+                        // No time, as e.g. in files written by buggy tools:
+                        trajectory_proto::Node {
+                            timestamp: 0,
+                            pose: Some(rigid(1.0, 0.0)),
+                            node_index: 1,
+                        },
+                    ],
+                    submap: vec![trajectory_proto::Submap {
+                        pose: Some(rigid(1.0, 2.0)),
+                        submap_index: 0,
+                    }],
+                    trajectory_id: 0,
+                }],
+            })),
+        );
+        write_message(
+            &mut file,
+            &data(Data::AllTrajectoryBuilderOptions(
+                proto::AllTrajectoryBuilderOptions {
+                    options_with_sensor_ids: vec![proto::TrajectoryBuilderOptionsWithSensorIds {
+                        sensor_id: vec![proto::SensorId {
+                            r#type: proto::sensor_id::SensorType::Range as i32,
+                            id: "scan".to_owned(),
+                        }],
+                        trajectory_builder_options: Some(proto::TrajectoryBuilderOptions {
+                            trajectory_builder_2d_options: Some(proto::Unparsed {}),
+                            pure_localization: true,
+                            ..Default::default()
+                        }),
+                    }],
+                },
+            )),
+        );
+        write_message(
+            &mut file,
+            &data(Data::Submap(Box::new(proto::Submap {
+                submap_id: Some(submap_id),
+                submap_2d: Some(proto::Submap2D {
+                    local_pose: Some(rigid(0.0, 0.0)),
+                    num_range_data: 90,
+                    finished: true,
+                    grid: Some(proto::Grid2D {
+                        limits: Some(proto::MapLimits {
+                            resolution: 0.05,
+                            max: Some(proto::Vector2d { x: 1.0, y: 2.0 }),
+                            cell_limits: Some(proto::CellLimits {
+                                num_x_cells: 3,
+                                num_y_cells: 2,
+                            }),
+                        }),
+                        cells: vec![0, 1, 32767, 0, 16384, 1],
+                        tsdf_2d: None,
+                        max_correspondence_cost: 0.9,
+                        min_correspondence_cost: 0.1,
+                    }),
+                }),
+                submap_3d: None,
+            }))),
+        );
+
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("map.pbstream");
+        std::fs::write(&path, file).unwrap();
+
+        let pbstream = read_pbstream(&path).unwrap();
+        let trajectory = &pbstream.pose_graph.trajectory[0];
+        assert_eq!(
+            unix_nanos(trajectory.node[0].timestamp),
+            Some(1_000_000_000)
+        );
+        assert_eq!(unix_nanos(trajectory.node[1].timestamp), None);
+        assert_eq!(trajectory.submap[0].pose, Some(rigid(1.0, 2.0)));
+        assert_eq!(
+            options_text(&pbstream.trajectory_options[0]),
+            "2D SLAM\nPure localization\nSensor \"scan\": range"
+        );
+        assert_eq!(
+            pbstream.submaps[&submap_id]
+                .submap_2d
+                .as_ref()
+                .unwrap()
+                .num_range_data,
+            90
+        );
+
+        let rows: Vec<_> = to_log_msgs(&path, &pbstream)
+            .unwrap()
+            .iter()
+            .filter_map(|msg| match msg {
+                LogMsg::ArrowMsg(_, arrow_msg) => {
+                    Some(DataTable::from_arrow_msg(arrow_msg).unwrap())
+                }
+                _ => None,
+            })
+            .flat_map(|table| table.to_rows().map(Result::unwrap).collect::<Vec<_>>())
+            .collect();
+        let entity_paths: std::collections::BTreeSet<String> =
+            rows.iter().map(|row| row.entity_path.to_string()).collect();
+        for expected in [
+            "/trajectories/0/pose",
+            "/trajectories/0/path",
+            "/trajectories/0/options",
+            "/submaps/0/0",
+            "/submaps/0/0/grid",
+            "/constraints/inter_submap",
+        ] {
+            assert!(
+                entity_paths.contains(expected),
+                "{expected} in {entity_paths:?}"
+            );
+        }
+
+        // Without the indicator, the grid view doesn't pick up the grid:
+        let indicator = re_types::LoggableBatch::name(&GridArchetypeIndicator::DEFAULT);
+        assert!(rows.iter().any(|row| {
+            row.entity_path.to_string() == "/submaps/0/0/grid"
+                && row.component_names().any(|name| name == indicator)
+        }));
+    }
+}
@@ -0,0 +1,339 @@
+//! The subset of Cartographer's protobuf messages needed to import a `.pbstream`.
+//!
+//! Field numbers follow `cartographer/mapping/proto/*.proto` and
+//! `cartographer/transform/proto/transform.proto`. Fields we don't use are left out,
+//! and skipped by the decoder.
+
+#[derive(Clone, PartialEq, prost::Message)]
+pub struct SerializationHeader {
+    #[prost(uint32, tag = "1")]
+    pub format_version: u32,
+}
+
+#[derive(Clone, PartialEq, prost::Message)]
+pub struct SerializedData {
+    #[prost(oneof = "serialized_data::Data", tags = "1, 2, 3")]
+    pub data: Option<serialized_data::Data>,
+}
+
+pub mod serialized_data {
+    #[derive(Clone, PartialEq, prost::Oneof)]
+    pub enum Data {
+        #[prost(message, tag = "1")]
+        PoseGraph(super::PoseGraph),
+
+        #[prost(message, tag = "2")]
+        AllTrajectoryBuilderOptions(super::AllTrajectoryBuilderOptions),
+
+        #[prost(message, boxed, tag = "3")]
+        Submap(Box<super::Submap>),
+    }
+}
+
+#[derive(Clone, Copy, PartialEq, prost::Message)]
+pub struct Vector2d {
+    #[prost(double, tag = "1")]
+    pub x: f64,
+    #[prost(double, tag = "2")]
+    pub y: f64,
+}
+
+#[derive(Clone, Copy, PartialEq, prost::Message)]
+pub struct Vector3d {
+    #[prost(double, tag = "1")]
+    pub x: f64,
+    #[prost(double, tag = "2")]
+    pub y: f64,
+    #[prost(double, tag = "3")]
+    pub z: f64,
+}
+
+#[derive(Clone, Copy, PartialEq, prost::Message)]
+pub struct Quaterniond {
+    #[prost(double, tag = "1")]
+    pub x: f64,
+    #[prost(double, tag = "2")]
+    pub y: f64,
+    #[prost(double, tag = "3")]
+    pub z: f64,
+    #[prost(double, tag = "4")]
+    pub w: f64,
+}
+
+#[derive(Clone, Copy, PartialEq, prost::Message)]
+pub struct Rigid3d {
+    #[prost(message, optional, tag = "1")]
+    pub translation: Option<Vector3d>,
+    #[prost(message, optional, tag = "2")]
+    pub rotation: Option<Quaterniond>,
+}
+
+#[derive(Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord, prost::Message)]
+pub struct SubmapId {
+    #[prost(int32, tag = "1")]
+    pub trajectory_id: i32,
+    #[prost(int32, tag = "2")]
+    pub submap_index: i32,
+}
+
+#[derive(Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord, prost::Message)]
+pub struct NodeId {
+    #[prost(int32, tag = "1")]
+    pub trajectory_id: i32,
+    #[prost(int32, tag = "2")]
+    pub node_index: i32,
+}
+
+#[derive(Clone, PartialEq, prost::Message)]
+pub struct PoseGraph {
+    #[prost(message, repeated, tag = "2")]
+    pub constraint: Vec<pose_graph::Constraint>,
+    #[prost(message, repeated, tag = "4")]
+    pub trajectory: Vec<TrajectoryProto>,
+}
+
+pub mod pose_graph {
+    #[derive(Clone, Copy, PartialEq, prost::Message)]
+    pub struct Constraint {
+        #[prost(message, optional, tag = "1")]
+        pub submap_id: Option<super::SubmapId>,
+        #[prost(message, optional, tag = "2")]
+        pub node_id: Option<super::NodeId>,
+
+        /// Pose of the node relative to the submap.
+        #[prost(message, optional, tag = "3")]
+        pub relative_pose: Option<super::Rigid3d>,
+        #[prost(enumeration = "constraint::Tag", tag = "5")]
+        pub tag: i32,
+        #[prost(double, tag = "6")]
+        pub translation_weight: f64,
+        #[prost(double, tag = "7")]
+        pub rotation_weight: f64,
+    }
+
+    pub mod constraint {
+        #[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, PartialOrd, Ord, prost::Enumeration)]
+        #[repr(i32)]
+        pub enum Tag {
+            IntraSubmap = 0,
+            InterSubmap = 1,
+        }
+    }
+}
+
+#[derive(Clone, PartialEq, prost::Message)]
+pub struct TrajectoryProto {
+    #[prost(message, repeated, tag = "1")]
+    pub node: Vec<trajectory_proto::Node>,
+    #[prost(message, repeated, tag = "2")]
+    pub submap: Vec<trajectory_proto::Submap>,
+    #[prost(int32, tag = "3")]
+    pub trajectory_id: i32,
+}
+
+pub mod trajectory_proto {
+    #[derive(Clone, Copy, PartialEq, prost::Message)]
+    pub struct Node {
+        /// Cartographer time: 100 ns ticks since 0001-01-01.
+        #[prost(int64, tag = "1")]
+        pub timestamp: i64,
+
+        /// Global pose, as optimized by the pose graph.
+        #[prost(message, optional, tag = "5")]
+        pub pose: Option<super::Rigid3d>,
+        #[prost(int32, tag = "7")]
+        pub node_index: i32,
+    }
+
+    #[derive(Clone, Copy, PartialEq, prost::Message)]
+    pub struct Submap {
+        /// Global pose, as optimized by the pose graph.
+        #[prost(message, optional, tag = "1")]
+        pub pose: Option<super::Rigid3d>,
+        #[prost(int32, tag = "2")]
+        pub submap_index: i32,
+    }
+}
+
+/// The options each trajectory was built with, indexed by trajectory id.
+#[derive(Clone, PartialEq, prost::Message)]
+pub struct AllTrajectoryBuilderOptions {
+    #[prost(message, repeated, tag = "1")]
+    pub options_with_sensor_ids: Vec<TrajectoryBuilderOptionsWithSensorIds>,
+}
+
+#[derive(Clone, PartialEq, prost::Message)]
+pub struct TrajectoryBuilderOptionsWithSensorIds {
+    #[prost(message, repeated, tag = "1")]
+    pub sensor_id: Vec<SensorId>,
+    #[prost(message, optional, tag = "2")]
+    pub trajectory_builder_options: Option<TrajectoryBuilderOptions>,
+}
+
+#[derive(Clone, PartialEq, prost::Message)]
+pub struct SensorId {
+    #[prost(enumeration = "sensor_id::SensorType", tag = "1")]
+    pub r#type: i32,
+    #[prost(string, tag = "2")]
+    pub id: String,
+}
+
+pub mod sensor_id {
+    #[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, PartialOrd, Ord, prost::Enumeration)]
+    #[repr(i32)]
+    pub enum SensorType {
+        Range = 0,
+        Imu = 1,
+        Odometry = 2,
+        FixedFramePose = 3,
+        Landmark = 4,
+        LocalSlamResult = 5,
+    }
+}
+
+#[derive(Clone, Copy, PartialEq, prost::Message)]
+pub struct TrajectoryBuilderOptions {
+    /// Set for 2D SLAM. Only its presence is imported, not the tuning parameters.
+    #[prost(message, optional, tag = "1")]
+    pub trajectory_builder_2d_options: Option<Unparsed>,
+
+    /// Set for 3D SLAM.
+    #[prost(message, optional, tag = "2")]
+    pub trajectory_builder_3d_options: Option<Unparsed>,
+    #[prost(bool, tag = "3")]
+    pub pure_localization: bool,
+
+    /// Where the trajectory starts relative to another one, if given.
+    #[prost(message, optional, tag = "4")]
+    pub initial_trajectory_pose: Option<trajectory_builder_options::InitialTrajectoryPose>,
+    #[prost(bool, tag = "7")]
+    pub collate_fixed_frame: bool,
+    #[prost(bool, tag = "8")]
+    pub collate_landmarks: bool,
+}
+
+pub mod trajectory_builder_options {
+    #[derive(Clone, Copy, PartialEq, prost::Message)]
+    pub struct InitialTrajectoryPose {
+        #[prost(message, optional, tag = "1")]
+        pub relative_pose: Option<super::Rigid3d>,
+        #[prost(int32, tag = "2")]
+        pub to_trajectory_id: i32,
+        #[prost(int64, tag = "3")]
+        pub timestamp: i64,
+    }
+}
+
+/// A message whose fields we skip.
+#[derive(Clone, Copy, PartialEq, prost::Message)]
+pub struct Unparsed {}
+
+#[derive(Clone, PartialEq, prost::Message)]
+pub struct Submap {
+    #[prost(message, optional, tag = "1")]
+    pub submap_id: Option<SubmapId>,
+    #[prost(message, optional, tag = "2")]
+    pub submap_2d: Option<Submap2D>,
+    #[prost(message, optional, tag = "3")]
+    pub submap_3d: Option<Submap3D>,
+}
+
+#[derive(Clone, PartialEq, prost::Message)]
+pub struct Submap2D {
+    #[prost(message, optional, tag = "1")]
+    pub local_pose: Option<Rigid3d>,
+    #[prost(int32, tag = "2")]
+    pub num_range_data: i32,
+    #[prost(bool, tag = "3")]
+    pub finished: bool,
+    #[prost(message, optional, tag = "4")]
+    pub grid: Option<Grid2D>,
+}
+
+#[derive(Clone, Copy, PartialEq, prost::Message)]
+pub struct Submap3D {
+    #[prost(message, optional, tag = "1")]
+    pub local_pose: Option<Rigid3d>,
+    #[prost(int32, tag = "2")]
+    pub num_range_data: i32,
+    #[prost(bool, tag = "3")]
+    pub finished: bool,
+}
+
+#[derive(Clone, PartialEq, prost::Message)]
+pub struct Grid2D {
+    #[prost(message, optional, tag = "1")]
+    pub limits: Option<MapLimits>,
+
+    /// Encoded correspondence costs or signed distances, row major,
+    /// see [`Grid2D::probabilities`] and [`Grid2D::signed_distances`].
+    #[prost(int32, repeated, tag = "2")]
+    pub cells: Vec<i32>,
+
+    /// Set if [`Self::cells`] are a truncated signed distance field, not probabilities.
+    #[prost(message, optional, tag = "5")]
+    pub tsdf_2d: Option<Tsdf2D>,
+    #[prost(float, tag = "6")]
+    pub max_correspondence_cost: f32,
+    #[prost(float, tag = "7")]
+    pub min_correspondence_cost: f32,
+}
+
+#[derive(Clone, Copy, PartialEq, prost::Message)]
+pub struct Tsdf2D {
+    #[prost(float, tag = "1")]
+    pub truncation_distance: f32,
+}
+
+#[derive(Clone, Copy, PartialEq, prost::Message)]
+pub struct MapLimits {
+    /// Size of a cell, in meters.
+    #[prost(double, tag = "1")]
+    pub resolution: f64,
+
+    /// Corner of the grid with the largest coordinates.
+    #[prost(message, optional, tag = "2")]
+    pub max: Option<Vector2d>,
+    #[prost(message, optional, tag = "3")]
+    pub cell_limits: Option<CellLimits>,
+}
+
+#[derive(Clone, Copy, PartialEq, prost::Message)]
+pub struct CellLimits {
+    #[prost(int32, tag = "1")]
+    pub num_x_cells: i32,
+    #[prost(int32, tag = "2")]
+    pub num_y_cells: i32,
+}
+
+impl Grid2D {
+    /// Probability of each cell being occupied, or `NaN` for unknown cells.
+    pub fn probabilities(&self) -> Vec<f32> {
+        self.values().map(|cost| 1.0 - cost).collect()
+    }
+
+    /// Signed distance of each cell of a TSDF grid to the closest surface, in meters,
+    /// or `NaN` for unknown cells.
+    pub fn signed_distances(&self) -> Vec<f32> {
+        self.values().collect()
+    }
+
+    /// Cells are stored as 15 bit values: 0 is unknown, and `1..=32767` map linearly
+    /// to the range of correspondence costs `1 - probability` or signed distances.
+    fn values(&self) -> impl Iterator<Item = f32> + '_ {
+        const UNKNOWN: i32 = 0;
+        const MAX_VALUE: i32 = 32767;
+        let (min_cost, max_cost) = (self.min_correspondence_cost, self.max_correspondence_cost);
+
+        self.cells.iter().map(move |&value| {
+            // Strip the update marker, in case it was left set:
+            let value = value & MAX_VALUE;
+            if value == UNKNOWN {
+                f32::NAN
+            } else {
+                let t = (value - 1) as f32 / (MAX_VALUE - 1) as f32;
+                min_cost + t * (max_cost - min_cost)
+            }
+        })
+    }
+}
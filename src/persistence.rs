@@ -0,0 +1,262 @@
+//! The Cartographer panel's own state in [`eframe::Storage`], next to the viewer's.
+//!
+//! The state is stored as JSON with a version number, so that state from older versions
+//! can be migrated instead of being dropped.
+
+use std::collections::BTreeMap;
+
+use re_viewer::external::{eframe, re_log, re_log_types, re_types};
+
+const KEY: &str = "cartographer_panel";
+
+/// Version of [`PersistedState`], bumped on changes that need a migration.
+///
+/// Version 0 is the list of open tabs, stored as a RON tuple before the rest of the
+/// state was kept.
+const VERSION: u32 = 1;
+
+/// Key of the open tabs in version 0.
+const TABS_KEY_V0: &str = "cartographer_open_tabs";
+
+/// Everything of the Cartographer panel that is kept across sessions.
+#[derive(serde::Deserialize, serde::Serialize)]
+#[serde(default)]
+pub struct PersistedState {
+    pub version: u32,
+
+    pub panel_width: Option<f32>,
+
+    pub follow_viewer: bool,
+    pub pin_to_latest: bool,
+    pub timeline: Option<PersistedTimeline>,
+
+    /// `None` if all tabs are open.
+    pub open_tabs: Option<Vec<String>>,
+    pub active_tab: Option<String>,
+
+    pub pinned_components: Vec<PersistedComponent>,
+
+    /// State of each panel, by [`crate::CartographerPanel::id`].
+    pub panels: BTreeMap<String, serde_json::Value>,
+}
+
+impl Default for PersistedState {
+    fn default() -> Self {
+        Self {
+            version: VERSION,
+            panel_width: None,
+            follow_viewer: true,
+            pin_to_latest: false,
+            timeline: None,
+            open_tabs: None,
+            active_tab: None,
+            pinned_components: Vec::new(),
+            panels: Default::default(),
+        }
+    }
+}
+
+/// A timeline, which may not exist in the recordings of the next session.
+#[derive(serde::Deserialize, serde::Serialize)]
+pub struct PersistedTimeline {
+    name: String,
+    sequence: bool,
+}
+
+impl From<re_log_types::Timeline> for PersistedTimeline {
+    fn from(timeline: re_log_types::Timeline) -> Self {
+        Self {
+            name: timeline.name().to_string(),
+            sequence: timeline.typ() == re_log_types::TimeType::Sequence,
+        }
+    }
+}
+
+impl From<PersistedTimeline> for re_log_types::Timeline {
+    fn from(timeline: PersistedTimeline) -> Self {
+        let typ = if timeline.sequence {
+            re_log_types::TimeType::Sequence
+        } else {
+            re_log_types::TimeType::Time
+        };
+        Self::new(timeline.name.as_str(), typ)
+    }
+}
+
+/// A component of an entity, which may not exist in the recordings of the next session.
+#[derive(Clone, Debug, PartialEq, Eq, serde::Deserialize, serde::Serialize)]
+pub struct PersistedComponent {
+    entity_path: String,
+    component: String,
+}
+
+impl From<&(re_log_types::EntityPath, re_types::ComponentName)> for PersistedComponent {
+    fn from(
+        (entity_path, component): &(re_log_types::EntityPath, re_types::ComponentName),
+    ) -> Self {
+        Self {
+            entity_path: entity_path.to_string(),
+            component: component.to_string(),
+        }
+    }
+}
+
+impl From<PersistedComponent> for (re_log_types::EntityPath, re_types::ComponentName) {
+    fn from(component: PersistedComponent) -> Self {
+        (
+            component.entity_path.as_str().into(),
+            component.component.as_str().into(),
+        )
+    }
+}
+
+/// Read the state of the last session, migrating it from older versions.
+///
+/// Returns the default state if there is none, or if it can't be read.
+pub fn load(storage: &dyn eframe::Storage) -> PersistedState {
+    if let Some(json) = storage.get_string(KEY) {
+        match serde_json::from_str(&json)
+            .map_err(|err| err.to_string())
+            .and_then(migrate)
+        {
+            Ok(state) => return state,
+            Err(err) => {
+                re_log::warn!("Ignoring the stored Cartographer panel state: {err}");
+                return Default::default();
+            }
+        }
+    }
+
+    // Version 0 only had the tabs:
+    match eframe::get_value::<(Vec<String>, Option<String>)>(storage, TABS_KEY_V0) {
+        Some((open_tabs, active_tab)) => PersistedState {
+            open_tabs: Some(open_tabs),
+            active_tab,
+            ..Default::default()
+        },
+        None => Default::default(),
+    }
+}
+
+/// Bring stored state up to the current [`VERSION`].
+fn migrate(state: serde_json::Value) -> Result<PersistedState, String> {
+    let version = state
+        .get("version")
+        .and_then(serde_json::Value::as_u64)
+        .ok_or("no version")?;
+    match u32::try_from(version) {
+        Ok(VERSION) => serde_json::from_value(state).map_err(|err| err.to_string()),
+        _ => Err(format!(
+            "version {version} is not supported, expected {VERSION}"
+        )),
+    }
+}
+
+pub fn save(storage: &mut dyn eframe::Storage, state: &PersistedState) {
+    match serde_json::to_string(state) {
+        Ok(json) => storage.set_string(KEY, json),
+        Err(err) => re_log::warn!("Failed to store the Cartographer panel state: {err}"),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashMap;
+
+    use super::*;
+    use eframe::Storage as _;
+
+    #[derive(Default)]
+    struct MemoryStorage(HashMap<String, String>);
+
+    impl eframe::Storage for MemoryStorage {
+        fn get_string(&self, key: &str) -> Option<String> {
+            self.0.get(key).cloned()
+        }
+
+        fn set_string(&mut self, key: &str, value: String) {
+            self.0.insert(key.to_owned(), value);
+        }
+
+        fn flush(&mut self) {}
+    }
+
+    #[test]
+    fn migrates_v0_tabs() {
+        let mut storage = MemoryStorage::default();
+        eframe::set_value(
+            &mut storage,
+            TABS_KEY_V0,
+            &(vec!["memory".to_owned()], Some("memory".to_owned())),
+        );
+
+        let state = load(&storage);
+        assert_eq!(state.version, VERSION);
+        assert_eq!(state.open_tabs, Some(vec!["memory".to_owned()]));
+        assert_eq!(state.active_tab.as_deref(), Some("memory"));
+        assert!(state.follow_viewer);
+        assert!(state.pinned_components.is_empty());
+    }
+
+    #[test]
+    fn migrates_v1_without_pins() {
+        let mut storage = MemoryStorage::default();
+        storage.set_string(KEY, r#"{"version": 1, "panel_width": 300.0}"#.to_owned());
+
+        let state = load(&storage);
+        assert_eq!(state.panel_width, Some(300.0));
+        assert!(state.pinned_components.is_empty());
+    }
+
+    #[test]
+    fn round_trip() {
+        let mut storage = MemoryStorage::default();
+        let pinned = (
+            re_log_types::EntityPath::from("trajectories/0/pose"),
+            re_types::ComponentName::from("rerun.components.Translation3D"),
+        );
+        let state = PersistedState {
+            panel_width: Some(300.0),
+            follow_viewer: false,
+            pin_to_latest: true,
+            timeline: Some(re_log_types::Timeline::new_sequence("frame").into()),
+            open_tabs: Some(vec!["ingest".to_owned()]),
+            active_tab: Some("ingest".to_owned()),
+            pinned_components: vec![(&pinned).into()],
+            panels: [("ingest".to_owned(), serde_json::json!({ "tolerance": 0.5 }))].into(),
+            ..Default::default()
+        };
+        save(&mut storage, &state);
+
+        let loaded = load(&storage);
+        assert_eq!(
+            serde_json::to_value(&loaded).unwrap(),
+            serde_json::to_value(&state).unwrap()
+        );
+        let timeline = re_log_types::Timeline::from(loaded.timeline.unwrap());
+        assert_eq!(timeline, re_log_types::Timeline::new_sequence("frame"));
+        let loaded_pinned: Vec<_> = loaded
+            .pinned_components
+            .into_iter()
+            .map(<(re_log_types::EntityPath, re_types::ComponentName)>::from)
+            .collect();
+        assert_eq!(loaded_pinned, [pinned]);
+    }
+
+    #[test]
+    fn unknown_version_is_default() {
+        let mut storage = MemoryStorage::default();
+        storage.set_string(KEY, r#"{"version": 99, "panel_width": 300.0}"#.to_owned());
+        // Even with the old tabs around, which are older still:
+        eframe::set_value(
+            &mut storage,
+            TABS_KEY_V0,
+            &(vec!["memory".to_owned()], None::<String>),
+        );
+
+        let state = load(&storage);
+        assert_eq!(state.version, VERSION);
+        assert_eq!(state.panel_width, None);
+        assert_eq!(state.open_tabs, None);
+    }
+}
@@ -0,0 +1,199 @@
+//! Statistics of point clouds, e.g. range scans, to spot degraded or misconfigured sensors.
+//!
+//! Everything is computed from the logged positions as they are, in the frame of the entity:
+//! transforms of the entity or its parents are not applied. Ranges are measured from the
+//! origin of the entity, which is the sensor origin when scans are logged in the sensor frame.
+
+use re_viewer::external::{
+    arrow2, egui, re_data_store, re_entity_db, re_log_types,
+    re_types::{self, ComponentName, Loggable as _},
+};
+
+use crate::history;
+
+/// Number of bars in the histograms.
+const NUM_BINS: usize = 40;
+
+pub fn position_component_name() -> ComponentName {
+    re_types::components::Position3D::name()
+}
+
+/// Which per-point component to show the distribution of, kept in egui memory.
+#[derive(Clone, Default)]
+struct StatisticsState {
+    intensity: Option<ComponentName>,
+}
+
+/// Statistics of the point cloud at the time of the query:
+/// count, bounding box, density, and histograms of range and intensity.
+///
+/// All in the frame of the entity, see the [module docs](self).
+pub fn statistics_ui(
+    ui: &mut egui::Ui,
+    entity_db: &re_entity_db::EntityDb,
+    query: &re_data_store::LatestAtQuery,
+    entity_path: &re_log_types::EntityPath,
+) {
+    let Some(positions) =
+        history::latest_at(entity_db, query, entity_path, position_component_name())
+            .and_then(|data| re_types::components::Position3D::from_arrow(&*data).ok())
+    else {
+        ui.label("No points at this time.");
+        return;
+    };
+    let points: Vec<glam::Vec3> = positions
+        .iter()
+        .map(|position| glam::vec3(position.x(), position.y(), position.z()))
+        .filter(|point| point.is_finite())
+        .collect();
+    if points.is_empty() {
+        ui.label("No points at this time.");
+        return;
+    }
+
+    let (min, max) = points.iter().fold(
+        (
+            glam::Vec3::splat(f32::INFINITY),
+            glam::Vec3::splat(f32::NEG_INFINITY),
+        ),
+        |(min, max), &point| (min.min(point), max.max(point)),
+    );
+    let size = max - min;
+    let num_invalid = positions.len() - points.len();
+
+    egui::Grid::new((entity_path, "point_cloud_statistics"))
+        .num_columns(2)
+        .show(ui, |ui| {
+            ui.label("Points");
+            ui.label(if num_invalid == 0 {
+                points.len().to_string()
+            } else {
+                format!("{} ({num_invalid} not finite)", points.len())
+            });
+            ui.end_row();
+
+            ui.label("Min");
+            ui.monospace(format!("{:.3}, {:.3}, {:.3}", min.x, min.y, min.z));
+            ui.end_row();
+
+            ui.label("Max");
+            ui.monospace(format!("{:.3}, {:.3}, {:.3}", max.x, max.y, max.z));
+            ui.end_row();
+
+            ui.label("Size");
+            ui.monospace(format!("{:.3} × {:.3} × {:.3} m", size.x, size.y, size.z));
+            ui.end_row();
+
+            // Scans are often flat, so fall back to the area if there is no volume:
+            ui.label("Density");
+            let volume = size.x * size.y * size.z;
+            let area = size.x * size.y;
+            ui.monospace(if f32::EPSILON < volume {
+                format!("{:.1} points/m³", points.len() as f32 / volume)
+            } else if f32::EPSILON < area {
+                format!("{:.1} points/m²", points.len() as f32 / area)
+            } else {
+                "–".to_owned()
+            });
+            ui.end_row();
+        });
+
+    let ranges: Vec<f64> = points.iter().map(|p| f64::from(p.length())).collect();
+    ui.label("Range from entity origin [m]")
+        .on_hover_text("The sensor origin, for scans logged in the sensor frame");
+    histogram_ui(ui, (entity_path, "range_histogram"), &ranges);
+
+    intensity_ui(ui, entity_db, query, entity_path, positions.len());
+}
+
+/// Histogram of a per-point scalar component, e.g. intensity.
+fn intensity_ui(
+    ui: &mut egui::Ui,
+    entity_db: &re_entity_db::EntityDb,
+    query: &re_data_store::LatestAtQuery,
+    entity_path: &re_log_types::EntityPath,
+    num_points: usize,
+) {
+    // Numeric components with one value per point:
+    let candidates: Vec<(ComponentName, Box<dyn arrow2::array::Array>)> = entity_db
+        .store()
+        .all_components(&query.timeline(), entity_path)
+        .into_iter()
+        .flatten()
+        .filter(|&component| component != position_component_name())
+        .filter_map(|component| {
+            let data = history::latest_at(entity_db, query, entity_path, component)?;
+            (data.len() == num_points && history::scalar(&*data, 0).is_some())
+                .then_some((component, data))
+        })
+        .collect();
+    if candidates.is_empty() {
+        return;
+    }
+
+    let id = ui.id().with((entity_path, "point_cloud_intensity"));
+    let mut state: StatisticsState = ui.data_mut(|d| d.get_temp(id)).unwrap_or_default();
+    let selected = state
+        .intensity
+        .filter(|name| candidates.iter().any(|(c, _)| c == name))
+        .unwrap_or(candidates[0].0);
+
+    ui.horizontal(|ui| {
+        ui.label("Distribution of");
+        egui::ComboBox::from_id_source(id)
+            .selected_text(selected.short_name())
+            .show_ui(ui, |ui| {
+                for (component, _) in &candidates {
+                    if ui
+                        .selectable_label(*component == selected, component.short_name())
+                        .clicked()
+                    {
+                        state.intensity = Some(*component);
+                    }
+                }
+            });
+    });
+
+    if let Some((_, data)) = candidates.iter().find(|(c, _)| *c == selected) {
+        let values: Vec<f64> = (0..data.len())
+            .filter_map(|i| history::scalar(&**data, i))
+            .filter(|v| v.is_finite())
+            .collect();
+        histogram_ui(ui, (entity_path, selected, "histogram"), &values);
+    }
+
+    ui.data_mut(|d| d.insert_temp(id, state));
+}
+
+fn histogram_ui(ui: &mut egui::Ui, id_source: impl std::hash::Hash, values: &[f64]) {
+    let (min, max) = values
+        .iter()
+        .fold((f64::INFINITY, f64::NEG_INFINITY), |(min, max), &v| {
+            (min.min(v), max.max(v))
+        });
+    if values.is_empty() {
+        return;
+    }
+
+    // All values equal gives a single bar:
+    let bin_width = ((max - min) / NUM_BINS as f64).max(f64::EPSILON);
+    let mut counts = [0_usize; NUM_BINS];
+    for value in values {
+        let bin = ((value - min) / bin_width) as usize;
+        counts[bin.min(NUM_BINS - 1)] += 1;
+    }
+
+    let bars = counts
+        .iter()
+        .enumerate()
+        .map(|(i, &count)| {
+            egui_plot::Bar::new(min + (i as f64 + 0.5) * bin_width, count as f64).width(bin_width)
+        })
+        .collect();
+    egui_plot::Plot::new(id_source)
+        .height(100.0)
+        .allow_scroll(false)
+        .show(ui, |plot_ui| {
+            plot_ui.bar_chart(egui_plot::BarChart::new(bars));
+        });
+}
@@ -0,0 +1,357 @@
+use std::{
+    collections::BTreeMap,
+    path::{Path, PathBuf},
+    sync::{
+        atomic::{AtomicBool, AtomicU64, Ordering},
+        Arc,
+    },
+    time::{Duration, Instant},
+};
+
+use re_viewer::external::{
+    re_log,
+    re_log_types::{LogMsg, StoreId},
+};
+
+/// How the headless recorder splits and retains its output files.
+#[derive(Clone, Debug)]
+pub struct RecorderOptions {
+    /// Directory the `.rrd` files are written to.
+    pub dir: PathBuf,
+
+    /// Start a new file once the current one is at least this large.
+    pub max_file_bytes: Option<u64>,
+
+    /// Start a new file once the current one has been open this long.
+    pub max_file_duration: Option<Duration>,
+
+    /// Delete the oldest files so that at most this many remain.
+    /// The file being written is always kept.
+    pub max_files: Option<usize>,
+}
+
+/// Write everything arriving on `rx` to rotating `.rrd` files, until SIGINT or until `rx` closes.
+///
+/// Each file starts with the store info of every store seen so far,
+/// so that any single file can be loaded on its own.
+pub fn run(
+    rx: &re_smart_channel::Receiver<LogMsg>,
+    options: &RecorderOptions,
+) -> Result<(), Box<dyn std::error::Error>> {
+    std::fs::create_dir_all(&options.dir)?;
+
+    let shutdown = Arc::new(AtomicBool::new(false));
+    {
+        let shutdown = shutdown.clone();
+        ctrlc::set_handler(move || shutdown.store(true, Ordering::SeqCst))?;
+    }
+
+    let mut store_infos: BTreeMap<StoreId, LogMsg> = BTreeMap::new();
+    let mut file = RrdFile::create(options, &store_infos)?;
+
+    while !shutdown.load(Ordering::SeqCst) {
+        if file.should_rotate(options) {
+            file.finish()?;
+            file = RrdFile::create(options, &store_infos)?;
+        }
+
+        let msg = match rx.recv_timeout(Duration::from_millis(100)) {
+            Ok(msg) => msg,
+            Err(re_smart_channel::RecvTimeoutError::Timeout) => continue,
+            Err(re_smart_channel::RecvTimeoutError::Disconnected) => break,
+        };
+
+        match msg.payload {
+            re_smart_channel::SmartMessagePayload::Msg(msg) => {
+                if let LogMsg::SetStoreInfo(set_store_info) = &msg {
+                    store_infos.insert(set_store_info.info.store_id.clone(), msg.clone());
+                }
+                file.append(&msg)?;
+            }
+            re_smart_channel::SmartMessagePayload::Flush { on_flush_done } => {
+                file.encoder.flush_blocking()?;
+                on_flush_done();
+            }
+            re_smart_channel::SmartMessagePayload::Quit(err) => {
+                if let Some(err) = err {
+                    re_log::warn!("Data source has left unexpectedly: {err}");
+                }
+            }
+        }
+    }
+
+    re_log::info!("Shutting down recorder");
+    file.finish()
+}
+
+/// Counts the bytes written through it, including those still buffered.
+struct CountingWriter<W> {
+    inner: W,
+    bytes: Arc<AtomicU64>,
+}
+
+impl<W: std::io::Write> std::io::Write for CountingWriter<W> {
+    fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+        let written = self.inner.write(buf)?;
+        self.bytes.fetch_add(written as u64, Ordering::Relaxed);
+        Ok(written)
+    }
+
+    fn flush(&mut self) -> std::io::Result<()> {
+        self.inner.flush()
+    }
+}
+
+/// The `.rrd` file currently being written.
+struct RrdFile {
+    path: PathBuf,
+    encoder: re_log_encoding::encoder::Encoder<CountingWriter<std::io::BufWriter<std::fs::File>>>,
+
+    /// Bytes handed to the encoder's writer so far.
+    bytes: Arc<AtomicU64>,
+
+    opened: Instant,
+}
+
+impl RrdFile {
+    fn create(
+        options: &RecorderOptions,
+        store_infos: &BTreeMap<StoreId, LogMsg>,
+    ) -> Result<Self, Box<dyn std::error::Error>> {
+        // Make room first, so the new file is never among the ones removed:
+        if let Some(max_files) = options.max_files {
+            remove_old_files(&options.dir, max_files.saturating_sub(1))?;
+        }
+
+        let (path, handle) = create_next_file(&options.dir)?;
+        let bytes = Arc::new(AtomicU64::new(0));
+        let encoder = re_log_encoding::encoder::Encoder::new(
+            re_build_info::CrateVersion::LOCAL,
+            re_log_encoding::EncodingOptions::COMPRESSED,
+            CountingWriter {
+                inner: std::io::BufWriter::new(handle),
+                bytes: bytes.clone(),
+            },
+        )?;
+        re_log::info!("Recording to {}", path.display());
+
+        let mut file = Self {
+            path,
+            encoder,
+            bytes,
+            opened: Instant::now(),
+        };
+        for msg in store_infos.values() {
+            file.append(msg)?;
+        }
+
+        Ok(file)
+    }
+
+    fn append(&mut self, msg: &LogMsg) -> Result<(), Box<dyn std::error::Error>> {
+        self.encoder.append(msg)?;
+        Ok(())
+    }
+
+    fn should_rotate(&self, options: &RecorderOptions) -> bool {
+        let too_big = options
+            .max_file_bytes
+            .is_some_and(|max_bytes| max_bytes <= self.bytes.load(Ordering::Relaxed));
+        let too_old = options
+            .max_file_duration
+            .is_some_and(|max_duration| max_duration <= self.opened.elapsed());
+        too_big || too_old
+    }
+
+    fn finish(mut self) -> Result<(), Box<dyn std::error::Error>> {
+        self.encoder.flush_blocking()?;
+        re_log::info!("Finished {}", self.path.display());
+        Ok(())
+    }
+}
+
+const FILE_PREFIX: &str = "cartographer_";
+
+/// Create a new file whose name sorts after all earlier ones.
+///
+/// Files created within the same millisecond get a counter suffix,
+/// and existing files are never overwritten.
+fn create_next_file(dir: &Path) -> Result<(PathBuf, std::fs::File), Box<dyn std::error::Error>> {
+    let since_epoch = std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .unwrap_or_default();
+    for counter in 0..10_000 {
+        let path = dir.join(format!(
+            "{FILE_PREFIX}{:020}_{counter:04}.rrd",
+            since_epoch.as_millis()
+        ));
+        match std::fs::File::options()
+            .write(true)
+            .create_new(true)
+            .open(&path)
+        {
+            Ok(file) => return Ok((path, file)),
+            Err(err) if err.kind() == std::io::ErrorKind::AlreadyExists => continue,
+            Err(err) => return Err(format!("Failed to create {}: {err}", path.display()).into()),
+        }
+    }
+    Err(format!("Failed to find a free file name in {}", dir.display()).into())
+}
+
+fn remove_old_files(dir: &Path, max_files: usize) -> std::io::Result<()> {
+    let mut files: Vec<PathBuf> = std::fs::read_dir(dir)?
+        .filter_map(|entry| Some(entry.ok()?.path()))
+        .filter(|path| {
+            path.extension().is_some_and(|ext| ext == "rrd")
+                && path
+                    .file_name()
+                    .is_some_and(|name| name.to_string_lossy().starts_with(FILE_PREFIX))
+        })
+        .collect();
+    files.sort();
+
+    let num_to_remove = files.len().saturating_sub(max_files);
+    for path in files.drain(..num_to_remove) {
+        re_log::info!("Removing old recording {}", path.display());
+        std::fs::remove_file(&path)?;
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use re_viewer::external::re_log_types::{
+        ApplicationId, RowId, SetStoreInfo, StoreInfo, StoreKind, StoreSource, Time,
+    };
+
+    use super::*;
+
+    fn options(dir: &Path) -> RecorderOptions {
+        RecorderOptions {
+            dir: dir.to_owned(),
+            max_file_bytes: None,
+            max_file_duration: None,
+            max_files: None,
+        }
+    }
+
+    fn store_info() -> SetStoreInfo {
+        SetStoreInfo {
+            row_id: RowId::new(),
+            info: StoreInfo {
+                application_id: ApplicationId::from("test"),
+                store_id: StoreId::random(StoreKind::Recording),
+                cloned_from: None,
+                is_official_example: false,
+                started: Time::now(),
+                store_source: StoreSource::Unknown,
+                store_version: Some(re_build_info::CrateVersion::LOCAL),
+            },
+        }
+    }
+
+    fn file_names(dir: &Path) -> Vec<String> {
+        let mut names: Vec<String> = std::fs::read_dir(dir)
+            .unwrap()
+            .map(|entry| entry.unwrap().file_name().to_string_lossy().into_owned())
+            .collect();
+        names.sort();
+        names
+    }
+
+    #[test]
+    fn unique_names() {
+        let dir = tempfile::tempdir().unwrap();
+        // Many in the same millisecond:
+        let paths: Vec<PathBuf> = (0..20)
+            .map(|_| create_next_file(dir.path()).unwrap().0)
+            .collect();
+
+        let names: Vec<String> = paths
+            .iter()
+            .map(|path| path.file_name().unwrap().to_string_lossy().into_owned())
+            .collect();
+        for name in &names {
+            assert!(
+                name.starts_with(FILE_PREFIX) && name.ends_with(".rrd"),
+                "{name}"
+            );
+            // `cartographer_{millis:020}_{counter:04}.rrd`:
+            assert_eq!(name.len(), FILE_PREFIX.len() + 20 + 1 + 4 + 4, "{name}");
+        }
+        // In the order they were created, with none overwritten:
+        let mut sorted = names.clone();
+        sorted.sort();
+        sorted.dedup();
+        assert_eq!(sorted, names);
+        assert_eq!(file_names(dir.path()), names);
+    }
+
+    #[test]
+    fn rotation() {
+        let dir = tempfile::tempdir().unwrap();
+        let set_store_info = store_info();
+        let store_infos = BTreeMap::from([(
+            set_store_info.info.store_id.clone(),
+            LogMsg::SetStoreInfo(set_store_info.clone()),
+        )]);
+
+        let file = RrdFile::create(&options(dir.path()), &store_infos).unwrap();
+        assert!(!file.should_rotate(&options(dir.path())));
+
+        let by_size = RecorderOptions {
+            max_file_bytes: Some(1),
+            ..options(dir.path())
+        };
+        assert!(file.should_rotate(&by_size));
+        let by_duration = RecorderOptions {
+            max_file_duration: Some(Duration::ZERO),
+            ..options(dir.path())
+        };
+        assert!(file.should_rotate(&by_duration));
+
+        // Every file starts with the store infos, to be loaded on its own:
+        let path = file.path.clone();
+        file.finish().unwrap();
+        let decoder = re_log_encoding::decoder::Decoder::new(
+            re_log_encoding::decoder::VersionPolicy::Error,
+            std::io::BufReader::new(std::fs::File::open(path).unwrap()),
+        )
+        .unwrap();
+        let msgs: Vec<LogMsg> = decoder.map(Result::unwrap).collect();
+        assert!(matches!(
+            msgs.as_slice(),
+            [LogMsg::SetStoreInfo(info)] if info.info.store_id == set_store_info.info.store_id
+        ));
+    }
+
+    #[test]
+    fn removes_old_files() {
+        let dir = tempfile::tempdir().unwrap();
+        let old: Vec<PathBuf> = (0..3)
+            .map(|_| create_next_file(dir.path()).unwrap().0)
+            .collect();
+        // Not ours, so never removed:
+        for name in ["other.rrd", "cartographer_notes.txt"] {
+            std::fs::write(dir.path().join(name), "").unwrap();
+        }
+
+        remove_old_files(dir.path(), 2).unwrap();
+        assert!(!old[0].exists() && old[1].exists() && old[2].exists());
+
+        // A new file makes room for itself:
+        let max_files = RecorderOptions {
+            max_files: Some(2),
+            ..options(dir.path())
+        };
+        let file = RrdFile::create(&max_files, &BTreeMap::new()).unwrap();
+        assert!(!old[1].exists() && old[2].exists() && file.path.exists());
+        assert_eq!(file_names(dir.path()).len(), 4);
+
+        remove_old_files(dir.path(), 0).unwrap();
+        assert_eq!(
+            file_names(dir.path()),
+            ["cartographer_notes.txt", "other.rrd"]
+        );
+    }
+}
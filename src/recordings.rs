@@ -0,0 +1,133 @@
+use std::sync::{Arc, Mutex};
+
+use re_viewer::external::{
+    re_log,
+    re_log_types::{ApplicationId, ArrowMsg, DataTable, LogMsg, StoreInfo, StoreKind},
+};
+
+use crate::{ingest::IngestMonitor, memory::Pruner};
+
+/// Count the message for the [`IngestMonitor`] and prune it, decoding it at most once.
+///
+/// Returns `None` if the [`Pruner`] dropped all of it.
+fn inspect(
+    ingest: &IngestMonitor,
+    pruner: &Pruner,
+    source: &re_smart_channel::SmartMessageSource,
+    arrow_msg: ArrowMsg,
+) -> Option<ArrowMsg> {
+    let monitored = IngestMonitor::is_monitored(source);
+    if !monitored && !pruner.is_active() {
+        return Some(arrow_msg);
+    }
+
+    let rows: Vec<_> = match DataTable::from_arrow_msg(&arrow_msg) {
+        Ok(table) => table.to_rows().filter_map(Result::ok).collect(),
+        Err(err) => {
+            re_log::debug!("Failed to read message from {source}: {err}");
+            return Some(arrow_msg);
+        }
+    };
+    if monitored {
+        ingest.record(source, &rows);
+    }
+    if pruner.is_active() {
+        pruner.apply(arrow_msg, rows)
+    } else {
+        Some(arrow_msg)
+    }
+}
+
+/// Keeps track of the recordings that arrive over the receivers we feed to the viewer.
+///
+/// Every receiver is wrapped by [`Recordings::watch`], which forwards all messages
+/// unchanged but takes note of each new [`StoreInfo`] on the way,
+/// and of the message rates for the [`IngestMonitor`].
+/// Data is pruned by the [`Pruner`] on the way, if configured.
+#[derive(Clone, Default)]
+pub struct Recordings {
+    known: Arc<Mutex<Vec<StoreInfo>>>,
+
+    /// Rates and latency of everything we forward.
+    pub ingest: IngestMonitor,
+
+    /// Thins out incoming data before it reaches the viewer.
+    pub pruner: Pruner,
+}
+
+impl Recordings {
+    /// Forward everything from `rx` to the returned receiver, recording any new stores.
+    pub fn watch(
+        &self,
+        rx: re_smart_channel::Receiver<LogMsg>,
+    ) -> re_smart_channel::Receiver<LogMsg> {
+        let (tx, forwarded) = re_smart_channel::smart_channel(
+            re_smart_channel::SmartMessageSource::Unknown,
+            rx.source().clone(),
+        );
+
+        let known = self.known.clone();
+        let ingest = self.ingest.clone();
+        let pruner = self.pruner.clone();
+        let spawned = std::thread::Builder::new()
+            .name(format!("watch {}", rx.source()))
+            .spawn(move || {
+                while let Ok(msg) = rx.recv() {
+                    if let re_smart_channel::SmartMessagePayload::Msg(LogMsg::SetStoreInfo(
+                        set_store_info,
+                    )) = &msg.payload
+                    {
+                        let info = &set_store_info.info;
+                        let mut known = known.lock().unwrap();
+                        if !known.iter().any(|k| k.store_id == info.store_id) {
+                            known.push(info.clone());
+                        }
+                    }
+
+                    let payload = match msg.payload {
+                        re_smart_channel::SmartMessagePayload::Msg(LogMsg::ArrowMsg(
+                            store_id,
+                            arrow_msg,
+                        )) => match inspect(&ingest, &pruner, &msg.source, arrow_msg) {
+                            Some(arrow_msg) => re_smart_channel::SmartMessagePayload::Msg(
+                                LogMsg::ArrowMsg(store_id, arrow_msg),
+                            ),
+                            None => continue,
+                        },
+                        payload => payload,
+                    };
+
+                    if tx.send_at(msg.time, msg.source, payload).is_err() {
+                        // The viewer hung up.
+                        return;
+                    }
+                }
+            });
+
+        if let Err(err) = spawned {
+            re_log::error!("Failed to spawn watcher thread: {err}");
+        }
+
+        forwarded
+    }
+
+    /// Did a blueprint arrive for the application, e.g. one sent by the logging code?
+    pub fn has_blueprint(&self, app_id: &ApplicationId) -> bool {
+        self.known.lock().unwrap().iter().any(|info| {
+            info.store_id.kind == StoreKind::Blueprint && &info.application_id == app_id
+        })
+    }
+
+    /// All recordings seen so far, in the order they arrived.
+    ///
+    /// Blueprints are not included.
+    pub fn list(&self) -> Vec<StoreInfo> {
+        self.known
+            .lock()
+            .unwrap()
+            .iter()
+            .filter(|info| info.store_id.kind == StoreKind::Recording)
+            .cloned()
+            .collect()
+    }
+}
@@ -0,0 +1,317 @@
+//! Save probability grids as ROS `map_server` / Nav2 maps: a `.pgm` image plus a `.yaml`.
+
+use std::{io::Write as _, path::Path};
+
+use re_viewer::external::{egui, re_data_store, re_entity_db, re_log, re_log_types};
+
+use crate::grid::{Grid, GridLimits};
+
+/// Map metadata written to the `.yaml`.
+#[derive(Clone, Copy, Debug)]
+pub struct MapSettings {
+    /// Size of a cell, in meters.
+    ///
+    /// Ignored for grids with [`GridLimits`], which know their resolution.
+    pub resolution: f64,
+
+    /// Position of the lower-left cell in the map frame, in meters.
+    ///
+    /// Ignored for grids with [`GridLimits`], which know where they are.
+    pub origin: [f64; 2],
+
+    /// Cells with an occupancy probability above this are occupied.
+    pub occupied_thresh: f32,
+
+    /// Cells with an occupancy probability below this are free.
+    pub free_thresh: f32,
+}
+
+impl Default for MapSettings {
+    /// The defaults of ROS `map_saver`.
+    fn default() -> Self {
+        Self {
+            resolution: 0.05,
+            origin: [0.0, 0.0],
+            occupied_thresh: 0.65,
+            free_thresh: 0.196,
+        }
+    }
+}
+
+impl MapSettings {
+    /// These settings, with the resolution and origin of `grid` if it has [`GridLimits`].
+    pub fn with_limits_of(self, grid: &Grid) -> Self {
+        let Some(limits) = grid.limits else {
+            return self;
+        };
+        // Rows run along x and columns along y, see `GridLimits`:
+        Self {
+            resolution: limits.resolution,
+            origin: [
+                limits.max[0] - grid.height as f64 * limits.resolution,
+                limits.max[1] - grid.width as f64 * limits.resolution,
+            ],
+            ..self
+        }
+    }
+}
+
+/// Pixel values used by `map_saver` in trinary mode.
+const OCCUPIED: u8 = 0;
+const FREE: u8 = 254;
+const UNKNOWN: u8 = 205;
+
+/// Export the grid logged to `entity_path`, at the time of the query.
+pub fn export_ui(
+    ui: &mut egui::Ui,
+    entity_db: &re_entity_db::EntityDb,
+    query: &re_data_store::LatestAtQuery,
+    entity_path: &re_log_types::EntityPath,
+) {
+    egui::CollapsingHeader::new("Export ROS map")
+        .id_source((entity_path, "ros_map"))
+        .show(ui, |ui| {
+            let id = ui.id().with("settings");
+            let mut settings: MapSettings = ui.data_mut(|d| d.get_temp(id)).unwrap_or_default();
+            let limits = GridLimits::latest_at(entity_db, query, entity_path);
+
+            egui::Grid::new("ros_map_settings")
+                .num_columns(2)
+                .show(ui, |ui| {
+                    if let Some(limits) = limits {
+                        ui.label("Resolution (m/cell)");
+                        ui.label(limits.resolution.to_string());
+                        ui.end_row();
+
+                        ui.label("Origin (m)");
+                        ui.label("From the grid limits");
+                        ui.end_row();
+                    } else {
+                        ui.label("Resolution (m/cell)");
+                        ui.add(
+                            egui::DragValue::new(&mut settings.resolution)
+                                .speed(0.001)
+                                .range(0.001..=10.0),
+                        );
+                        ui.end_row();
+
+                        ui.label("Origin (m)");
+                        ui.horizontal(|ui| {
+                            ui.add(egui::DragValue::new(&mut settings.origin[0]).speed(0.1));
+                            ui.add(egui::DragValue::new(&mut settings.origin[1]).speed(0.1));
+                        });
+                        ui.end_row();
+                    }
+
+                    ui.label("Occupied threshold");
+                    ui.add(egui::Slider::new(&mut settings.occupied_thresh, 0.0..=1.0));
+                    ui.end_row();
+
+                    ui.label("Free threshold");
+                    ui.add(egui::Slider::new(&mut settings.free_thresh, 0.0..=1.0));
+                    ui.end_row();
+                });
+
+            if ui.button("Save map…").clicked() {
+                save_ui(entity_db, query, entity_path, &settings);
+            }
+
+            ui.data_mut(|d| d.insert_temp(id, settings));
+        });
+}
+
+fn save_ui(
+    entity_db: &re_entity_db::EntityDb,
+    query: &re_data_store::LatestAtQuery,
+    entity_path: &re_log_types::EntityPath,
+    settings: &MapSettings,
+) {
+    let grid = match Grid::latest_at(entity_db, query, entity_path) {
+        Ok(grid) => grid,
+        Err(err) => {
+            re_log::error!("Cannot export {entity_path} as a map: {err}");
+            return;
+        }
+    };
+    if grid.truncation_distance.is_some() {
+        re_log::error!("Cannot export {entity_path} as a map: TSDF grids have no occupancy");
+        return;
+    }
+
+    let Some(pgm_path) = rfd::FileDialog::new()
+        .set_file_name("map.pgm")
+        .add_filter("Portable graymap", &["pgm"])
+        .save_file()
+    else {
+        return;
+    };
+
+    match save_map(&grid, settings, &pgm_path) {
+        Ok(()) => re_log::info!("Saved map to {}", pgm_path.display()),
+        Err(err) => re_log::error!("Failed to save map to {}: {err}", pgm_path.display()),
+    }
+}
+
+/// The probabilities of `grid` as `(width, height, pixels)` of the map image,
+/// with x to the right and the largest y in the top row, as `map_server` expects.
+fn map_image(grid: &Grid) -> (usize, usize, Vec<f32>) {
+    let (width, height) = (grid.width, grid.height);
+    let cell = |row: usize, col: usize| grid.values[row * width + col];
+
+    if grid.limits.is_some() {
+        // Cartographer: rows run along -x and columns along -y, see `GridLimits`.
+        let pixels = (0..width)
+            .flat_map(|col| (0..height).rev().map(move |row| cell(row, col)))
+            .collect();
+        (height, width, pixels)
+    } else if grid.bottom_up {
+        let pixels = (0..height)
+            .rev()
+            .flat_map(|row| (0..width).map(move |col| cell(row, col)))
+            .collect();
+        (width, height, pixels)
+    } else {
+        (width, height, grid.values.clone())
+    }
+}
+
+/// Write `{name}.pgm` and `{name}.yaml`.
+pub fn save_map(grid: &Grid, settings: &MapSettings, pgm_path: &Path) -> std::io::Result<()> {
+    let settings = settings.with_limits_of(grid);
+    let (width, height, probabilities) = map_image(grid);
+
+    let mut pgm = std::io::BufWriter::new(std::fs::File::create(pgm_path)?);
+    write!(
+        pgm,
+        "P5\n# CREATOR: cartographer_rerun {:.3} m/pix\n{width} {height}\n255\n",
+        settings.resolution
+    )?;
+    let pixels: Vec<u8> = probabilities
+        .iter()
+        .map(|&p| {
+            if p.is_nan() {
+                UNKNOWN
+            } else if settings.occupied_thresh < p {
+                OCCUPIED
+            } else if p < settings.free_thresh {
+                FREE
+            } else {
+                UNKNOWN
+            }
+        })
+        .collect();
+    pgm.write_all(&pixels)?;
+    pgm.flush()?;
+
+    let image = pgm_path
+        .file_name()
+        .map(|name| name.to_string_lossy().into_owned())
+        .unwrap_or_default();
+    let yaml = format!(
+        "image: {image}\n\
+         mode: trinary\n\
+         resolution: {}\n\
+         origin: [{}, {}, 0.0]\n\
+         negate: 0\n\
+         occupied_thresh: {}\n\
+         free_thresh: {}\n",
+        settings.resolution,
+        settings.origin[0],
+        settings.origin[1],
+        settings.occupied_thresh,
+        settings.free_thresh,
+    );
+    std::fs::write(pgm_path.with_extension("yaml"), yaml)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// 3 columns and 2 rows, with the value of each cell telling where it is.
+    fn grid(limits: Option<GridLimits>, bottom_up: bool) -> Grid {
+        Grid {
+            width: 3,
+            height: 2,
+            values: vec![0.0, 0.1, 0.2, 0.3, 0.4, 0.5],
+            truncation_distance: None,
+            limits,
+            bottom_up,
+        }
+    }
+
+    const LIMITS: GridLimits = GridLimits {
+        resolution: 0.5,
+        max: [10.0, 20.0],
+    };
+
+    #[test]
+    fn image_orientation() {
+        // Images already have their first row at the top:
+        assert_eq!(
+            map_image(&grid(None, false)),
+            (3, 2, vec![0.0, 0.1, 0.2, 0.3, 0.4, 0.5])
+        );
+
+        // ROS grids have it at the bottom:
+        assert_eq!(
+            map_image(&grid(None, true)),
+            (3, 2, vec![0.3, 0.4, 0.5, 0.0, 0.1, 0.2])
+        );
+
+        // Cartographer's rows run along -x and its columns along -y, so the image is
+        // transposed: the top left is the last row (least x) and first column (most y).
+        assert_eq!(
+            map_image(&grid(Some(LIMITS), false)),
+            (2, 3, vec![0.3, 0.0, 0.4, 0.1, 0.5, 0.2])
+        );
+    }
+
+    #[test]
+    fn origin_and_resolution() {
+        let settings = MapSettings {
+            resolution: 0.1,
+            origin: [1.0, 2.0],
+            ..Default::default()
+        };
+
+        let unchanged = settings.with_limits_of(&grid(None, true));
+        assert_eq!((unchanged.resolution, unchanged.origin), (0.1, [1.0, 2.0]));
+
+        // The lower left corner of 2 rows along x and 3 columns along y:
+        let limited = settings.with_limits_of(&grid(Some(LIMITS), false));
+        assert_eq!((limited.resolution, limited.origin), (0.5, [9.0, 18.5]));
+    }
+
+    #[test]
+    fn saves_map() {
+        let dir = tempfile::tempdir().unwrap();
+        let pgm_path = dir.path().join("map.pgm");
+        let grid = Grid {
+            values: vec![f32::NAN, 0.0, 0.19, 0.5, 0.66, 1.0],
+            ..grid(Some(LIMITS), false)
+        };
+        save_map(&grid, &MapSettings::default(), &pgm_path).unwrap();
+
+        let pgm = std::fs::read(&pgm_path).unwrap();
+        let header = b"P5\n# CREATOR: cartographer_rerun 0.500 m/pix\n2 3\n255\n";
+        assert_eq!(&pgm[..header.len()], header);
+        // Transposed as in `image_orientation`:
+        assert_eq!(
+            &pgm[header.len()..],
+            [UNKNOWN, UNKNOWN, OCCUPIED, FREE, OCCUPIED, FREE]
+        );
+
+        let yaml = std::fs::read_to_string(dir.path().join("map.yaml")).unwrap();
+        assert_eq!(
+            yaml,
+            "image: map.pgm\n\
+             mode: trinary\n\
+             resolution: 0.5\n\
+             origin: [9, 18.5, 0.0]\n\
+             negate: 0\n\
+             occupied_thresh: 0.65\n\
+             free_thresh: 0.196\n"
+        );
+    }
+}
@@ -0,0 +1,246 @@
+use std::sync::Arc;
+
+use re_viewer::external::{
+    egui, re_data_store, re_entity_db, re_log_types, re_types::ComponentName,
+};
+
+use crate::{history, stats::Stats, time_cursor};
+
+/// Points of a plot and their statistics, extended as values are logged.
+#[derive(Clone)]
+struct CachedPoints {
+    key: PointsKey,
+    generation: re_data_store::StoreGeneration,
+
+    /// Time range of the entity when last updated, to notice removed or earlier values.
+    entity_time_range: re_log_types::ResolvedTimeRange,
+
+    /// The last time with points, and how many there are at it.
+    last: Option<(re_log_types::TimeInt, usize)>,
+
+    points: Arc<Vec<[f64; 2]>>,
+    stats: Option<Stats>,
+}
+
+#[derive(Clone, PartialEq)]
+struct PointsKey {
+    store_id: re_log_types::StoreId,
+    timeline: re_log_types::Timeline,
+}
+
+impl CachedPoints {
+    fn new(key: PointsKey) -> Self {
+        Self {
+            key,
+            generation: Default::default(),
+            entity_time_range: re_log_types::ResolvedTimeRange::EMPTY,
+            last: None,
+            points: Default::default(),
+            stats: None,
+        }
+    }
+
+    /// Add the values logged since the last update.
+    ///
+    /// Starts over when values were removed, e.g. by the memory limit,
+    /// or logged before the first one seen so far.
+    fn update(
+        &mut self,
+        entity_db: &re_entity_db::EntityDb,
+        entity_path: &re_log_types::EntityPath,
+        component_name: ComponentName,
+    ) {
+        let generation = entity_db.generation();
+        if self.generation == generation {
+            return;
+        }
+        let timeline = self.key.timeline;
+        let entity_time_range = entity_db
+            .store()
+            .entity_stats(timeline, entity_path.hash())
+            .time_range;
+        if entity_time_range.min() != self.entity_time_range.min() {
+            *self = Self::new(self.key.clone());
+        }
+        self.generation = generation;
+        self.entity_time_range = entity_time_range;
+
+        // The last time is queried again, in case more values were logged at it:
+        let points = Arc::make_mut(&mut self.points);
+        let from = match self.last {
+            Some((time, num_points)) => {
+                points.truncate(points.len() - num_points);
+                time
+            }
+            None => re_log_types::TimeInt::MIN,
+        };
+        let samples = history::component_history_in(
+            entity_db,
+            timeline,
+            re_log_types::ResolvedTimeRange::new(from, re_log_types::TimeInt::MAX),
+            entity_path,
+            component_name,
+        );
+        for sample in samples {
+            let Some(value) = history::scalar(&*sample.data, 0) else {
+                continue;
+            };
+            points.push([time_cursor::time_as_f64(timeline, sample.time), value]);
+            self.last = match self.last {
+                Some((time, num_points)) if time == sample.time => Some((time, num_points + 1)),
+                _ => Some((sample.time, 1)),
+            };
+        }
+
+        self.stats = Stats::new(points.iter().map(|[_, y]| *y));
+    }
+}
+
+/// The line through `points`, sampled at the plot's resolution so that
+/// the points are shared with the cache rather than copied every frame.
+fn line(points: Arc<Vec<[f64; 2]>>, num_samples: usize) -> egui_plot::PlotPoints {
+    let (Some(&[first_x, _]), Some(&[last_x, _])) = (points.first(), points.last()) else {
+        return egui_plot::PlotPoints::default();
+    };
+    if first_x == last_x {
+        return points.to_vec().into();
+    }
+    egui_plot::PlotPoints::from_explicit_callback(
+        move |x| interpolate(&points, x),
+        first_x..=last_x,
+        num_samples.max(2),
+    )
+}
+
+/// The value of the line through `points` at `x`, which are sorted by x.
+fn interpolate(points: &[[f64; 2]], x: f64) -> f64 {
+    let next = points.partition_point(|[point_x, _]| *point_x < x);
+    match (next.checked_sub(1).map(|i| points[i]), points.get(next)) {
+        (Some([x0, y0]), Some(&[x1, y1])) if x0 < x1 => y0 + (y1 - y0) * (x - x0) / (x1 - x0),
+        (_, Some(&[_, y])) | (Some([_, y]), None) => y,
+        (None, None) => f64::NAN,
+    }
+}
+
+/// Plot the history of a numeric component, with min/max/mean statistics.
+///
+/// `cursor` is the time shown in the rest of the panel, drawn as a vertical line.
+pub fn scalar_plot_ui(
+    ui: &mut egui::Ui,
+    entity_db: &re_entity_db::EntityDb,
+    timeline: re_log_types::Timeline,
+    entity_path: &re_log_types::EntityPath,
+    component_name: ComponentName,
+    cursor: Option<re_log_types::TimeInt>,
+) {
+    // Querying the whole history is too slow to do every frame, so only new values are:
+    let id = ui.id().with((entity_path, component_name, "plot_points"));
+    let key = PointsKey {
+        store_id: entity_db.store_id().clone(),
+        timeline,
+    };
+    // Taken out of the memory, so that the points aren't copied when extended:
+    let cached = ui.data_mut(|d| {
+        let cached = d.get_temp::<CachedPoints>(id);
+        d.remove::<CachedPoints>(id);
+        cached
+    });
+    let mut cached = cached
+        .filter(|cached| cached.key == key)
+        .unwrap_or_else(|| CachedPoints::new(key));
+    cached.update(entity_db, entity_path, component_name);
+    let (points, stats) = (cached.points.clone(), cached.stats);
+    ui.data_mut(|d| d.insert_temp(id, cached));
+
+    let Some(stats) = stats else {
+        ui.label("No values on this timeline.");
+        return;
+    };
+    ui.label(format!(
+        "n: {}  min: {}  max: {}  mean: {}",
+        stats.count,
+        re_format::format_f64(stats.min),
+        re_format::format_f64(stats.max),
+        re_format::format_f64(stats.mean),
+    ));
+
+    egui_plot::Plot::new((entity_path, component_name, "plot"))
+        .height(120.0)
+        .allow_scroll(false)
+        .show(ui, |plot_ui| {
+            let num_samples = plot_ui.response().rect.width() as usize;
+            plot_ui.line(
+                egui_plot::Line::new(line(points, num_samples)).name(component_name.short_name()),
+            );
+            if let Some(cursor) = cursor {
+                plot_ui.vline(egui_plot::VLine::new(time_cursor::time_as_f64(
+                    timeline, cursor,
+                )));
+            }
+        });
+}
+
+#[cfg(test)]
+mod tests {
+    use re_viewer::external::re_types::{components, Loggable as _};
+
+    use super::*;
+
+    fn log(entity_db: &mut re_entity_db::EntityDb, frame: i64, value: f64) {
+        let row = re_log_types::DataRow::from_cells1_sized(
+            re_log_types::RowId::new(),
+            "speed",
+            [(re_log_types::Timeline::new_sequence("frame"), frame)],
+            [components::Scalar::from(value)],
+        )
+        .unwrap();
+        entity_db.add_data_row(row).unwrap();
+    }
+
+    #[test]
+    fn updates_incrementally() {
+        let mut entity_db = re_entity_db::EntityDb::new(re_log_types::StoreId::random(
+            re_log_types::StoreKind::Recording,
+        ));
+        let entity_path = re_log_types::EntityPath::from("speed");
+        let component_name = components::Scalar::name();
+        let mut cached = CachedPoints::new(PointsKey {
+            store_id: entity_db.store_id().clone(),
+            timeline: re_log_types::Timeline::new_sequence("frame"),
+        });
+
+        log(&mut entity_db, 1, 1.0);
+        log(&mut entity_db, 2, 2.0);
+        cached.update(&entity_db, &entity_path, component_name);
+        assert_eq!(*cached.points, [[1.0, 1.0], [2.0, 2.0]]);
+
+        // More at the last time, and later:
+        log(&mut entity_db, 2, 3.0);
+        log(&mut entity_db, 3, 4.0);
+        cached.update(&entity_db, &entity_path, component_name);
+        assert_eq!(
+            *cached.points,
+            [[1.0, 1.0], [2.0, 2.0], [2.0, 3.0], [3.0, 4.0]]
+        );
+        assert_eq!(cached.stats.map(|stats| stats.max), Some(4.0));
+
+        // Earlier than everything so far:
+        log(&mut entity_db, 0, 0.0);
+        cached.update(&entity_db, &entity_path, component_name);
+        assert_eq!(
+            *cached.points,
+            [[0.0, 0.0], [1.0, 1.0], [2.0, 2.0], [2.0, 3.0], [3.0, 4.0]]
+        );
+    }
+
+    #[test]
+    fn interpolates() {
+        let points = [[0.0, 0.0], [1.0, 2.0], [1.0, 4.0], [3.0, 0.0]];
+        let values: Vec<f64> = [-1.0, 0.0, 0.5, 1.0, 2.0, 3.0, 4.0]
+            .into_iter()
+            .map(|x| interpolate(&points, x))
+            .collect();
+        assert_eq!(values, [0.0, 0.0, 1.0, 2.0, 2.0, 0.0, 0.0]);
+        assert!(interpolate(&[], 0.0).is_nan());
+    }
+}
@@ -0,0 +1,69 @@
+//! Summary statistics of a set of values, e.g. of a plotted component or of trajectory errors.
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Stats {
+    pub count: usize,
+    pub min: f64,
+    pub max: f64,
+    pub mean: f64,
+    pub median: f64,
+
+    /// Root mean square, the usual summary of errors.
+    pub rmse: f64,
+}
+
+impl Stats {
+    /// `None` if there are no values.
+    pub fn new(values: impl IntoIterator<Item = f64>) -> Option<Self> {
+        let mut sorted: Vec<f64> = values.into_iter().collect();
+        if sorted.is_empty() {
+            return None;
+        }
+        sorted.sort_by(f64::total_cmp);
+
+        let count = sorted.len();
+        let n = count as f64;
+        let mid = count / 2;
+        let median = if count % 2 == 0 {
+            0.5 * (sorted[mid - 1] + sorted[mid])
+        } else {
+            sorted[mid]
+        };
+
+        Some(Self {
+            count,
+            min: sorted[0],
+            max: sorted[count - 1],
+            mean: sorted.iter().sum::<f64>() / n,
+            median,
+            rmse: (sorted.iter().map(|v| v * v).sum::<f64>() / n).sqrt(),
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn stats() {
+        assert_eq!(Stats::new([]), None);
+
+        assert_eq!(
+            Stats::new([4.0, -2.0, 1.0]),
+            Some(Stats {
+                count: 3,
+                min: -2.0,
+                max: 4.0,
+                mean: 1.0,
+                median: 1.0,
+                rmse: 7.0_f64.sqrt(),
+            })
+        );
+
+        // The median of an even count is between the middle values:
+        let stats = Stats::new([3.0, 1.0, 2.0, 10.0]).unwrap();
+        assert_eq!(stats.median, 2.5);
+        assert_eq!(stats.mean, 4.0);
+    }
+}
@@ -0,0 +1,339 @@
+//! Submaps and their metadata, logged as a custom component.
+//!
+//! Submaps live at `submaps/{trajectory_id}/{submap_index}`, with their global pose as the
+//! transform of that entity, their grid as a child entity (with [`crate::grid::GridLimits`]),
+//! and a [`COMPONENT_NAME`] component with the metadata Cartographer keeps for each submap.
+
+use std::collections::BTreeSet;
+
+use re_viewer::external::{
+    arrow2::{
+        self,
+        array::{Array, BooleanArray, Float64Array, Int32Array, StructArray},
+        datatypes::{DataType, Field},
+    },
+    egui, re_data_store, re_entity_db, re_log_types, re_types,
+};
+
+use crate::{constraints::TrajectoryIndex, history, trajectory::Pose};
+
+pub const COMPONENT_NAME: &str = "cartographer.components.SubmapInfo";
+
+/// Parent of all submap entities.
+pub const ROOT: &str = "submaps";
+
+/// Metadata of a submap, as in Cartographer's `Submap2D` / `Submap3D`.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct SubmapInfo {
+    /// Number of range data inserted into the submap.
+    pub num_range_data: i32,
+
+    /// Finished submaps no longer change.
+    pub finished: bool,
+
+    /// Size of a grid cell in meters, or `NaN` if unknown (e.g. for 3D submaps).
+    pub resolution: f64,
+
+    /// Pose in the local SLAM frame, before pose graph optimization.
+    pub local_pose: Pose,
+}
+
+const FIELDS: [(&str, DataType); 10] = [
+    ("num_range_data", DataType::Int32),
+    ("finished", DataType::Boolean),
+    ("resolution", DataType::Float64),
+    ("tx", DataType::Float64),
+    ("ty", DataType::Float64),
+    ("tz", DataType::Float64),
+    ("qx", DataType::Float64),
+    ("qy", DataType::Float64),
+    ("qz", DataType::Float64),
+    ("qw", DataType::Float64),
+];
+
+fn data_type() -> DataType {
+    DataType::Struct(std::sync::Arc::new(
+        FIELDS
+            .iter()
+            .map(|(name, data_type)| Field::new(*name, data_type.clone(), false))
+            .collect(),
+    ))
+}
+
+pub fn to_arrow(infos: &[SubmapInfo]) -> Box<dyn Array> {
+    let floats = |f: fn(&SubmapInfo) -> f64| -> Box<dyn Array> {
+        Float64Array::from_vec(infos.iter().map(f).collect()).boxed()
+    };
+
+    let values = vec![
+        Int32Array::from_vec(infos.iter().map(|i| i.num_range_data).collect()).boxed(),
+        BooleanArray::from_slice(infos.iter().map(|i| i.finished).collect::<Vec<_>>()).boxed(),
+        floats(|i| i.resolution),
+        floats(|i| i.local_pose.translation.x),
+        floats(|i| i.local_pose.translation.y),
+        floats(|i| i.local_pose.translation.z),
+        floats(|i| i.local_pose.rotation.x),
+        floats(|i| i.local_pose.rotation.y),
+        floats(|i| i.local_pose.rotation.z),
+        floats(|i| i.local_pose.rotation.w),
+    ];
+    StructArray::new(data_type(), values, None).boxed()
+}
+
+pub fn from_arrow(array: &dyn Array) -> Option<Vec<SubmapInfo>> {
+    let array = array.as_any().downcast_ref::<StructArray>()?;
+
+    let num_range_data: &Int32Array = history::struct_field(array, "num_range_data")?;
+    let finished: &BooleanArray = history::struct_field(array, "finished")?;
+    let floats = |name| history::struct_field::<Float64Array>(array, name);
+    let resolution = floats("resolution")?;
+    let (tx, ty, tz) = (floats("tx")?, floats("ty")?, floats("tz")?);
+    let (qx, qy, qz, qw) = (floats("qx")?, floats("qy")?, floats("qz")?, floats("qw")?);
+
+    Some(
+        (0..array.len())
+            .map(|i| SubmapInfo {
+                num_range_data: num_range_data.value(i),
+                finished: finished.value(i),
+                resolution: resolution.value(i),
+                local_pose: Pose {
+                    translation: glam::dvec3(tx.value(i), ty.value(i), tz.value(i)),
+                    rotation: glam::DQuat::from_xyzw(
+                        qx.value(i),
+                        qy.value(i),
+                        qz.value(i),
+                        qw.value(i),
+                    ),
+                },
+            })
+            .collect(),
+    )
+}
+
+/// A batch of submap infos, for logging with the SDK.
+pub struct SubmapInfoBatch<'a>(pub &'a [SubmapInfo]);
+
+impl re_types::LoggableBatch for SubmapInfoBatch<'_> {
+    type Name = re_types::ComponentName;
+
+    fn name(&self) -> Self::Name {
+        COMPONENT_NAME.into()
+    }
+
+    fn num_instances(&self) -> usize {
+        self.0.len()
+    }
+
+    fn arrow_field(&self) -> arrow2::datatypes::Field {
+        Field::new(COMPONENT_NAME, data_type(), false)
+    }
+
+    fn to_arrow(&self) -> re_types::SerializationResult<Box<dyn Array>> {
+        Ok(to_arrow(self.0))
+    }
+}
+
+impl re_types::ComponentBatch for SubmapInfoBatch<'_> {}
+
+/// A submap entity found in the log database.
+pub struct LoggedSubmap {
+    pub id: TrajectoryIndex,
+    pub entity_path: re_log_types::EntityPath,
+
+    /// `None` if the submap was logged without metadata.
+    pub info: Option<SubmapInfo>,
+}
+
+/// All entities at `submaps/{trajectory_id}/{submap_index}`, ordered by id.
+pub fn all_submaps(
+    entity_db: &re_entity_db::EntityDb,
+    query: &re_data_store::LatestAtQuery,
+) -> Vec<LoggedSubmap> {
+    let component_name = re_types::ComponentName::from(COMPONENT_NAME);
+    let Some(root) = entity_db
+        .tree()
+        .subtree(&re_log_types::EntityPath::from(ROOT))
+    else {
+        return Vec::new();
+    };
+
+    let mut submaps = Vec::new();
+    for trajectory in root.children.values() {
+        let Some(trajectory_id) = last_part_as_i32(&trajectory.path) else {
+            continue;
+        };
+        for submap in trajectory.children.values() {
+            let Some(index) = last_part_as_i32(&submap.path) else {
+                continue;
+            };
+            let info = history::latest_at(entity_db, query, &submap.path, component_name)
+                .and_then(|data| from_arrow(&*data))
+                .and_then(|infos| infos.into_iter().next());
+            submaps.push(LoggedSubmap {
+                id: TrajectoryIndex {
+                    trajectory_id,
+                    index,
+                },
+                entity_path: submap.path.clone(),
+                info,
+            });
+        }
+    }
+    submaps.sort_by_key(|submap| submap.id);
+    submaps
+}
+
+fn last_part_as_i32(entity_path: &re_log_types::EntityPath) -> Option<i32> {
+    entity_path.last()?.unescaped_str().parse().ok()
+}
+
+/// Which submaps are shown in the main views.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct SubmapVisibility {
+    pub hidden: BTreeSet<re_log_types::EntityPath>,
+
+    /// Show only this submap, regardless of [`Self::hidden`].
+    pub solo: Option<re_log_types::EntityPath>,
+}
+
+impl SubmapVisibility {
+    /// Rules for a space view's entity path filter, e.g. `- /submaps/0/3/**`.
+    pub fn query_expressions(&self) -> Vec<String> {
+        if let Some(solo) = &self.solo {
+            vec![format!("- /{ROOT}/**"), format!("+ {solo}/**")]
+        } else {
+            self.hidden
+                .iter()
+                .map(|entity_path| format!("- {entity_path}/**"))
+                .collect()
+        }
+    }
+}
+
+/// List the submaps with their metadata and visibility.
+///
+/// Returns `true` if the visibility changed.
+pub fn submap_browser_ui(
+    ui: &mut egui::Ui,
+    entity_db: &re_entity_db::EntityDb,
+    query: &re_data_store::LatestAtQuery,
+    visibility: &mut SubmapVisibility,
+) -> bool {
+    let submaps = all_submaps(entity_db, query);
+    if submaps.is_empty() {
+        ui.label(format!("No submaps logged under /{ROOT}."));
+        return false;
+    }
+
+    let before = visibility.clone();
+    ui.horizontal(|ui| {
+        if ui.button("Show all").clicked() {
+            *visibility = SubmapVisibility::default();
+        }
+        if ui.button("Hide unfinished").clicked() {
+            visibility.solo = None;
+            visibility.hidden = submaps
+                .iter()
+                .filter(|submap| submap.info.is_some_and(|info| !info.finished))
+                .map(|submap| submap.entity_path.clone())
+                .collect();
+        }
+    });
+
+    let row_height = ui.text_style_height(&egui::TextStyle::Body);
+    egui_extras::TableBuilder::new(ui)
+        .striped(true)
+        .resizable(true)
+        .max_scroll_height(300.0)
+        .columns(egui_extras::Column::auto(), 6)
+        .column(egui_extras::Column::remainder())
+        .header(row_height, |mut header| {
+            for name in [
+                "Visible",
+                "Submap",
+                "Range data",
+                "Finished",
+                "Resolution",
+                "Local pose",
+                "",
+            ] {
+                header.col(|ui| {
+                    ui.strong(name);
+                });
+            }
+        })
+        .body(|body| {
+            body.rows(row_height, submaps.len(), |mut row| {
+                let submap = &submaps[row.index()];
+                let entity_path = &submap.entity_path;
+
+                row.col(|ui| {
+                    let mut visible = !visibility.hidden.contains(entity_path);
+                    let enabled = visibility.solo.is_none();
+                    if ui
+                        .add_enabled(enabled, egui::Checkbox::without_text(&mut visible))
+                        .changed()
+                    {
+                        if visible {
+                            visibility.hidden.remove(entity_path);
+                        } else {
+                            visibility.hidden.insert(entity_path.clone());
+                        }
+                    }
+                });
+                row.col(|ui| {
+                    ui.label(format!("{}/{}", submap.id.trajectory_id, submap.id.index))
+                        .on_hover_text(entity_path.to_string());
+                });
+
+                let info_cells = submap.info.map_or_else(
+                    || {
+                        [
+                            "–".to_owned(),
+                            "–".to_owned(),
+                            "–".to_owned(),
+                            "–".to_owned(),
+                        ]
+                    },
+                    |info| {
+                        let t = info.local_pose.translation;
+                        let (yaw, _, _) = info.local_pose.rotation.to_euler(glam::EulerRot::ZYX);
+                        [
+                            info.num_range_data.to_string(),
+                            if info.finished { "yes" } else { "no" }.to_owned(),
+                            if info.resolution.is_finite() {
+                                format!("{:.3} m", info.resolution)
+                            } else {
+                                "–".to_owned()
+                            },
+                            format!(
+                                "{:.2}, {:.2}, {:.2}, {:.1}°",
+                                t.x,
+                                t.y,
+                                t.z,
+                                yaw.to_degrees()
+                            ),
+                        ]
+                    },
+                );
+                for cell in info_cells {
+                    row.col(|ui| {
+                        ui.label(cell);
+                    });
+                }
+
+                row.col(|ui| {
+                    let is_solo = visibility.solo.as_ref() == Some(entity_path);
+                    if ui
+                        .selectable_label(is_solo, "Solo")
+                        .on_hover_text("Show only this submap")
+                        .clicked()
+                    {
+                        visibility.solo = (!is_solo).then(|| entity_path.clone());
+                    }
+                });
+            });
+        });
+
+    *visibility != before
+}
@@ -0,0 +1,67 @@
+use re_viewer::external::{egui, re_data_store, re_entity_db, re_log_types};
+
+use crate::viewer_state;
+
+/// The point in time the Cartographer panel shows data for.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct TimeCursor {
+    pub timeline: re_log_types::Timeline,
+
+    /// `None` means the latest data on the timeline.
+    pub time: Option<re_log_types::TimeInt>,
+
+    /// Is the viewer currently playing back or following incoming data?
+    pub playing: bool,
+}
+
+impl TimeCursor {
+    /// The latest data on the given timeline.
+    pub fn latest(timeline: re_log_types::Timeline) -> Self {
+        Self {
+            timeline,
+            time: None,
+            playing: false,
+        }
+    }
+
+    /// Read the time control of the viewer for the given recording.
+    ///
+    /// `None` when the viewer doesn't show a grid view, see [`crate::viewer_state`].
+    pub fn from_viewer(
+        egui_ctx: &egui::Context,
+        entity_db: &re_entity_db::EntityDb,
+    ) -> Option<Self> {
+        let state = viewer_state::get(egui_ctx, entity_db.store_id())?;
+        Some(Self {
+            timeline: state.timeline,
+            time: state.time,
+            playing: state.playing,
+        })
+    }
+
+    pub fn latest_at_query(&self) -> re_data_store::LatestAtQuery {
+        match self.time {
+            Some(time) => re_data_store::LatestAtQuery::new(self.timeline, time),
+            None => re_data_store::LatestAtQuery::latest(self.timeline),
+        }
+    }
+
+    /// Human-readable description of the current time, e.g. for labels.
+    pub fn format_time(&self) -> String {
+        match self.time {
+            Some(time) => self
+                .timeline
+                .typ()
+                .format(time, re_log_types::TimeZone::Utc),
+            None => "latest".to_owned(),
+        }
+    }
+}
+
+/// A time as a number: seconds for time timelines, the plain value for sequence timelines.
+pub fn time_as_f64(timeline: re_log_types::Timeline, time: re_log_types::TimeInt) -> f64 {
+    match timeline.typ() {
+        re_log_types::TimeType::Time => time.as_i64() as f64 * 1e-9,
+        re_log_types::TimeType::Sequence => time.as_i64() as f64,
+    }
+}
@@ -0,0 +1,161 @@
+use re_viewer::external::{
+    re_entity_db, re_log_types,
+    re_types::{self, datatypes, Loggable as _},
+};
+
+use crate::history;
+
+/// A rigid transform, in double precision.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Pose {
+    pub translation: glam::DVec3,
+    pub rotation: glam::DQuat,
+}
+
+impl Pose {
+    pub fn inverse(&self) -> Self {
+        let rotation = self.rotation.inverse();
+        Self {
+            translation: -(rotation * self.translation),
+            rotation,
+        }
+    }
+
+    /// Row-major 3x4 matrix `[R | t]`.
+    pub fn to_3x4(self) -> [[f64; 4]; 3] {
+        let r = glam::DMat3::from_quat(self.rotation);
+        let t = self.translation;
+        [
+            [r.x_axis.x, r.y_axis.x, r.z_axis.x, t.x],
+            [r.x_axis.y, r.y_axis.y, r.z_axis.y, t.y],
+            [r.x_axis.z, r.y_axis.z, r.z_axis.z, t.z],
+        ]
+    }
+
+    /// Read a logged `Transform3D`, ignoring its scale.
+    ///
+    /// Converted to `f64` first, so that inverting it loses no precision.
+    pub fn from_transform(transform: &datatypes::Transform3D) -> Self {
+        let vec3 = |v: &datatypes::Vec3D| glam::Vec3::from(v.0).as_dvec3();
+        let (translation, rotation, from_parent) = match transform {
+            datatypes::Transform3D::TranslationAndMat3x3(t) => {
+                let rotation = t.mat3x3.map_or(glam::DQuat::IDENTITY, |mat3x3| {
+                    let mat3 = glam::Mat3::from_cols_array(&mat3x3.0).as_dmat3();
+                    let (_scale, rotation, _) =
+                        glam::DAffine3::from_mat3(mat3).to_scale_rotation_translation();
+                    rotation
+                });
+                (t.translation.as_ref(), rotation, t.from_parent)
+            }
+            datatypes::Transform3D::TranslationRotationScale(t) => {
+                let rotation = match &t.rotation {
+                    None => glam::DQuat::IDENTITY,
+                    Some(datatypes::Rotation3D::Quaternion(quat)) => {
+                        glam::Quat::from_array(quat.0).as_dquat()
+                    }
+                    Some(datatypes::Rotation3D::AxisAngle(axis_angle)) => {
+                        glam::DQuat::from_axis_angle(
+                            vec3(&axis_angle.axis).normalize_or_zero(),
+                            f64::from(axis_angle.angle.radians()),
+                        )
+                    }
+                };
+                (t.translation.as_ref(), rotation, t.from_parent)
+            }
+        };
+        let pose = Self {
+            translation: translation.map_or(glam::DVec3::ZERO, vec3),
+            rotation: rotation.normalize(),
+        };
+        if from_parent {
+            pose.inverse()
+        } else {
+            pose
+        }
+    }
+}
+
+impl std::ops::Mul for Pose {
+    type Output = Self;
+
+    fn mul(self, rhs: Self) -> Self {
+        Self {
+            translation: self.translation + self.rotation * rhs.translation,
+            rotation: self.rotation * rhs.rotation,
+        }
+    }
+}
+
+/// A pose logged at some time.
+#[derive(Clone, Copy, Debug)]
+pub struct TimedPose {
+    pub time: re_log_types::TimeInt,
+    pub pose: Pose,
+}
+
+pub fn transform_component_name() -> re_types::ComponentName {
+    re_types::components::Transform3D::name()
+}
+
+/// Every `Transform3D` logged to the entity on the given timeline, in time order.
+pub fn pose_history(
+    entity_db: &re_entity_db::EntityDb,
+    timeline: re_log_types::Timeline,
+    entity_path: &re_log_types::EntityPath,
+) -> Vec<TimedPose> {
+    history::component_history(entity_db, timeline, entity_path, transform_component_name())
+        .iter()
+        .filter_map(|sample| {
+            let transform = re_types::components::Transform3D::from_arrow(&*sample.data)
+                .ok()?
+                .into_iter()
+                .next()?;
+            Some(TimedPose {
+                time: sample.time,
+                pose: Pose::from_transform(&transform.0),
+            })
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn from_transform() {
+        let quarter_turn = glam::DQuat::from_rotation_z(std::f64::consts::FRAC_PI_2);
+        let child_from_parent = datatypes::Transform3D::TranslationRotationScale(
+            datatypes::TranslationRotationScale3D {
+                translation: Some(datatypes::Vec3D([100_000.5, 2.0, 0.0])),
+                rotation: Some(datatypes::Rotation3D::Quaternion(datatypes::Quaternion(
+                    quarter_turn.as_quat().to_array(),
+                ))),
+                scale: None,
+                from_parent: true,
+            },
+        );
+        // Inverted in f64, so the half meter survives:
+        let pose = Pose::from_transform(&child_from_parent);
+        assert!(pose.rotation.angle_between(quarter_turn.inverse()) < 1e-6);
+        assert!(
+            pose.translation.distance(glam::dvec3(-2.0, 100_000.5, 0.0)) < 1e-6,
+            "{pose:?}"
+        );
+
+        // A scaled rotation matrix, column-major:
+        let scaled =
+            datatypes::Transform3D::TranslationAndMat3x3(datatypes::TranslationAndMat3x3 {
+                translation: None,
+                mat3x3: Some(datatypes::Mat3x3([
+                    0.0, 2.0, 0.0, //
+                    -2.0, 0.0, 0.0, //
+                    0.0, 0.0, 2.0,
+                ])),
+                from_parent: false,
+            });
+        let pose = Pose::from_transform(&scaled);
+        assert!(pose.rotation.angle_between(quarter_turn) < 1e-6);
+        assert_eq!(pose.translation, glam::DVec3::ZERO);
+    }
+}
@@ -0,0 +1,391 @@
+//! Save the history of a `Transform3D` entity for trajectory evaluation tools.
+
+use std::{collections::BTreeMap, io::Write as _, path::Path};
+
+use re_viewer::external::{egui, re_data_store, re_entity_db, re_log, re_log_types};
+
+use crate::{
+    constraints, time_cursor,
+    trajectory::{self, Pose, TimedPose},
+};
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum TrajectoryFormat {
+    /// `timestamp tx ty tz qx qy qz qw` per line.
+    Tum,
+
+    /// Row-major 3x4 `[R | t]` matrix per line.
+    Kitti,
+
+    /// Pose graph with `VERTEX_SE3:QUAT` and `EDGE_SE3:QUAT` lines.
+    G2o,
+}
+
+impl TrajectoryFormat {
+    pub const ALL: [Self; 3] = [Self::Tum, Self::Kitti, Self::G2o];
+
+    pub fn name(self) -> &'static str {
+        match self {
+            Self::Tum => "TUM",
+            Self::Kitti => "KITTI",
+            Self::G2o => "g2o",
+        }
+    }
+
+    pub fn extension(self) -> &'static str {
+        match self {
+            Self::Tum | Self::Kitti => "txt",
+            Self::G2o => "g2o",
+        }
+    }
+}
+
+/// Buttons for exporting the trajectory of the entity on the timeline.
+pub fn export_ui(
+    ui: &mut egui::Ui,
+    entity_db: &re_entity_db::EntityDb,
+    query: &re_data_store::LatestAtQuery,
+    entity_path: &re_log_types::EntityPath,
+) {
+    let timeline = query.timeline();
+    ui.horizontal(|ui| {
+        ui.label("Export trajectory:");
+        for format in TrajectoryFormat::ALL {
+            if !ui.small_button(format.name()).clicked() {
+                continue;
+            }
+
+            let Some(path) = rfd::FileDialog::new()
+                .set_file_name(format!("trajectory.{}", format.extension()))
+                .save_file()
+            else {
+                continue;
+            };
+
+            let poses = trajectory::pose_history(entity_db, timeline, entity_path);
+            let result = match format {
+                TrajectoryFormat::Tum => write_tum(&path, timeline, &poses),
+                TrajectoryFormat::Kitti => write_kitti(&path, &poses),
+                TrajectoryFormat::G2o => write_g2o(
+                    &path,
+                    timeline,
+                    entity_path,
+                    &poses,
+                    &constraints::all_constraints(entity_db, query),
+                ),
+            };
+            match result {
+                Ok(()) => re_log::info!("Saved {} poses to {}", poses.len(), path.display()),
+                Err(err) => re_log::error!("Failed to save {}: {err}", path.display()),
+            }
+        }
+    });
+}
+
+pub fn write_tum(
+    path: &Path,
+    timeline: re_log_types::Timeline,
+    poses: &[TimedPose],
+) -> std::io::Result<()> {
+    let mut out = std::io::BufWriter::new(std::fs::File::create(path)?);
+    for TimedPose { time, pose } in poses {
+        let (t, q) = (pose.translation, pose.rotation);
+        writeln!(
+            out,
+            "{:.9} {} {} {} {} {} {} {}",
+            time_cursor::time_as_f64(timeline, *time),
+            t.x,
+            t.y,
+            t.z,
+            q.x,
+            q.y,
+            q.z,
+            q.w
+        )?;
+    }
+    out.flush()
+}
+
+pub fn write_kitti(path: &Path, poses: &[TimedPose]) -> std::io::Result<()> {
+    let mut out = std::io::BufWriter::new(std::fs::File::create(path)?);
+    for TimedPose { pose, .. } in poses {
+        let values: Vec<String> = pose
+            .to_3x4()
+            .iter()
+            .flatten()
+            .map(|v| format!("{v:e}"))
+            .collect();
+        writeln!(out, "{}", values.join(" "))?;
+    }
+    out.flush()
+}
+
+/// Write the poses of `entity_path` as a chain of vertices, plus the pose graph constraints.
+///
+/// On sequence timelines (e.g. `node_index`) the vertex id is the time. Constraints whose
+/// node pose was logged to `entity_path`, with the node index as its time on `timeline`
+/// (see [`constraints::PosePaths`]), are added as edges.
+/// Otherwise vertices are numbered in order, and constraints are left out since they
+/// cannot be matched to poses. Submaps get the vertex ids after the largest node id.
+pub fn write_g2o(
+    path: &Path,
+    timeline: re_log_types::Timeline,
+    entity_path: &re_log_types::EntityPath,
+    poses: &[TimedPose],
+    constraints: &[constraints::LoggedConstraint],
+) -> std::io::Result<()> {
+    let is_sequence = timeline.typ() == re_log_types::TimeType::Sequence;
+
+    let nodes: BTreeMap<i64, Pose> = poses
+        .iter()
+        .enumerate()
+        .map(|(i, TimedPose { time, pose })| {
+            let id = if is_sequence { time.as_i64() } else { i as i64 };
+            (id, *pose)
+        })
+        .collect();
+    let mut vertices = nodes.clone();
+
+    let first_submap_id = nodes.keys().next_back().map_or(0, |max_id| max_id + 1);
+    let mut submap_ids: BTreeMap<constraints::TrajectoryIndex, i64> = BTreeMap::new();
+
+    let mut edges = Vec::new();
+    if is_sequence {
+        for logged in constraints {
+            let constraint = &logged.constraint;
+            let is_node = constraint.poses.as_ref().is_some_and(|poses| {
+                &poses.node_entity_path == entity_path
+                    && poses.node_timeline == timeline.name().as_str()
+            });
+            if !is_node {
+                continue;
+            }
+            let node_id = i64::from(constraint.node.index);
+            let Some(node_pose) = nodes.get(&node_id).copied() else {
+                continue;
+            };
+            let next_submap_id = first_submap_id + submap_ids.len() as i64;
+            let submap_id = *submap_ids
+                .entry(constraint.submap)
+                .or_insert(next_submap_id);
+            // The submap pose follows from the node pose and the relative pose:
+            vertices
+                .entry(submap_id)
+                .or_insert_with(|| node_pose * constraint.relative_pose.inverse());
+            edges.push((submap_id, node_id, constraint));
+        }
+    }
+
+    let mut out = std::io::BufWriter::new(std::fs::File::create(path)?);
+    for (id, pose) in &vertices {
+        let (t, q) = (pose.translation, pose.rotation);
+        writeln!(
+            out,
+            "VERTEX_SE3:QUAT {id} {} {} {} {} {} {} {}",
+            t.x, t.y, t.z, q.x, q.y, q.z, q.w
+        )?;
+    }
+    if let Some(first) = vertices.keys().next() {
+        writeln!(out, "FIX {first}")?;
+    }
+
+    for (from, to, constraint) in edges {
+        let (t, q) = (
+            constraint.relative_pose.translation,
+            constraint.relative_pose.rotation,
+        );
+        // Upper triangle of a diagonal 6x6 information matrix:
+        let diagonal = [
+            constraint.translation_weight,
+            constraint.translation_weight,
+            constraint.translation_weight,
+            constraint.rotation_weight,
+            constraint.rotation_weight,
+            constraint.rotation_weight,
+        ];
+        let mut information = Vec::with_capacity(21);
+        for (row, weight) in diagonal.into_iter().enumerate() {
+            information.push(weight);
+            information.extend(std::iter::repeat(0.0).take(5 - row));
+        }
+        let information: Vec<String> = information.iter().map(|v| v.to_string()).collect();
+        writeln!(
+            out,
+            "EDGE_SE3:QUAT {from} {to} {} {} {} {} {} {} {} {}",
+            t.x,
+            t.y,
+            t.z,
+            q.x,
+            q.y,
+            q.z,
+            q.w,
+            information.join(" ")
+        )?;
+    }
+
+    re_log::debug!("g2o: {} nodes, {} submaps", nodes.len(), submap_ids.len());
+    out.flush()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use constraints::{Constraint, ConstraintKind, LoggedConstraint, PosePaths, TrajectoryIndex};
+
+    fn pose(x: f64, y: f64, yaw: f64) -> Pose {
+        Pose {
+            translation: glam::DVec3::new(x, y, 0.0),
+            rotation: glam::DQuat::from_rotation_z(yaw),
+        }
+    }
+
+    fn timed(time: i64, pose: Pose) -> TimedPose {
+        TimedPose {
+            time: re_log_types::TimeInt::new_temporal(time),
+            pose,
+        }
+    }
+
+    fn numbers(line: &str) -> Vec<f64> {
+        line.split(' ').map(|v| v.parse().unwrap()).collect()
+    }
+
+    fn assert_close(actual: &[f64], expected: &[f64]) {
+        assert_eq!(actual.len(), expected.len(), "{actual:?} vs {expected:?}");
+        for (a, e) in actual.iter().zip(expected) {
+            assert!((a - e).abs() < 1e-9, "{actual:?} vs {expected:?}");
+        }
+    }
+
+    fn write(write: impl FnOnce(&Path) -> std::io::Result<()>) -> Vec<String> {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("trajectory");
+        write(&path).unwrap();
+        std::fs::read_to_string(&path)
+            .unwrap()
+            .lines()
+            .map(str::to_owned)
+            .collect()
+    }
+
+    #[test]
+    fn tum() {
+        let timeline = re_log_types::Timeline::new_temporal("sensor_time");
+        let poses = [
+            timed(1_500_000_000, pose(1.0, 2.0, 0.0)),
+            timed(2_000_000_001, pose(-1.0, 0.5, std::f64::consts::FRAC_PI_2)),
+        ];
+        let lines = write(|path| write_tum(path, timeline, &poses));
+
+        assert_eq!(lines[0], "1.500000000 1 2 0 0 0 0 1");
+        assert!(lines[1].starts_with("2.000000001 -1 0.5 0 "));
+        let half = std::f64::consts::FRAC_1_SQRT_2;
+        assert_close(
+            &numbers(&lines[1]),
+            &[2.000000001, -1.0, 0.5, 0.0, 0.0, 0.0, half, half],
+        );
+        assert_eq!(lines.len(), 2);
+    }
+
+    #[test]
+    fn kitti() {
+        let poses = [
+            timed(0, pose(1.0, 2.0, 0.0)),
+            timed(1, pose(3.0, 4.0, std::f64::consts::FRAC_PI_2)),
+        ];
+        let lines = write(|path| write_kitti(path, &poses));
+
+        assert_eq!(lines[0], "1e0 0e0 0e0 1e0 0e0 1e0 0e0 2e0 0e0 0e0 1e0 0e0");
+        // Rotated a quarter turn to the left: the x axis points along y.
+        #[rustfmt::skip]
+        let expected = [
+            0.0, -1.0, 0.0, 3.0,
+            1.0, 0.0, 0.0, 4.0,
+            0.0, 0.0, 1.0, 0.0,
+        ];
+        assert_close(&numbers(&lines[1]), &expected);
+        assert_eq!(lines.len(), 2);
+    }
+
+    fn constraint(node_entity_path: Option<&str>, node_index: i32) -> LoggedConstraint {
+        LoggedConstraint {
+            entity_path: "constraints/inter_submap".into(),
+            instance: 0,
+            constraint: Constraint {
+                submap: TrajectoryIndex {
+                    trajectory_id: 0,
+                    index: 0,
+                },
+                node: TrajectoryIndex {
+                    trajectory_id: 0,
+                    index: node_index,
+                },
+                kind: ConstraintKind::InterSubmap,
+                relative_pose: pose(1.0, 0.0, 0.0),
+                translation_weight: 10.0,
+                rotation_weight: 2.0,
+                poses: node_entity_path.map(|node_entity_path| PosePaths {
+                    node_entity_path: node_entity_path.into(),
+                    node_timeline: "node_index".to_owned(),
+                    submap_entity_path: "submaps/0/0".into(),
+                }),
+            },
+        }
+    }
+
+    #[test]
+    fn g2o() {
+        let timeline = re_log_types::Timeline::new_sequence("node_index");
+        let entity_path = re_log_types::EntityPath::from("trajectories/0/pose");
+        let poses = [
+            timed(4, pose(0.0, 0.0, 0.0)),
+            timed(5, pose(1.0, 0.0, 0.0)),
+            timed(6, pose(3.0, 0.0, 0.0)),
+        ];
+        let constraints = [
+            constraint(Some("trajectories/0/pose"), 6),
+            // Nodes of another entity, or not known to be logged anywhere:
+            constraint(Some("trajectories/1/pose"), 5),
+            constraint(None, 5),
+            // Nodes without a pose:
+            constraint(Some("trajectories/0/pose"), 7),
+        ];
+        let lines = write(|path| write_g2o(path, timeline, &entity_path, &poses, &constraints));
+
+        assert_eq!(
+            lines,
+            [
+                "VERTEX_SE3:QUAT 4 0 0 0 0 0 0 1",
+                "VERTEX_SE3:QUAT 5 1 0 0 0 0 0 1",
+                "VERTEX_SE3:QUAT 6 3 0 0 0 0 0 1",
+                // The submap, placed by the node pose and the constraint:
+                "VERTEX_SE3:QUAT 7 2 0 0 0 0 0 1",
+                "FIX 4",
+                "EDGE_SE3:QUAT 7 6 1 0 0 0 0 0 1 \
+                 10 0 0 0 0 0 10 0 0 0 0 10 0 0 0 2 0 0 2 0 2",
+            ]
+        );
+    }
+
+    #[test]
+    fn g2o_without_node_indices() {
+        // On other timelines the vertices are just numbered, so constraints can't be matched:
+        let timeline = re_log_types::Timeline::new_temporal("sensor_time");
+        let entity_path = re_log_types::EntityPath::from("trajectories/0/pose");
+        let poses = [
+            timed(1_000, pose(0.0, 0.0, 0.0)),
+            timed(2_000, pose(1.0, 0.0, 0.0)),
+        ];
+        let constraints = [constraint(Some("trajectories/0/pose"), 1)];
+        let lines = write(|path| write_g2o(path, timeline, &entity_path, &poses, &constraints));
+
+        assert_eq!(
+            lines,
+            [
+                "VERTEX_SE3:QUAT 0 0 0 0 0 0 0 1",
+                "VERTEX_SE3:QUAT 1 1 0 0 0 0 0 1",
+                "FIX 0",
+            ]
+        );
+    }
+}
@@ -0,0 +1,111 @@
+//! State of the Rerun viewer that `re_viewer::App` keeps to itself.
+//!
+//! Only space views get to see the time control and the active blueprint, through the
+//! [`re_viewer_context::ViewerContext`] they are handed at the start of every frame.
+//! The occupancy grid view copies what the panel needs from there, and applies the time
+//! changes asked for by the panel. So this only works while the viewport has a grid view,
+//! which the Cartographer blueprint does.
+
+use std::sync::Mutex;
+
+use re_viewer::external::{re_log_types, re_viewer_context};
+
+/// What the viewer showed in the last frame.
+#[derive(Clone, Debug)]
+pub struct ViewerState {
+    /// The `egui` frame this was read in.
+    frame_nr: u64,
+
+    pub recording_id: re_log_types::StoreId,
+
+    /// The layout the viewer shows.
+    pub blueprint_id: re_log_types::StoreId,
+    pub timeline: re_log_types::Timeline,
+    pub time: Option<re_log_types::TimeInt>,
+    pub playing: bool,
+}
+
+struct Shared {
+    state: Option<ViewerState>,
+
+    /// Applied to the time control of the given recording in the next frame.
+    pending_time: Option<(
+        re_log_types::StoreId,
+        re_log_types::Timeline,
+        Option<re_log_types::TimeInt>,
+    )>,
+}
+
+static SHARED: Mutex<Shared> = Mutex::new(Shared {
+    state: None,
+    pending_time: None,
+});
+
+fn shared() -> std::sync::MutexGuard<'static, Shared> {
+    SHARED
+        .lock()
+        .unwrap_or_else(std::sync::PoisonError::into_inner)
+}
+
+/// Called by the grid view for every frame.
+pub fn on_frame_start(ctx: &re_viewer_context::ViewerContext<'_>) {
+    let mut shared = shared();
+    let recording_id = ctx.recording_id();
+
+    if shared
+        .pending_time
+        .as_ref()
+        .is_some_and(|(store_id, ..)| store_id == recording_id)
+    {
+        if let Some((_, timeline, time)) = shared.pending_time.take() {
+            let mut time_ctrl = ctx.rec_cfg.time_ctrl.write();
+            time_ctrl.set_timeline(timeline);
+            if let Some(time) = time {
+                time_ctrl.pause();
+                time_ctrl.set_time(time);
+            }
+        }
+    }
+
+    let frame_nr = ctx.egui_ctx.frame_nr();
+    if shared
+        .state
+        .as_ref()
+        .is_some_and(|state| state.frame_nr == frame_nr)
+    {
+        // Already read by another grid view:
+        return;
+    }
+
+    let time_ctrl = ctx.rec_cfg.time_ctrl.read();
+    shared.state = Some(ViewerState {
+        frame_nr,
+        recording_id: recording_id.clone(),
+        blueprint_id: ctx.store_context.blueprint.store_id().clone(),
+        timeline: *time_ctrl.timeline(),
+        time: time_ctrl.time_int(),
+        playing: time_ctrl.play_state() != re_viewer_context::PlayState::Paused,
+    });
+}
+
+/// The viewer state of the last frame, if it was for the given recording.
+///
+/// `None` when the viewport has no grid view to read it.
+pub fn get(
+    egui_ctx: &re_viewer::external::egui::Context,
+    recording_id: &re_log_types::StoreId,
+) -> Option<ViewerState> {
+    let state = shared().state.clone()?;
+    // The panel is shown before the viewport, so the last update is from the frame before:
+    let is_recent = egui_ctx.frame_nr() <= state.frame_nr + 1;
+    (is_recent && &state.recording_id == recording_id).then_some(state)
+}
+
+/// Move the time cursor of the viewer in the next frame.
+pub fn set_time(
+    recording_id: re_log_types::StoreId,
+    timeline: re_log_types::Timeline,
+    time: Option<re_log_types::TimeInt>,
+) {
+    shared().pending_time = Some((recording_id, timeline, time));
+}